@@ -0,0 +1,41 @@
+//! Compares reading every preset of a bank with reading only the first, as
+//! a patch browser listing many files would. Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use synthahol_dx7::Bank;
+
+/// The number of banks read by each measurement, like a large directory.
+const BANK_COUNT: usize = 10_000;
+
+fn measure(name: &str, mut read: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..BANK_COUNT {
+        read();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<24} {:>10.2?} total {:>10.2?} per bank",
+        elapsed,
+        elapsed / BANK_COUNT as u32
+    );
+    elapsed
+}
+
+fn main() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/rom1a.syx");
+    let contents = std::fs::read(path).unwrap();
+
+    let all = measure("Bank::load", || {
+        black_box(Bank::load(&mut black_box(contents.as_slice()), None).unwrap());
+    });
+    let first = measure("Bank::presets take(1)", || {
+        let mut presets = Bank::presets(&mut black_box(contents.as_slice())).unwrap();
+        black_box(presets.next().unwrap().unwrap());
+    });
+    println!(
+        "Reading only the first preset is {:.1} times faster",
+        all.as_secs_f64() / first.as_secs_f64()
+    );
+}
@@ -1,6 +1,7 @@
 //! Routing between operators.
 
 use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
 
 use lazy_static::lazy_static;
 
@@ -9,11 +10,17 @@ use crate::{OperatorId, Preset};
 
 /// The destination of an operator
 ///
-/// An operator ID can be converted to an output:
+/// Outputs convert to and from their number. Operator IDs are the numbers
+/// of the operators and the amplifier is 6:
 ///
 /// ```
-/// use synthahol_dx7::Output;
-/// assert_eq!(Output::from(2), Some(Output::Op3));
+/// use synthahol_dx7::{AlgorithmId, Algorithms, Output};
+/// assert_eq!(Ok(Output::Op3), Output::try_from(2));
+/// assert_eq!(Some(2), Output::Op3.operator_id());
+///
+/// let algorithm = Algorithms::get(AlgorithmId::from_stored(0)).unwrap();
+/// let indices: Vec<u8> = algorithm.routing(4).unwrap().iter().copied().map(u8::from).collect();
+/// assert_eq!(vec![3], indices);
 /// ```
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 #[repr(u8)]
@@ -31,21 +38,48 @@ pub enum Output {
 }
 
 impl Output {
+    /// Every output, in order of their numbers.
+    pub const ALL: [Output; 7] = [
+        Output::Op1,
+        Output::Op2,
+        Output::Op3,
+        Output::Op4,
+        Output::Op5,
+        Output::Op6,
+        Output::Amplifier,
+    ];
+
     pub fn is_operator(&self) -> bool {
         self != &Output::Amplifier
     }
 
+    /// The ID of the operator, or `None` for the amplifier.
+    pub fn operator_id(&self) -> Option<OperatorId> {
+        self.is_operator().then_some(*self as OperatorId)
+    }
+
+    #[deprecated(note = "Use Output::try_from, which also converts the amplifier")]
     pub fn from(operator_id: OperatorId) -> Option<Output> {
-        use Output::*;
-        match operator_id {
-            0 => Some(Op1),
-            1 => Some(Op2),
-            2 => Some(Op3),
-            3 => Some(Op4),
-            4 => Some(Op5),
-            5 => Some(Op6),
-            _ => None,
-        }
+        Output::try_from(operator_id)
+            .ok()
+            .filter(Output::is_operator)
+    }
+}
+
+impl TryFrom<u8> for Output {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Output::ALL
+            .get(value as usize)
+            .copied()
+            .ok_or("Unknown output")
+    }
+}
+
+impl From<Output> for u8 {
+    fn from(output: Output) -> Self {
+        output as u8
     }
 }
 
@@ -65,9 +99,113 @@ impl Display for Output {
     }
 }
 
-pub type AlgorithmId = usize;
+/// Identifies one of the 32 algorithms. Algorithms are stored from 0 to 31
+/// but numbered from 1 to 32 on the DX7 and in its manuals, which is how
+/// they are displayed.
+///
+/// ```
+/// use synthahol_dx7::AlgorithmId;
+/// let algorithm_id = AlgorithmId::from_display(22).unwrap();
+/// assert_eq!(21, algorithm_id.stored());
+/// assert_eq!("Algorithm 22", algorithm_id.to_string());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct AlgorithmId(u8);
+
+impl AlgorithmId {
+    /// Create from the number stored in a preset, from 0 to 31. Numbers
+    /// out of range are kept so they can be reported when the preset is
+    /// checked.
+    pub const fn from_stored(stored: u8) -> Self {
+        Self(stored)
+    }
 
-/// Routing between the operators and amplifier
+    /// Create from the number shown on the DX7, from 1 to 32.
+    pub fn from_display(display: u8) -> Result<Self, AlgorithmError> {
+        if (1..=Algorithms::COUNT as u8).contains(&display) {
+            Ok(Self(display - 1))
+        } else {
+            Err(AlgorithmError::InvalidNumber(display))
+        }
+    }
+
+    /// The number stored in a preset, from 0 to 31.
+    pub const fn stored(&self) -> u8 {
+        self.0
+    }
+
+    /// The number shown on the DX7, from 1 to 32.
+    pub const fn display(&self) -> u8 {
+        self.0.saturating_add(1)
+    }
+}
+
+impl Display for AlgorithmId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Algorithm {}", self.display())
+    }
+}
+
+/// Algorithm IDs used to be the index of the algorithm from 0 to 31.
+#[deprecated(note = "Use AlgorithmId, which shows the algorithm number from 1 to 32")]
+pub type AlgorithmIndex = usize;
+
+/// Why a routing can't be used as an algorithm.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AlgorithmError {
+    /// The operator doesn't output to anything.
+    NoOutputs { operator_id: OperatorId },
+
+    /// The operator lists the same output more than once.
+    DuplicateOutput {
+        operator_id: OperatorId,
+        output: Output,
+    },
+
+    /// No operator outputs to the amplifier.
+    NoCarrier,
+
+    /// There is more than one feedback loop, the DX7 only has one. The
+    /// operator is the lowest numbered one in the second loop.
+    MultipleFeedbackLoops { operator_id: OperatorId },
+
+    /// The algorithm number is not between 1 and 32.
+    InvalidNumber(u8),
+}
+
+impl Display for AlgorithmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use AlgorithmError::*;
+        match self {
+            NoOutputs { operator_id } => {
+                write!(f, "Operator {} has no outputs", operator_id + 1)
+            }
+            DuplicateOutput {
+                operator_id,
+                output,
+            } => write!(
+                f,
+                "Operator {} outputs to {output} more than once",
+                operator_id + 1
+            ),
+            NoCarrier => f.write_str("No operator outputs to the amplifier"),
+            MultipleFeedbackLoops { operator_id } => write!(
+                f,
+                "Operator {} is in a second feedback loop",
+                operator_id + 1
+            ),
+            InvalidNumber(number) => write!(f, "Algorithm {number} is not between 1 and 32"),
+        }
+    }
+}
+
+impl std::error::Error for AlgorithmError {}
+
+/// Routing between the operators and amplifier. Algorithms are equal when
+/// each operator has the same outputs, in any order.
+#[derive(Clone, Debug)]
 pub struct Algorithm {
     routing_by_operator: [Vec<Output>; Preset::OPERATOR_COUNT],
 }
@@ -79,24 +217,485 @@ impl Algorithm {
         }
     }
 
-    /// Returns `true` if the operator exists and is a carrier
+    /// Check the routing before creating the algorithm. Every operator must
+    /// have at least one output without duplicates, at least one operator
+    /// must be a carrier and there can be at most one feedback loop.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Algorithm, AlgorithmError, Output::*};
+    /// let routing = [
+    ///     vec![Amplifier],
+    ///     vec![Op1],
+    ///     vec![],
+    ///     vec![Op3],
+    ///     vec![Op4],
+    ///     vec![Op5, Op6],
+    /// ];
+    /// assert_eq!(
+    ///     Err(AlgorithmError::NoOutputs { operator_id: 2 }),
+    ///     Algorithm::try_new(routing)
+    /// );
+    /// ```
+    pub fn try_new(
+        operators: [Vec<Output>; Preset::OPERATOR_COUNT],
+    ) -> Result<Self, AlgorithmError> {
+        let algorithm = Self::new(operators);
+        for (operator_id, outputs) in algorithm.routing_by_operator.iter().enumerate() {
+            let operator_id = operator_id as OperatorId;
+            if outputs.is_empty() {
+                return Err(AlgorithmError::NoOutputs { operator_id });
+            }
+            for (index, output) in outputs.iter().enumerate() {
+                if outputs[..index].contains(output) {
+                    return Err(AlgorithmError::DuplicateOutput {
+                        operator_id,
+                        output: *output,
+                    });
+                }
+            }
+        }
+        if let Some(second) = algorithm.loops().get(1) {
+            return Err(AlgorithmError::MultipleFeedbackLoops {
+                operator_id: second[0],
+            });
+        }
+        if algorithm.carriers().is_empty() {
+            return Err(AlgorithmError::NoCarrier);
+        }
+        Ok(algorithm)
+    }
+
+    /// Returns `true` if the operator exists and is a carrier. A carrier may
+    /// also modulate other operators.
     pub fn is_carrier(&self, operator_id: OperatorId) -> bool {
-        let routing = self.routing(operator_id);
-        routing == Some(&vec![Output::Amplifier])
+        self.routing(operator_id)
+            .is_some_and(|routing| routing.contains(&Output::Amplifier))
     }
 
-    /// Returns `true` if the operator exists and feeds back into itself.
+    /// The operators that output to the amplifier.
+    pub fn carriers(&self) -> Vec<OperatorId> {
+        (0..Preset::OPERATOR_COUNT as OperatorId)
+            .filter(|&operator_id| self.is_carrier(operator_id))
+            .collect()
+    }
+
+    /// The number of operators that output to the amplifier, including
+    /// carriers that also modulate other operators.
+    pub fn carrier_count(&self) -> usize {
+        self.carriers().len()
+    }
+
+    /// The number of operators that only modulate other operators.
+    pub fn modulator_count(&self) -> usize {
+        Preset::OPERATOR_COUNT - self.carrier_count()
+    }
+
+    /// Returns `true` if the operator exists and is part of the feedback
+    /// loop, either feeding back into itself or through other operators.
     pub fn is_feedback(&self, operator_id: OperatorId) -> bool {
-        let output = Output::from(operator_id);
-        let routing = self.routing(operator_id);
-        output
-            .and_then(|output| routing.map(|routing| routing.contains(&output)))
+        self.feedback_loop()
+            .is_some_and(|operators| operators.contains(&operator_id))
+    }
+
+    /// The operators in the feedback loop, in ascending order. An operator
+    /// feeding back into itself is a loop of one. Algorithms 4 and 6 feed
+    /// back through several operators.
+    pub fn feedback_loop(&self) -> Option<Vec<OperatorId>> {
+        (0..Preset::OPERATOR_COUNT as OperatorId).find_map(|operator_id| {
+            let mut visited = Vec::new();
+            let mut operators = self.path(operator_id, operator_id, &mut visited)?;
+            operators.sort();
+            Some(operators)
+        })
+    }
+
+    /// The feedback loops, each as its operators in ascending order.
+    /// Operators are in the same loop when each can reach the other through
+    /// their outputs. The DX7 algorithms have a single loop.
+    fn loops(&self) -> Vec<Vec<OperatorId>> {
+        let operator_ids = 0..Preset::OPERATOR_COUNT as OperatorId;
+        let reachable: Vec<Vec<OperatorId>> = operator_ids
+            .clone()
+            .map(|operator_id| self.reachable(operator_id))
+            .collect();
+        let mut loops: Vec<Vec<OperatorId>> = Vec::new();
+        for operator_id in operator_ids.clone() {
+            let in_loop = reachable[operator_id as usize].contains(&operator_id);
+            if !in_loop || loops.iter().flatten().any(|&other| other == operator_id) {
+                continue;
+            }
+            let operators = operator_ids
+                .clone()
+                .filter(|&other| {
+                    reachable[operator_id as usize].contains(&other)
+                        && reachable[other as usize].contains(&operator_id)
+                })
+                .collect();
+            loops.push(operators);
+        }
+        loops
+    }
+
+    /// The operators that can be reached by following the outputs of an
+    /// operator, which includes the operator itself if it is in a loop.
+    fn reachable(&self, operator_id: OperatorId) -> Vec<OperatorId> {
+        let mut reached = Vec::new();
+        let mut pending = vec![operator_id];
+        while let Some(from) = pending.pop() {
+            for to in self.routing(from).into_iter().flatten() {
+                if let Some(to) = to.operator_id().filter(|to| !reached.contains(to)) {
+                    reached.push(to);
+                    pending.push(to);
+                }
+            }
+        }
+        reached
+    }
+
+    /// Returns `true` if the output of an operator to the target feeds back.
+    /// That is an output to the same or a higher numbered operator in the
+    /// same feedback loop, which the DX7 delays by a sample. Other outputs
+    /// to higher numbered operators are ordinary modulation.
+    fn is_feedback_output(&self, operator_id: OperatorId, target: OperatorId) -> bool {
+        target >= operator_id
+            && self
+                .loops()
+                .iter()
+                .any(|operators| operators.contains(&operator_id) && operators.contains(&target))
+    }
+
+    /// The operators on a path from an operator to the target, not
+    /// including the target, that does not pass through visited operators.
+    fn path(
+        &self,
+        from: OperatorId,
+        target: OperatorId,
+        visited: &mut Vec<OperatorId>,
+    ) -> Option<Vec<OperatorId>> {
+        for output in self.routing(from)? {
+            let Some(next) = output.operator_id() else {
+                continue;
+            };
+            if next == target {
+                return Some(vec![from]);
+            }
+            if visited.contains(&next) {
+                continue;
+            }
+            visited.push(next);
+            if let Some(mut operators) = self.path(next, target, visited) {
+                operators.insert(0, from);
+                return Some(operators);
+            }
+        }
+        None
+    }
+
+    /// The operators that output to the target, including an operator that
+    /// feeds back into itself. The inputs of the amplifier are the
+    /// carriers.
+    pub fn inputs(&self, target: Output) -> Vec<OperatorId> {
+        (0..Preset::OPERATOR_COUNT as OperatorId)
+            .filter(|&operator_id| {
+                self.routing(operator_id)
+                    .is_some_and(|routing| routing.contains(&target))
+            })
+            .collect()
+    }
+
+    /// The other operators that modulate an operator, excluding feedback
+    /// into itself.
+    pub fn modulators_of(&self, operator_id: OperatorId) -> Vec<OperatorId> {
+        let Some(target) = Output::try_from(operator_id)
+            .ok()
+            .filter(Output::is_operator)
+        else {
+            return Vec::new();
+        };
+        self.inputs(target)
+            .into_iter()
+            .filter(|&input| input != operator_id)
+            .collect()
+    }
+
+    /// The operators in an order where every operator comes after the
+    /// operators that modulate it, for evaluating them one at a time.
+    ///
+    /// Feedback can't be ordered that way, so an operator in the feedback
+    /// loop that modulates itself or an operator with a higher number is
+    /// treated as a delayed connection. Its output is expected to be the one
+    /// from the previous sample, as on the DX7. Each carrier follows its own
+    /// modulators so the order is the same every time.
+    ///
+    /// ```
+    /// use synthahol_dx7::{AlgorithmId, Algorithms};
+    /// let algorithm = Algorithms::get(AlgorithmId::from_stored(0)).unwrap();
+    /// assert_eq!(vec![1, 0, 5, 4, 3, 2], algorithm.evaluation_order());
+    /// ```
+    pub fn evaluation_order(&self) -> Vec<OperatorId> {
+        fn visit(algorithm: &Algorithm, operator_id: OperatorId, order: &mut Vec<OperatorId>) {
+            if order.contains(&operator_id) {
+                return;
+            }
+            for modulator in algorithm.modulators_of(operator_id) {
+                if !algorithm.is_feedback_output(modulator, operator_id) {
+                    visit(algorithm, modulator, order);
+                }
+            }
+            order.push(operator_id);
+        }
+
+        let mut order = Vec::with_capacity(Preset::OPERATOR_COUNT);
+        let operator_ids = 0..Preset::OPERATOR_COUNT as OperatorId;
+        for operator_id in self.carriers().into_iter().chain(operator_ids) {
+            visit(self, operator_id, &mut order);
+        }
+        order
+    }
+
+    /// How far an operator is from the amplifier, counting the operators it
+    /// modulates on the shortest path. Carriers are at depth 0 and their
+    /// modulators at depth 1. Feedback is ignored. Returns `None` if the
+    /// operator doesn't exist or never reaches the amplifier.
+    pub fn depth(&self, operator_id: OperatorId) -> Option<u8> {
+        if self.is_carrier(operator_id) {
+            return Some(0);
+        }
+        self.modulation_targets(operator_id)
+            .into_iter()
+            .filter_map(|target| self.depth(target))
+            .min()
+            .map(|depth| depth + 1)
+    }
+
+    /// The depth of the operator furthest from the amplifier.
+    pub fn max_depth(&self) -> u8 {
+        (0..Preset::OPERATOR_COUNT as OperatorId)
+            .filter_map(|operator_id| self.depth(operator_id))
+            .max()
             .unwrap_or_default()
     }
 
+    /// The outputs of an operator. Prefer [`Algorithm::routing_slice`],
+    /// which doesn't depend on how the outputs are stored.
     pub fn routing(&self, operator_id: OperatorId) -> Option<&Vec<Output>> {
         self.routing_by_operator.get(operator_id as usize)
     }
+
+    /// The outputs of an operator.
+    pub fn routing_slice(&self, operator_id: OperatorId) -> Option<&[Output]> {
+        self.routing(operator_id).map(Vec::as_slice)
+    }
+
+    /// The outputs of each operator, in order of the operators.
+    ///
+    /// ```
+    /// use synthahol_dx7::{AlgorithmId, Algorithms, Output};
+    /// let algorithm = Algorithms::get(AlgorithmId::from_stored(31)).unwrap();
+    /// for (operator_id, outputs) in algorithm.iter() {
+    ///     assert!(outputs.contains(&Output::Amplifier), "Operator ID {operator_id}");
+    /// }
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (OperatorId, &[Output])> {
+        self.routing_by_operator
+            .iter()
+            .enumerate()
+            .map(|(operator_id, outputs)| (operator_id as OperatorId, outputs.as_slice()))
+    }
+
+    /// Returns `true` if the operators have the same outputs, in any order.
+    fn has_routing(&self, routing: &[Vec<Output>; Preset::OPERATOR_COUNT]) -> bool {
+        self.routing_by_operator
+            .iter()
+            .zip(routing)
+            .all(|(outputs, other)| sorted(outputs) == sorted(other))
+    }
+
+    /// The operators modulated by an operator, excluding feedback to itself
+    /// or to operators earlier in the loop.
+    fn modulation_targets(&self, operator_id: OperatorId) -> Vec<OperatorId> {
+        self.routing(operator_id)
+            .into_iter()
+            .flatten()
+            .filter_map(Output::operator_id)
+            .filter(|&target| !self.is_feedback_output(operator_id, target))
+            .collect()
+    }
+
+    /// Describe the routing as a Graphviz DOT digraph, for example to render
+    /// it with `dot -Tsvg`. The nodes are named `OP1` to `OP6` and `OUT`.
+    /// Carriers are drawn as double circles and feedback edges are dashed.
+    /// The algorithm number is the one shown on the DX7, from 1 to 32, and
+    /// is only used to name and label the graph.
+    ///
+    /// Dump all the algorithms to files:
+    ///
+    /// ```no_run
+    /// use synthahol_dx7::Algorithms;
+    /// for (index, algorithm) in Algorithms::all().iter().enumerate() {
+    ///     let number = index + 1;
+    ///     std::fs::write(format!("algorithm_{number}.dot"), algorithm.to_dot(number)).unwrap();
+    /// }
+    /// ```
+    pub fn to_dot(&self, algorithm_number: usize) -> String {
+        let node = |output: Output| {
+            if output.is_operator() {
+                format!("OP{}", output as u8 + 1)
+            } else {
+                "OUT".to_string()
+            }
+        };
+
+        let mut dot = format!("digraph algorithm_{algorithm_number} {{\n");
+        dot += &format!("    label=\"Algorithm {algorithm_number}\";\n");
+        dot += "    OUT [shape=box];\n";
+        for operator_id in 0..Preset::OPERATOR_COUNT as OperatorId {
+            let shape = if self.is_carrier(operator_id) {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            dot += &format!("    OP{} [shape={shape}];\n", operator_id + 1);
+        }
+
+        for operator_id in 0..Preset::OPERATOR_COUNT as OperatorId {
+            for &output in self.routing(operator_id).into_iter().flatten() {
+                let feedback = output
+                    .operator_id()
+                    .is_some_and(|target| self.is_feedback_output(operator_id, target));
+                let style = if feedback { " [style=dashed]" } else { "" };
+                dot += &format!("    OP{} -> {}{style};\n", operator_id + 1, node(output));
+            }
+        }
+        dot += "}\n";
+        dot
+    }
+
+    /// Draw the operators like the chart printed on the DX7. Carriers are
+    /// on the bottom row with the operators that modulate them stacked
+    /// above. Operators in the feedback loop are marked with an asterisk.
+    ///
+    /// ```
+    /// use synthahol_dx7::{AlgorithmId, Algorithms};
+    /// let diagram = Algorithms::get(AlgorithmId::from_stored(4)).unwrap().diagram();
+    /// assert_eq!(diagram.lines().next(), Some("[2]  [4]  [6]*"));
+    /// ```
+    pub fn diagram(&self) -> String {
+        const CELL_WIDTH: usize = 5;
+        let operator_ids = 0..Preset::OPERATOR_COUNT as OperatorId;
+        let carriers = self.carriers();
+        let Some(&first_carrier) = carriers.first() else {
+            return String::new();
+        };
+
+        // Each modulator is drawn above the first operator it modulates
+        let parent = |operator_id: OperatorId| {
+            if self.is_carrier(operator_id) {
+                None
+            } else {
+                self.modulation_targets(operator_id).first().copied()
+            }
+        };
+        let children = |operator_id: OperatorId| -> Vec<OperatorId> {
+            operator_ids
+                .clone()
+                .filter(|&child| parent(child) == Some(operator_id))
+                .collect()
+        };
+        fn width(
+            operator_id: OperatorId,
+            children: &dyn Fn(OperatorId) -> Vec<OperatorId>,
+        ) -> usize {
+            children(operator_id)
+                .into_iter()
+                .map(|child| width(child, children))
+                .sum::<usize>()
+                .max(1)
+        }
+
+        // Column and row of each operator, with row 0 at the bottom
+        let mut positions = [(0, 0); Preset::OPERATOR_COUNT];
+        let mut pending: Vec<(OperatorId, usize, usize)> = Vec::new();
+        let mut column = 0;
+        for &carrier in &carriers {
+            pending.push((carrier, column, 0));
+            column += width(carrier, &children);
+        }
+        let column_count = column;
+        while let Some((operator_id, column, row)) = pending.pop() {
+            positions[operator_id as usize] = (column, row);
+            let mut child_column = column;
+            for child in children(operator_id) {
+                pending.push((child, child_column, row + 1));
+                child_column += width(child, &children);
+            }
+        }
+        let row_count = positions.iter().map(|(_, row)| row + 1).max().unwrap_or(1);
+
+        // Horizontal lines join the columns at each end with a plus
+        let line_width = column_count * CELL_WIDTH;
+        let connect = |line: &mut Vec<char>, from: usize, to: usize| {
+            let (from, to) = (from.min(to) * CELL_WIDTH + 1, from.max(to) * CELL_WIDTH + 1);
+            if from == to {
+                if line[from] == ' ' {
+                    line[from] = '|';
+                }
+                return;
+            }
+            for c in &mut line[from + 1..to] {
+                if *c == ' ' {
+                    *c = '-';
+                }
+            }
+            line[from] = '+';
+            line[to] = '+';
+        };
+        let feedback = self.feedback_loop().unwrap_or_default();
+
+        let mut lines = Vec::new();
+        for row in (0..row_count).rev() {
+            let mut line = vec![' '; line_width];
+            for operator_id in operator_ids.clone() {
+                let (column, operator_row) = positions[operator_id as usize];
+                if operator_row != row {
+                    continue;
+                }
+                let start = column * CELL_WIDTH;
+                let cell = format!("[{}]", operator_id + 1);
+                for (c, cell_char) in line[start..].iter_mut().zip(cell.chars()) {
+                    *c = cell_char;
+                }
+                if feedback.contains(&operator_id) {
+                    line[start + 3] = '*';
+                }
+            }
+            lines.push(line);
+
+            // Connect the operators of this row to the ones below
+            let mut line = vec![' '; line_width];
+            for operator_id in operator_ids.clone() {
+                let (column, operator_row) = positions[operator_id as usize];
+                if operator_row != row {
+                    continue;
+                }
+                if self.is_carrier(operator_id) {
+                    // Carriers are joined to each other on the last line
+                    connect(&mut line, positions[first_carrier as usize].0, column);
+                } else {
+                    for target in self.modulation_targets(operator_id) {
+                        connect(&mut line, column, positions[target as usize].0);
+                    }
+                }
+            }
+            lines.push(line);
+        }
+
+        lines
+            .into_iter()
+            .map(|line| line.into_iter().collect::<String>().trim_end().to_owned())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 lazy_static! {
@@ -131,9 +730,9 @@ static ref ALGORITHMS: [Algorithm; Algorithms::COUNT] = {
             vec![Amplifier],
             vec![Op1],
             vec![Op2],
-            vec![Amplifier],
+            vec![Amplifier, Op6],
             vec![Op4],
-            vec![Op5, Amplifier],
+            vec![Op5],
         ]),
         Algorithm::new( [
             vec![Amplifier],
@@ -148,8 +747,8 @@ Algorithm::new(        [
             vec![Op1],
             vec![Amplifier],
             vec![Op3],
-            vec![Amplifier],
-            vec![Op5, Amplifier],
+            vec![Amplifier, Op6],
+            vec![Op5],
         ]),
 Algorithm::new(        [
             vec![Amplifier],
@@ -363,17 +962,69 @@ Algorithm::new(        [
 };
 }
 
+impl PartialEq for Algorithm {
+    fn eq(&self, other: &Self) -> bool {
+        self.has_routing(&other.routing_by_operator)
+    }
+}
+
+impl Eq for Algorithm {}
+
+impl Hash for Algorithm {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for outputs in &self.routing_by_operator {
+            sorted(outputs).hash(state);
+        }
+    }
+}
+
+/// Outputs in the order of the operators, then the amplifier.
+fn sorted(outputs: &[Output]) -> Vec<Output> {
+    let mut outputs = outputs.to_vec();
+    outputs.sort_by_key(|output| *output as u8);
+    outputs
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.diagram())
+    }
+}
+
 pub struct Algorithms;
 
 impl Algorithms {
-    const COUNT: usize = 32;
+    /// Number of algorithms on the DX7.
+    pub const COUNT: usize = 32;
 
     pub fn all() -> &'static [Algorithm; Algorithms::COUNT] {
         &ALGORITHMS
     }
 
     pub fn get(id: AlgorithmId) -> Option<&'static Algorithm> {
-        ALGORITHMS.get(id)
+        ALGORITHMS.get(id.stored() as usize)
+    }
+
+    /// The DX7 algorithm with the same routing as the outputs of each
+    /// operator, in any order.
+    ///
+    /// ```
+    /// use synthahol_dx7::{AlgorithmId, Algorithms, Output::*};
+    /// let routing = [
+    ///     vec![Amplifier],
+    ///     vec![Amplifier],
+    ///     vec![Amplifier],
+    ///     vec![Amplifier],
+    ///     vec![Amplifier],
+    ///     vec![Op6, Amplifier],
+    /// ];
+    /// assert_eq!(Some(AlgorithmId::from_stored(31)), Algorithms::find(&routing));
+    /// ```
+    pub fn find(routing: &[Vec<Output>; Preset::OPERATOR_COUNT]) -> Option<AlgorithmId> {
+        ALGORITHMS
+            .iter()
+            .position(|algorithm| algorithm.has_routing(routing))
+            .map(|index| AlgorithmId::from_stored(index as u8))
     }
 }
 
@@ -383,9 +1034,52 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn algorithm_id() {
+        assert_eq!(
+            Ok(AlgorithmId::from_stored(0)),
+            AlgorithmId::from_display(1)
+        );
+        assert_eq!(
+            Ok(AlgorithmId::from_stored(31)),
+            AlgorithmId::from_display(32)
+        );
+        assert_eq!(
+            Err(AlgorithmError::InvalidNumber(0)),
+            AlgorithmId::from_display(0)
+        );
+        assert_eq!(
+            Err(AlgorithmError::InvalidNumber(33)),
+            AlgorithmId::from_display(33)
+        );
+        assert_eq!(32, AlgorithmId::from_stored(31).display());
+        assert_eq!("Algorithm 1", AlgorithmId::default().to_string());
+        assert!(Algorithms::get(AlgorithmId::from_stored(32)).is_none());
+    }
+
+    #[test]
+    fn output_conversions() {
+        for (number, output) in Output::ALL.into_iter().enumerate() {
+            let number = number as u8;
+            assert_eq!(Ok(output), Output::try_from(number));
+            assert_eq!(number, u8::from(output));
+            match output {
+                Output::Amplifier => assert_eq!(None, output.operator_id()),
+                _ => assert_eq!(Some(number), output.operator_id()),
+            }
+        }
+        assert!(Output::try_from(7).is_err());
+
+        #[allow(deprecated)]
+        {
+            assert_eq!(Some(Output::Op6), Output::from(5));
+            assert_eq!(None, Output::from(6));
+        }
+    }
+
     #[test]
     fn carrier() {
-        let algorithm = Algorithms::get(0).unwrap();
+        let algorithm = Algorithms::get(AlgorithmId::from_stored(0)).unwrap();
         assert!(algorithm.is_carrier(0));
         assert!(!algorithm.is_carrier(1));
         assert!(algorithm.is_carrier(2));
@@ -394,11 +1088,514 @@ mod tests {
         assert!(!algorithm.is_carrier(6));
     }
 
+    #[test]
+    fn carriers() {
+        let carriers = |algorithm_id| {
+            Algorithms::get(AlgorithmId::from_stored(algorithm_id))
+                .unwrap()
+                .carriers()
+        };
+        assert_eq!(vec![0, 2], carriers(0));
+        assert_eq!(vec![0, 2, 4], carriers(5));
+        assert_eq!(vec![0, 1, 2, 3, 4], carriers(24));
+        assert_eq!(vec![0, 1, 2, 3, 4, 5], carriers(31));
+        assert!(Algorithms::get(AlgorithmId::from_stored(31))
+            .unwrap()
+            .is_carrier(5));
+    }
+
+    #[test]
+    fn carrier_count() {
+        // From the algorithm chart on the DX7
+        let carrier_counts = [
+            2, 2, 2, 2, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 3, 3, 4, 4, 4, 5, 5, 3, 3, 3, 4,
+            4, 5, 6,
+        ];
+        for (algorithm_index, algorithm) in Algorithms::all().iter().enumerate() {
+            assert_eq!(
+                carrier_counts[algorithm_index],
+                algorithm.carrier_count(),
+                "Algorithm index {algorithm_index}"
+            );
+            assert_eq!(
+                Preset::OPERATOR_COUNT,
+                algorithm.carrier_count() + algorithm.modulator_count()
+            );
+        }
+    }
+
+    #[test]
+    fn inputs() {
+        let algorithm = Algorithms::get(AlgorithmId::from_stored(0)).unwrap();
+        assert_eq!(vec![1], algorithm.inputs(Output::Op1));
+        assert_eq!(vec![5], algorithm.inputs(Output::Op5));
+        assert_eq!(vec![5], algorithm.inputs(Output::Op6));
+        assert_eq!(algorithm.carriers(), algorithm.inputs(Output::Amplifier));
+        assert_eq!(vec![5], algorithm.modulators_of(4));
+        assert!(algorithm.modulators_of(5).is_empty());
+        assert!(algorithm.modulators_of(6).is_empty());
+
+        // Every operator is an input of exactly the outputs it routes to
+        let outputs = [
+            Output::Op1,
+            Output::Op2,
+            Output::Op3,
+            Output::Op4,
+            Output::Op5,
+            Output::Op6,
+            Output::Amplifier,
+        ];
+        for (algorithm_index, algorithm) in Algorithms::all().iter().enumerate() {
+            for operator_id in 0..Preset::OPERATOR_COUNT as OperatorId {
+                let routing = algorithm.routing(operator_id).unwrap();
+                for output in outputs {
+                    assert_eq!(
+                        routing.contains(&output),
+                        algorithm.inputs(output).contains(&operator_id),
+                        "Algorithm index {algorithm_index}, operator ID {operator_id}, {output}"
+                    );
+                }
+                assert!(!algorithm.modulators_of(operator_id).contains(&operator_id));
+            }
+            assert_eq!(algorithm.carriers(), algorithm.inputs(Output::Amplifier));
+        }
+    }
+
+    #[test]
+    fn evaluation_order() {
+        let order = |algorithm_id| {
+            Algorithms::get(AlgorithmId::from_stored(algorithm_id))
+                .unwrap()
+                .evaluation_order()
+        };
+        assert_eq!(vec![1, 0, 5, 4, 3, 2], order(0));
+        assert_eq!(vec![0, 1, 2, 3, 4, 5], order(31));
+
+        // Feedback from operator 4 to 6 is delayed
+        assert_eq!(vec![2, 1, 0, 5, 4, 3], order(3));
+
+        // Operator 1 modulating operator 6 without a loop is not delayed
+        use Output::*;
+        let algorithm = Algorithm::try_new([
+            vec![Op6],
+            vec![Amplifier],
+            vec![Op2],
+            vec![Amplifier],
+            vec![Op4],
+            vec![Amplifier, Op6],
+        ])
+        .unwrap();
+        assert_eq!(vec![2, 1, 4, 3, 0, 5], algorithm.evaluation_order());
+
+        for (algorithm_index, algorithm) in Algorithms::all().iter().enumerate() {
+            let order = algorithm.evaluation_order();
+            let mut sorted = order.clone();
+            sorted.sort();
+            assert_eq!(vec![0, 1, 2, 3, 4, 5], sorted);
+
+            let position = |operator_id| order.iter().position(|&id| id == operator_id);
+            for operator_id in 0..Preset::OPERATOR_COUNT as OperatorId {
+                for modulator in algorithm.modulators_of(operator_id) {
+                    if !algorithm.is_feedback_output(modulator, operator_id) {
+                        assert!(
+                            position(modulator) < position(operator_id),
+                            "Algorithm index {algorithm_index}, operator ID {operator_id}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     fn feedback() {
-        let algorithm = Algorithms::get(0).unwrap();
+        let algorithm = Algorithms::get(AlgorithmId::from_stored(0)).unwrap();
         assert!(!algorithm.is_feedback(0));
         assert!(algorithm.is_feedback(5));
+        assert_eq!(Some(vec![5]), algorithm.feedback_loop());
+
+        let algorithm = Algorithms::get(AlgorithmId::from_stored(3)).unwrap();
+        assert_eq!(Some(vec![3, 4, 5]), algorithm.feedback_loop());
+        assert!(algorithm.is_feedback(3));
+        assert!(!algorithm.is_feedback(2));
+
+        let algorithm = Algorithms::get(AlgorithmId::from_stored(5)).unwrap();
+        assert_eq!(Some(vec![4, 5]), algorithm.feedback_loop());
+        assert!(algorithm.is_feedback(4));
+        assert!(!algorithm.is_feedback(3));
+
+        // Every DX7 algorithm has feedback
+        assert!(Algorithms::all()
+            .iter()
+            .all(|algorithm| algorithm.feedback_loop().is_some()));
+
+        use Output::*;
+        let algorithm = Algorithm::new([
+            vec![Amplifier],
+            vec![Op1],
+            vec![Op2],
+            vec![Op3],
+            vec![Op4],
+            vec![Op5],
+        ]);
+        assert_eq!(None, algorithm.feedback_loop());
+        assert!(!algorithm.is_feedback(5));
+    }
+
+    #[test]
+    fn chart() {
+        // The algorithms as printed on the DX7 and reproduced by Dexed, with
+        // operators numbered from 1. Each has the operators that modulate
+        // other operators, the carriers, and the operator with feedback and
+        // the operator it feeds back into.
+        type Chart = (&'static [(u8, u8)], &'static [u8], (u8, u8));
+        let charts: [Chart; Algorithms::COUNT] = [
+            (&[(2, 1), (6, 5), (5, 4), (4, 3)], &[1, 3], (6, 6)), // 1
+            (&[(2, 1), (6, 5), (5, 4), (4, 3)], &[1, 3], (2, 2)), // 2
+            (&[(3, 2), (2, 1), (6, 5), (5, 4)], &[1, 4], (6, 6)), // 3
+            (&[(3, 2), (2, 1), (6, 5), (5, 4)], &[1, 4], (4, 6)), // 4
+            (&[(2, 1), (4, 3), (6, 5)], &[1, 3, 5], (6, 6)),      // 5
+            (&[(2, 1), (4, 3), (6, 5)], &[1, 3, 5], (5, 6)),      // 6
+            (&[(2, 1), (4, 3), (5, 3), (6, 5)], &[1, 3], (6, 6)), // 7
+            (&[(2, 1), (4, 3), (5, 3), (6, 5)], &[1, 3], (4, 4)), // 8
+            (&[(2, 1), (4, 3), (5, 3), (6, 5)], &[1, 3], (2, 2)), // 9
+            (&[(3, 2), (2, 1), (5, 4), (6, 4)], &[1, 4], (3, 3)), // 10
+            (&[(3, 2), (2, 1), (5, 4), (6, 4)], &[1, 4], (6, 6)), // 11
+            (&[(2, 1), (4, 3), (5, 3), (6, 3)], &[1, 3], (2, 2)), // 12
+            (&[(2, 1), (4, 3), (5, 3), (6, 3)], &[1, 3], (6, 6)), // 13
+            (&[(2, 1), (4, 3), (5, 4), (6, 4)], &[1, 3], (6, 6)), // 14
+            (&[(2, 1), (4, 3), (5, 4), (6, 4)], &[1, 3], (2, 2)), // 15
+            (&[(2, 1), (3, 1), (4, 3), (5, 1), (6, 5)], &[1], (6, 6)), // 16
+            (&[(2, 1), (3, 1), (4, 3), (5, 1), (6, 5)], &[1], (2, 2)), // 17
+            (&[(2, 1), (3, 1), (4, 1), (5, 4), (6, 5)], &[1], (3, 3)), // 18
+            (&[(2, 1), (3, 2), (6, 4), (6, 5)], &[1, 4, 5], (6, 6)), // 19
+            (&[(3, 1), (3, 2), (5, 4), (6, 4)], &[1, 2, 4], (3, 3)), // 20
+            (&[(3, 1), (3, 2), (6, 4), (6, 5)], &[1, 2, 4, 5], (3, 3)), // 21
+            (&[(2, 1), (6, 3), (6, 4), (6, 5)], &[1, 3, 4, 5], (6, 6)), // 22
+            (&[(3, 2), (6, 4), (6, 5)], &[1, 2, 4, 5], (6, 6)),   // 23
+            (&[(6, 3), (6, 4), (6, 5)], &[1, 2, 3, 4, 5], (6, 6)), // 24
+            (&[(6, 4), (6, 5)], &[1, 2, 3, 4, 5], (6, 6)),        // 25
+            (&[(3, 2), (5, 4), (6, 4)], &[1, 2, 4], (6, 6)),      // 26
+            (&[(3, 2), (5, 4), (6, 4)], &[1, 2, 4], (3, 3)),      // 27
+            (&[(2, 1), (4, 3), (5, 4)], &[1, 3, 6], (5, 5)),      // 28
+            (&[(4, 3), (6, 5)], &[1, 2, 3, 5], (6, 6)),           // 29
+            (&[(4, 3), (5, 4)], &[1, 2, 3, 6], (5, 5)),           // 30
+            (&[(6, 5)], &[1, 2, 3, 4, 5], (6, 6)),                // 31
+            (&[], &[1, 2, 3, 4, 5, 6], (6, 6)),                   // 32
+        ];
+        for (algorithm_index, (modulation, carriers, feedback)) in charts.into_iter().enumerate() {
+            let mut routing: [Vec<Output>; Preset::OPERATOR_COUNT] = Default::default();
+            for (modulator, target) in modulation.iter().chain([&feedback]) {
+                let target = Output::try_from(target - 1).unwrap();
+                routing[*modulator as usize - 1].push(target);
+            }
+            for carrier in carriers {
+                routing[*carrier as usize - 1].push(Output::Amplifier);
+            }
+            let algorithm = &Algorithms::all()[algorithm_index];
+            assert_eq!(
+                Algorithm::new(routing),
+                *algorithm,
+                "Algorithm {}",
+                algorithm_index + 1
+            );
+
+            let feedback_loop = algorithm.feedback_loop().unwrap();
+            assert!(feedback_loop.contains(&(feedback.0 - 1)));
+            assert!(feedback_loop.contains(&(feedback.1 - 1)));
+        }
+    }
+
+    #[test]
+    fn depth() {
+        let depths = |algorithm_id| {
+            let algorithm = Algorithms::get(AlgorithmId::from_stored(algorithm_id)).unwrap();
+            (0..Preset::OPERATOR_COUNT as OperatorId)
+                .map(|operator_id| algorithm.depth(operator_id).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![0, 1, 0, 1, 2, 3], depths(0));
+        assert_eq!(vec![0, 0, 0, 0, 0, 0], depths(31));
+        assert_eq!(
+            None,
+            Algorithms::get(AlgorithmId::from_stored(0))
+                .unwrap()
+                .depth(6)
+        );
+
+        // Operator 1 modulates operator 6 without a loop
+        use Output::*;
+        let algorithm = Algorithm::try_new([
+            vec![Op6],
+            vec![Amplifier],
+            vec![Op2],
+            vec![Amplifier],
+            vec![Op4],
+            vec![Amplifier, Op6],
+        ])
+        .unwrap();
+        assert_eq!(Some(1), algorithm.depth(0));
+        assert_eq!(1, algorithm.max_depth());
+
+        // Height of the tallest stack on the chart, less the carrier
+        let max_depths = [
+            3, 3, 2, 2, 1, 1, 2, 2, 2, 2, 2, 1, 1, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+            2, 1, 0,
+        ];
+        for (algorithm_index, algorithm) in Algorithms::all().iter().enumerate() {
+            assert_eq!(
+                max_depths[algorithm_index],
+                algorithm.max_depth(),
+                "Algorithm index {algorithm_index}"
+            );
+        }
+    }
+
+    #[test]
+    fn diagram() {
+        let diagram = |algorithm_id| {
+            Algorithms::get(AlgorithmId::from_stored(algorithm_id))
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            "     [6]*\n      |\n     [5]\n      |\n[2]  [4]\n |    |\n[1]  [3]\n +----+",
+            diagram(0)
+        );
+        assert_eq!(
+            "[2]  [4]  [6]*\n |    |    |\n[1]  [3]  [5]\n +----+----+",
+            diagram(4)
+        );
+        assert_eq!(
+            "     [4]  [6]*\n      |    |\n[2]  [3]  [5]\n +----+----+\n[1]\n |",
+            diagram(15)
+        );
+        assert_eq!(
+            "[1]  [2]  [3]  [4]  [5]  [6]*\n +----+----+----+----+----+",
+            diagram(31)
+        );
+
+        for (algorithm_index, algorithm) in Algorithms::all().iter().enumerate() {
+            let diagram = algorithm.diagram();
+            for operator_id in 1..=6 {
+                assert_eq!(
+                    1,
+                    diagram.matches(&format!("[{operator_id}]")).count(),
+                    "Algorithm index {algorithm_index} operator {operator_id}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn to_dot() {
+        let dot = Algorithms::get(AlgorithmId::from_stored(0))
+            .unwrap()
+            .to_dot(1);
+        let lines: Vec<&str> = dot.lines().collect();
+        assert_eq!(Some(&"digraph algorithm_1 {"), lines.first());
+        assert_eq!(Some(&"}"), lines.last());
+        for line in [
+            "    label=\"Algorithm 1\";",
+            "    OP1 [shape=doublecircle];",
+            "    OP2 [shape=circle];",
+            "    OP1 -> OUT;",
+            "    OP2 -> OP1;",
+            "    OP3 -> OUT;",
+            "    OP6 -> OP5;",
+            "    OP6 -> OP6 [style=dashed];",
+        ] {
+            assert!(lines.contains(&line), "Missing {line:?} in\n{dot}");
+        }
+
+        // Feedback through several operators
+        let dot = Algorithms::get(AlgorithmId::from_stored(3))
+            .unwrap()
+            .to_dot(4);
+        assert!(dot.contains("    OP4 -> OP6 [style=dashed];\n"));
+        assert!(dot.contains("    OP6 -> OP5;\n"));
+        assert!(dot.contains("    OP5 -> OP4;\n"));
+
+        // Modulating a higher numbered operator without a loop is not feedback
+        use Output::*;
+        let algorithm = Algorithm::try_new([
+            vec![Op6],
+            vec![Amplifier],
+            vec![Op2],
+            vec![Amplifier],
+            vec![Op4],
+            vec![Amplifier, Op6],
+        ])
+        .unwrap();
+        let dot = algorithm.to_dot(0);
+        assert!(dot.contains("    OP1 -> OP6;\n"));
+        assert!(dot.contains("    OP6 -> OP6 [style=dashed];\n"));
+
+        // One edge per routing and exactly one feedback edge
+        for (algorithm_index, algorithm) in Algorithms::all().iter().enumerate() {
+            let dot = algorithm.to_dot(algorithm_index + 1);
+            let edge_count: usize = (0..Preset::OPERATOR_COUNT as OperatorId)
+                .map(|operator_id| algorithm.routing(operator_id).unwrap().len())
+                .sum();
+            assert_eq!(edge_count, dot.matches(" -> ").count());
+            assert_eq!(1, dot.matches("style=dashed").count());
+            assert_eq!(
+                algorithm.carriers().len(),
+                dot.matches("doublecircle").count()
+            );
+        }
+    }
+
+    #[test]
+    fn eq() {
+        let algorithm = Algorithms::get(AlgorithmId::from_stored(0)).unwrap();
+        let mut routing = algorithm.routing_by_operator.clone();
+        for outputs in routing.iter_mut() {
+            outputs.reverse();
+        }
+        let permuted = Algorithm::new(routing);
+        assert_eq!(*algorithm, permuted);
+        assert_eq!(algorithm.clone(), permuted);
+
+        let hash = |algorithm: &Algorithm| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            algorithm.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(algorithm), hash(&permuted));
+
+        // Every algorithm is different
+        let unique: HashSet<&Algorithm> = Algorithms::all().iter().collect();
+        assert_eq!(Algorithms::COUNT, unique.len());
+        for (index, algorithm) in Algorithms::all().iter().enumerate() {
+            for other in &Algorithms::all()[index + 1..] {
+                assert_ne!(algorithm, other);
+            }
+        }
+
+        assert!(format!("{algorithm:?}").contains("[Op5, Op6]"));
+    }
+
+    #[test]
+    fn find() {
+        for (algorithm_index, algorithm) in Algorithms::all().iter().enumerate() {
+            let mut routing = algorithm.routing_by_operator.clone();
+            assert_eq!(
+                Some(AlgorithmId::from_stored(algorithm_index as u8)),
+                Algorithms::find(&routing)
+            );
+            for outputs in routing.iter_mut() {
+                outputs.reverse();
+            }
+            assert_eq!(
+                Some(AlgorithmId::from_stored(algorithm_index as u8)),
+                Algorithms::find(&routing)
+            );
+        }
+
+        use Output::*;
+        let routing = [
+            vec![Amplifier],
+            vec![Op1],
+            vec![Op2],
+            vec![Op3],
+            vec![Op4],
+            vec![Op5],
+        ];
+        assert_eq!(None, Algorithms::find(&routing));
+    }
+
+    #[test]
+    fn try_new() {
+        for (algorithm_index, algorithm) in Algorithms::all().iter().enumerate() {
+            assert!(
+                Algorithm::try_new(algorithm.routing_by_operator.clone()).is_ok(),
+                "Algorithm index {algorithm_index}"
+            );
+        }
+
+        use Output::*;
+        let error = |routing| Algorithm::try_new(routing).err();
+        assert_eq!(
+            Some(AlgorithmError::DuplicateOutput {
+                operator_id: 1,
+                output: Op1
+            }),
+            error([
+                vec![Amplifier],
+                vec![Op1, Op1],
+                vec![Amplifier],
+                vec![Amplifier],
+                vec![Amplifier],
+                vec![Amplifier],
+            ])
+        );
+        assert_eq!(
+            Some(AlgorithmError::NoCarrier),
+            error([
+                vec![Op2],
+                vec![Op1],
+                vec![Op2],
+                vec![Op3],
+                vec![Op4],
+                vec![Op5],
+            ])
+        );
+        assert_eq!(
+            Some(AlgorithmError::MultipleFeedbackLoops { operator_id: 5 }),
+            error([
+                vec![Amplifier, Op1],
+                vec![Amplifier],
+                vec![Amplifier],
+                vec![Amplifier],
+                vec![Amplifier],
+                vec![Amplifier, Op6],
+            ])
+        );
+
+        // A loop with two outputs to higher numbered operators is one loop
+        assert!(Algorithm::try_new([
+            vec![Op4],
+            vec![Op5],
+            vec![Amplifier],
+            vec![Op2],
+            vec![Op1],
+            vec![Amplifier],
+        ])
+        .is_ok());
+
+        assert_eq!(
+            "Operator 2 outputs to Operator 1 more than once",
+            AlgorithmError::DuplicateOutput {
+                operator_id: 1,
+                output: Op1
+            }
+            .to_string()
+        );
+
+        // Outputs to higher operators that don't loop back are allowed
+        assert!(Algorithm::try_new([
+            vec![Amplifier, Op6],
+            vec![Amplifier],
+            vec![Amplifier],
+            vec![Amplifier],
+            vec![Amplifier],
+            vec![Amplifier, Op6],
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn iter() {
+        for algorithm in Algorithms::all() {
+            let entries: Vec<(OperatorId, &[Output])> = algorithm.iter().collect();
+            assert_eq!(Preset::OPERATOR_COUNT, entries.len());
+            for (index, (operator_id, outputs)) in entries.into_iter().enumerate() {
+                assert_eq!(index as OperatorId, operator_id);
+                assert_eq!(algorithm.routing_slice(operator_id), Some(outputs));
+            }
+        }
+        assert_eq!(None, Algorithms::all()[0].routing_slice(6));
     }
 
     #[test]
@@ -409,7 +1606,7 @@ mod tests {
                 let routing = algorithm
                     .routing(operator_id as OperatorId)
                     .expect("every operator has a routing");
-                let unique: HashSet<&Output> = routing.into_iter().collect();
+                let unique: HashSet<&Output> = routing.iter().collect();
                 assert_eq!(
                     routing.len(),
                     unique.len(),
@@ -0,0 +1,277 @@
+use std::ops::{Index, IndexMut};
+use std::slice;
+
+use crate::Preset;
+
+/// A bank of up to [`Bank::PRESET_COUNT`] presets, such as a cartridge or
+/// the internal memory of the DX7, and where it came from.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Bank {
+    /// Name of the bank. Banks read from a file are named after the file
+    /// without its extension.
+    pub name: Option<String>,
+
+    /// Where the bank was read from, such as the path of a file.
+    pub source: Option<String>,
+
+    /// The presets in the order of their slots.
+    pub presets: Vec<Preset>,
+}
+
+impl Bank {
+    /// A bank of presets without a name or source.
+    pub fn new(presets: Vec<Preset>) -> Self {
+        Self {
+            presets,
+            ..Default::default()
+        }
+    }
+
+    /// The number of presets in the bank.
+    pub fn len(&self) -> usize {
+        self.presets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, Preset> {
+        self.presets.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, Preset> {
+        self.presets.iter_mut()
+    }
+
+    /// Presets whose name contains the pattern, ignoring case, with their
+    /// slots. Spaces at the start and end of the names and the pattern are
+    /// ignored.
+    ///
+    /// ```
+    /// use synthahol_dx7::Bank;
+    /// let bank = Bank::load_file("tests/rom1a.syx").unwrap();
+    /// let pianos = bank.find_by_name("piano");
+    /// let slots: Vec<usize> = pianos.iter().map(|(slot, _)| *slot).collect();
+    /// assert_eq!(vec![7, 8, 9, 10], slots);
+    /// ```
+    pub fn find_by_name(&self, pattern: &str) -> Vec<(usize, &Preset)> {
+        let pattern = pattern.trim().to_uppercase();
+        self.find(|name| name.to_uppercase().contains(&pattern))
+    }
+
+    /// Presets whose name matches a pattern where `*` matches any number of
+    /// characters and `?` matches a single character, ignoring case. Spaces
+    /// at the start and end of the names and the pattern are ignored.
+    ///
+    /// ```
+    /// use synthahol_dx7::Bank;
+    /// let bank = Bank::load_file("tests/rom1a.syx").unwrap();
+    /// assert_eq!(3, bank.find_by_glob("brass*").len());
+    /// assert_eq!(10, bank.find_by_glob("E.PIANO ?")[0].0);
+    /// ```
+    pub fn find_by_glob(&self, pattern: &str) -> Vec<(usize, &Preset)> {
+        let pattern: Vec<char> = pattern.trim().to_uppercase().chars().collect();
+        self.find(|name| {
+            let name: Vec<char> = name.to_uppercase().chars().collect();
+            glob_matches(&pattern, &name)
+        })
+    }
+
+    /// Slot of the first preset with exactly the name, ignoring spaces at
+    /// the start and end.
+    pub fn position_of_exact(&self, name: &str) -> Option<usize> {
+        let name = name.trim();
+        self.presets
+            .iter()
+            .position(|preset| preset.name.as_str().trim() == name)
+    }
+
+    fn find(&self, matches: impl Fn(&str) -> bool) -> Vec<(usize, &Preset)> {
+        self.presets
+            .iter()
+            .enumerate()
+            .filter(|(_, preset)| matches(preset.name.as_str().trim()))
+            .collect()
+    }
+}
+
+/// Whether the text matches a pattern with `*` and `?` wildcards.
+fn glob_matches(pattern: &[char], text: &[char]) -> bool {
+    // Where to resume when the characters after the last `*` don't match
+    let mut backtrack: Option<(usize, usize)> = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some('?') => {
+                p += 1;
+                t += 1;
+            }
+            Some(&c) if c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the star match one more character
+                Some((star, star_text)) => {
+                    backtrack = Some((star, star_text + 1));
+                    p = star + 1;
+                    t = star_text + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+impl From<Vec<Preset>> for Bank {
+    fn from(presets: Vec<Preset>) -> Self {
+        Bank::new(presets)
+    }
+}
+
+impl Index<usize> for Bank {
+    type Output = Preset;
+
+    fn index(&self, slot: usize) -> &Self::Output {
+        &self.presets[slot]
+    }
+}
+
+impl IndexMut<usize> for Bank {
+    fn index_mut(&mut self, slot: usize) -> &mut Self::Output {
+        &mut self.presets[slot]
+    }
+}
+
+impl IntoIterator for Bank {
+    type Item = Preset;
+    type IntoIter = std::vec::IntoIter<Preset>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.presets.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Bank {
+    type Item = &'a Preset;
+    type IntoIter = slice::Iter<'a, Preset>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.presets.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Bank {
+    type Item = &'a mut Preset;
+    type IntoIter = slice::IterMut<'a, Preset>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.presets.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    #[test]
+    fn load_file() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let bank = Bank::load_file(&path).unwrap();
+        assert_eq!(Some("rom1a"), bank.name.as_deref());
+        assert_eq!(
+            Some(path.to_string_lossy().as_ref()),
+            bank.source.as_deref()
+        );
+        assert_eq!(Bank::PRESET_COUNT, bank.len());
+        assert!(!bank.is_empty());
+        assert_eq!("BRASS   1", bank[0].name.to_string());
+
+        let names: Vec<String> = bank.iter().map(|preset| preset.name.to_string()).collect();
+        assert_eq!("STRINGS 1", names[3]);
+        assert_eq!("TAKE OFF", names[31]);
+        assert_eq!(names.len(), (&bank).into_iter().count());
+
+        let data = std::fs::read(&path).unwrap();
+        let named = Bank::load(&mut data.as_slice(), Some("ROM1A".to_owned())).unwrap();
+        assert_eq!(Some("ROM1A"), named.name.as_deref());
+        assert_eq!(None, named.source);
+        assert_eq!(bank.presets, named.presets);
+    }
+
+    #[test]
+    fn modify() {
+        let mut bank = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        bank[1].feedback_level = 0;
+        for preset in &mut bank {
+            preset.transpose = 12;
+        }
+        bank.iter_mut().for_each(|preset| preset.lfo_delay = 1);
+        let presets: Vec<Preset> = bank.into_iter().collect();
+        assert_eq!(0, presets[1].feedback_level);
+        assert!(presets
+            .iter()
+            .all(|preset| preset.transpose == 12 && preset.lfo_delay == 1));
+    }
+
+    #[test]
+    fn find() {
+        let bank = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let slots = |found: Vec<(usize, &Preset)>| -> Vec<usize> {
+            found.into_iter().map(|(slot, _)| slot).collect()
+        };
+        assert_eq!(vec![0, 1, 2], slots(bank.find_by_glob("BRASS*")));
+        assert_eq!(vec![10], slots(bank.find_by_name("e.piano")));
+        assert_eq!(vec![10], slots(bank.find_by_name(" E.PIANO 1  ")));
+        assert_eq!(
+            "E.PIANO 1",
+            bank.find_by_name("e.piano")[0].1.name.to_string()
+        );
+        assert!(bank.find_by_name("KAZOO").is_empty());
+        assert!(bank.find_by_glob("KAZOO*").is_empty());
+        assert_eq!(Bank::PRESET_COUNT, bank.find_by_name("").len());
+        assert!(Bank::default().find_by_name("BRASS").is_empty());
+
+        assert_eq!(Some(31), bank.position_of_exact("TAKE OFF  "));
+        assert_eq!(Some(0), bank.position_of_exact("BRASS   1"));
+        assert_eq!(None, bank.position_of_exact("BRASS 1"));
+        assert_eq!(None, bank.position_of_exact("brass   1"));
+    }
+
+    #[test]
+    fn glob() {
+        let matches = |pattern: &str, text: &str| {
+            let pattern: Vec<char> = pattern.chars().collect();
+            let text: Vec<char> = text.chars().collect();
+            super::glob_matches(&pattern, &text)
+        };
+        assert!(matches("", ""));
+        assert!(matches("*", ""));
+        assert!(matches("*", "ANYTHING"));
+        assert!(matches("B?ASS", "BRASS"));
+        assert!(matches("*SS*1", "BRASS   1"));
+        assert!(matches("A*B*C", "AXXBYYBZC"));
+        assert!(matches("**A", "BA"));
+        assert!(!matches("?", ""));
+        assert!(!matches("BRASS", "BRASS   1"));
+        assert!(!matches("A*B", "AXXBC"));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_read() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let presets: Vec<Preset> = Bank::read_file(&path).unwrap();
+        let data = std::fs::read(&path).unwrap();
+        let read: Vec<Preset> = Bank::read(&mut data.as_slice(), None).unwrap();
+        assert_eq!(presets, read);
+        assert_eq!(Bank::load_file(&path).unwrap().presets, presets);
+    }
+}
@@ -0,0 +1,311 @@
+use std::fmt::{Display, Formatter};
+
+use crate::operator::ratio;
+use crate::{Operator, OperatorMode, Preset};
+
+/// A rough kind of instrument, guessed by [`Preset::classify`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Category {
+    Bass,
+
+    /// Pianos and other struck or plucked keyboard and string sounds.
+    Keys,
+
+    ElectricPiano,
+
+    /// Bells, chimes and tuned percussion.
+    Bell,
+
+    Brass,
+
+    /// Strings, choirs and pads.
+    Pad,
+
+    Organ,
+
+    /// Drums and sound effects.
+    PercussionFx,
+
+    /// Solo sounds such as synthesizer leads and woodwinds.
+    Lead,
+
+    /// The preset didn't look like any of the other categories.
+    Unknown,
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use Category::*;
+        let txt = match self {
+            Bass => "Bass",
+            Keys => "Keys",
+            ElectricPiano => "Electric piano",
+            Bell => "Bell",
+            Brass => "Brass",
+            Pad => "Strings and pads",
+            Organ => "Organ",
+            PercussionFx => "Percussion and effects",
+            Lead => "Lead",
+            Unknown => "Unknown",
+        };
+        f.write_str(txt)
+    }
+}
+
+/// How [`Preset::classify_with`] guesses the category of a preset.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ClassifyOptions {
+    /// Look for words such as BASS or PIANO in the name when the parameters
+    /// don't suggest a category.
+    pub use_name: bool,
+}
+
+/// Words in preset names that suggest a category. Earlier words take
+/// precedence, so E.PIANO is found before PIANO.
+const NAME_KEYWORDS: &[(&str, Category)] = &[
+    ("E.PIANO", Category::ElectricPiano),
+    ("EPIANO", Category::ElectricPiano),
+    ("E.PNO", Category::ElectricPiano),
+    ("RHODES", Category::ElectricPiano),
+    ("WURLI", Category::ElectricPiano),
+    ("BASS", Category::Bass),
+    ("BELL", Category::Bell),
+    ("CHIME", Category::Bell),
+    ("GLOCK", Category::Bell),
+    ("VIBE", Category::Bell),
+    ("MARIMBA", Category::Bell),
+    ("XYLO", Category::Bell),
+    ("BRASS", Category::Brass),
+    ("HORN", Category::Brass),
+    ("TRUMP", Category::Brass),
+    ("TROMB", Category::Brass),
+    ("TUBA", Category::Brass),
+    ("STRING", Category::Pad),
+    ("PAD", Category::Pad),
+    ("CHOIR", Category::Pad),
+    ("VOICE", Category::Pad),
+    ("ORGAN", Category::Organ),
+    ("PIPE", Category::Organ),
+    ("DRUM", Category::PercussionFx),
+    ("PERC", Category::PercussionFx),
+    ("SNARE", Category::PercussionFx),
+    ("TOM", Category::PercussionFx),
+    ("TIMP", Category::PercussionFx),
+    ("FX", Category::PercussionFx),
+    ("LEAD", Category::Lead),
+    ("SOLO", Category::Lead),
+    ("FLUTE", Category::Lead),
+    ("SAX", Category::Lead),
+    ("CLARI", Category::Lead),
+    ("OBOE", Category::Lead),
+    ("PIANO", Category::Keys),
+    ("CLAV", Category::Keys),
+    ("HARPSI", Category::Keys),
+    ("GUITAR", Category::Keys),
+    ("KOTO", Category::Keys),
+    ("HARP", Category::Keys),
+];
+
+/// Ratio of an operator in ratio mode without detune.
+fn operator_ratio(operator: &Operator) -> Option<f64> {
+    (operator.mode == OperatorMode::Ratio)
+        .then(|| ratio(operator.frequency_course, operator.frequency_fine))
+}
+
+/// Returns `true` if the ratio is not close to a multiple of a half, so the
+/// partials it makes don't line up with the harmonics of the note.
+fn is_inharmonic(ratio: f64) -> bool {
+    let fraction = (ratio * 2.0).fract();
+    fraction.min(1.0 - fraction) > 0.1
+}
+
+impl Preset {
+    /// Guess the kind of instrument the preset sounds like.
+    ///
+    /// This is a heuristic, meant for sorting large collections of
+    /// uncategorized presets, and many presets will be misclassified. It
+    /// looks at whether the carriers sustain or die away and how quickly
+    /// they start, how many carriers the algorithm has, operators in fixed
+    /// mode, the frequency ratios of the operators, vibrato and transpose.
+    /// The name is ignored, see [`Preset::classify_with`].
+    pub fn classify(&self) -> Category {
+        self.classify_with(ClassifyOptions::default())
+    }
+
+    /// Guess the kind of instrument the preset sounds like, optionally
+    /// falling back to keywords in the name when the parameters don't
+    /// suggest a category.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Bank, Category, ClassifyOptions};
+    /// let presets = Bank::load_file("tests/rom1a.syx").unwrap();
+    /// let vibe = &presets[20];
+    /// assert_eq!(Category::Unknown, vibe.classify());
+    /// let options = ClassifyOptions { use_name: true };
+    /// assert_eq!(Category::Bell, vibe.classify_with(options));
+    /// ```
+    pub fn classify_with(&self, options: ClassifyOptions) -> Category {
+        match self.classify_parameters() {
+            Category::Unknown if options.use_name => self.classify_name(),
+            category => category,
+        }
+    }
+
+    fn classify_name(&self) -> Category {
+        let name = self.name.to_string().to_ascii_uppercase();
+        NAME_KEYWORDS
+            .iter()
+            .find(|(keyword, _)| name.contains(keyword))
+            .map_or(Category::Unknown, |(_, category)| *category)
+    }
+
+    fn classify_parameters(&self) -> Category {
+        let carriers: Vec<&Operator> = self
+            .carriers()
+            .into_iter()
+            .map(|carrier| &self.operators[carrier as usize])
+            .filter(|operator| operator.output_level > 0)
+            .collect();
+        if carriers.is_empty() {
+            return Category::Unknown;
+        }
+        let modulators: Vec<&Operator> = (0..Preset::OPERATOR_COUNT)
+            .filter(|&id| !self.is_operator_carrier(id as _) && self.is_operator_audible(id as _))
+            .map(|id| &self.operators[id])
+            .collect();
+        let percussive = self.is_percussive();
+        let transpose = self.transpose_semitones();
+
+        // Carriers at a fixed pitch don't follow the keyboard
+        if carriers
+            .iter()
+            .any(|operator| operator.mode == OperatorMode::Fixed)
+        {
+            return if percussive {
+                Category::Bell
+            } else {
+                Category::PercussionFx
+            };
+        }
+        if transpose.abs() >= 24 {
+            return Category::PercussionFx;
+        }
+
+        let carrier_ratios: Vec<f64> = carriers
+            .iter()
+            .filter_map(|operator| operator_ratio(operator))
+            .collect();
+        if transpose <= -12 && carrier_ratios.iter().all(|&ratio| ratio <= 0.55) {
+            return Category::Bass;
+        }
+
+        // The slowest carrier decides how the sound starts
+        let attack_rate = carriers
+            .iter()
+            .map(|operator| operator.envelope.attack_rate())
+            .min()
+            .unwrap_or_default();
+
+        if percussive {
+            let bright_modulator = modulators
+                .iter()
+                .filter_map(|operator| operator_ratio(operator))
+                .any(|ratio| ratio >= 10.0);
+            // Operators in fixed mode don't follow the harmonics either
+            let inharmonic = carriers
+                .iter()
+                .chain(&modulators)
+                .filter(|operator| operator_ratio(operator).map_or(true, is_inharmonic))
+                .count();
+            return if attack_rate < 55 {
+                Category::Pad
+            } else if bright_modulator && carriers.len() >= 2 {
+                Category::ElectricPiano
+            } else if inharmonic * 2 >= carriers.len() + modulators.len() {
+                Category::Bell
+            } else {
+                Category::Keys
+            };
+        }
+
+        let vibrato = self.lfo_pitch_mod_depth > 0 && self.lfo_pitch_mod_sensitivity > 0;
+        let drawbars = carriers
+            .iter()
+            .filter(|operator| {
+                operator.envelope.attack_rate() >= 90 && operator.envelope.sustain_level() >= 90
+            })
+            .count()
+            >= 4;
+        if drawbars {
+            Category::Organ
+        } else if attack_rate < 55 || (attack_rate < 65 && vibrato && carriers.len() >= 2) {
+            Category::Pad
+        } else if carriers.len() <= 2 {
+            Category::Lead
+        } else if self.feedback_level >= 6 {
+            Category::Brass
+        } else {
+            Category::Unknown
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    #[test]
+    fn rom1a() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let expected = [
+            (0, Category::Brass),
+            (3, Category::Pad),
+            (10, Category::ElectricPiano),
+            (14, Category::Bass),
+            (16, Category::Organ),
+            (13, Category::Lead),
+            (7, Category::Keys),
+            (25, Category::Bell),
+            (31, Category::PercussionFx),
+        ];
+        for (index, category) in expected {
+            let preset = &presets[index];
+            assert_eq!(category, preset.classify(), "{}", preset.name);
+        }
+    }
+
+    #[test]
+    fn name() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let vibe = &presets[20];
+        assert_eq!("VIBE    1", vibe.name.to_string());
+        assert_eq!(Category::Unknown, vibe.classify());
+        let options = ClassifyOptions { use_name: true };
+        assert_eq!(Category::Bell, vibe.classify_with(options));
+
+        // The parameters take precedence
+        let mut preset = presets[10].clone();
+        preset.set_name("BASS").unwrap();
+        assert_eq!(Category::ElectricPiano, preset.classify_with(options));
+    }
+
+    #[test]
+    fn random() {
+        // Bytes up to 99 keep the envelopes valid so most presets unpack
+        let mut seed = 0x1234_5678_u32;
+        let mut classified = 0;
+        for _ in 0..1000 {
+            let packed = std::array::from_fn(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed >> 24) as u8 % 100
+            });
+            if let Ok(preset) = Preset::from_packed(&packed) {
+                preset.classify_with(ClassifyOptions { use_name: true });
+                classified += 1;
+            }
+        }
+        assert!(classified > 500);
+        Preset::default().classify();
+    }
+}
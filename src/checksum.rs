@@ -0,0 +1,90 @@
+//! The checksum used by Yamaha bulk dumps.
+
+/// Compute the Yamaha bulk dump checksum of some data, which is the masked
+/// 2's complement of the sum of the bytes.
+///
+/// # Example
+///
+/// ```
+/// use synthahol_dx7::checksum;
+/// assert_eq!(113, checksum(&[1, 2, 3, 4, 5]));
+/// ```
+pub fn checksum(data: &[u8]) -> u8 {
+    Checksum::compute(data)
+}
+
+/// Incremental computation of the Yamaha bulk dump checksum so data can be
+/// checked as it is streamed.
+///
+/// # Example
+///
+/// ```
+/// use synthahol_dx7::Checksum;
+/// let mut checksum = Checksum::new();
+/// checksum.update(&[1, 2]);
+/// checksum.update(&[3, 4, 5]);
+/// assert!(checksum.verify(113));
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Checksum {
+    sum: u8,
+}
+
+impl Checksum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compute the checksum of all the data at once.
+    pub fn compute(data: &[u8]) -> u8 {
+        let mut checksum = Self::new();
+        checksum.update(data);
+        checksum.value()
+    }
+
+    /// Add more data to the checksum.
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.sum = bytes.iter().fold(self.sum, |sum, c| sum.wrapping_sub(*c));
+    }
+
+    /// The checksum of all the data so far.
+    pub fn value(&self) -> u8 {
+        self.sum & 0x7F
+    }
+
+    /// Returns `true` if the checksum matches the expected checksum.
+    pub fn verify(&self, expected: u8) -> bool {
+        self.value() == expected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(0, checksum(&[]));
+        assert_eq!(0, checksum(&[0,]));
+        assert_eq!(86, checksum(&[42,]));
+        assert_eq!(113, checksum(&[1, 2, 3, 4, 5,]));
+        assert_eq!(94, checksum(&[100, 20, 30, 40, 100,]));
+    }
+
+    #[test]
+    fn incremental() {
+        let data = [100, 20, 30, 40, 100, 0x7F, 0x7F, 0x7F];
+        for split in 0..=data.len() {
+            let (first, second) = data.split_at(split);
+            let mut checksum = Checksum::new();
+            checksum.update(first);
+            checksum.update(second);
+            assert_eq!(Checksum::compute(&data), checksum.value());
+        }
+
+        let mut checksum = Checksum::new();
+        data.chunks(3).for_each(|chunk| checksum.update(chunk));
+        assert!(checksum.verify(Checksum::compute(&data)));
+        assert!(!checksum.verify(Checksum::compute(&data) + 1));
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use crate::{Bank, Preset, PresetName};
+
+/// A preset that sounds the same as an earlier preset in the bank, found by
+/// comparing their [fingerprints](Preset::fingerprint).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Removed {
+    /// Slot of the duplicate in the bank before anything was removed.
+    pub slot: usize,
+
+    pub name: PresetName,
+
+    /// Slot of the earlier preset that is kept.
+    pub original_slot: usize,
+
+    /// Name of the earlier preset, which may differ from the name of the
+    /// duplicate.
+    pub original_name: PresetName,
+}
+
+impl Display for Removed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Slot {} ('{}') is the same as slot {} ('{}')",
+            self.slot, self.name, self.original_slot, self.original_name
+        )
+    }
+}
+
+/// How [`Bank::dedup_with`] removes duplicates.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DedupOptions {
+    /// Replace duplicates with the default preset so every other preset
+    /// keeps its slot. When `false` duplicates are removed and the presets
+    /// after them move up.
+    pub backfill: bool,
+}
+
+impl Bank {
+    /// Presets that sound the same as an earlier preset, ignoring their
+    /// names, in the order of their slots. These are the presets that
+    /// [`Bank::dedup`] removes.
+    pub fn duplicates(&self) -> Vec<Removed> {
+        let mut originals: HashMap<u64, usize> = HashMap::new();
+        self.presets
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, preset)| {
+                let original_slot = *originals.entry(preset.fingerprint()).or_insert(slot);
+                (original_slot != slot).then(|| Removed {
+                    slot,
+                    name: preset.name.clone(),
+                    original_slot,
+                    original_name: self.presets[original_slot].name.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Remove presets that sound the same as an earlier preset, ignoring
+    /// their names. The presets after them move up. See
+    /// [`Bank::dedup_with`].
+    ///
+    /// ```
+    /// use synthahol_dx7::{Bank, Preset};
+    /// let mut copy = Preset::default();
+    /// copy.set_name("COPY").unwrap();
+    /// let mut bank = Bank::new(vec![Preset::default(), copy]);
+    /// let removed = bank.dedup();
+    /// assert_eq!("COPY", removed[0].name.to_string());
+    /// assert_eq!(1, bank.len());
+    /// ```
+    pub fn dedup(&mut self) -> Vec<Removed> {
+        self.dedup_with(DedupOptions::default())
+    }
+
+    /// Remove presets that sound the same as an earlier preset, ignoring
+    /// their names, returning what was removed.
+    ///
+    /// Backfilled slots hold the default preset, so they are duplicates of
+    /// each other if the bank is deduplicated again.
+    pub fn dedup_with(&mut self, options: DedupOptions) -> Vec<Removed> {
+        let removed = self.duplicates();
+        if options.backfill {
+            for duplicate in &removed {
+                self.presets[duplicate.slot] = Preset::default();
+            }
+        } else {
+            let slots: HashSet<usize> = removed.iter().map(|duplicate| duplicate.slot).collect();
+            let mut slot = 0;
+            self.presets.retain(|_| {
+                let keep = !slots.contains(&slot);
+                slot += 1;
+                keep
+            });
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    /// ROM1A with copies of E.PIANO 1 under other names in slots 12 and 20.
+    fn bank_with_copies() -> Bank {
+        let mut bank = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        for (slot, name) in [(12, "RHODES"), (20, "EP COPY")] {
+            bank[slot] = bank[10].clone();
+            bank[slot].set_name(name).unwrap();
+        }
+        bank
+    }
+
+    #[test]
+    fn duplicates() {
+        let bank = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        assert!(bank.duplicates().is_empty());
+
+        let bank = bank_with_copies();
+        let duplicates = bank.duplicates();
+        assert_eq!(2, duplicates.len());
+        assert_eq!(
+            Removed {
+                slot: 12,
+                name: PresetName("RHODES".to_owned()),
+                original_slot: 10,
+                original_name: PresetName("E.PIANO 1".to_owned()),
+            },
+            duplicates[0]
+        );
+        assert_eq!(20, duplicates[1].slot);
+        assert_eq!("EP COPY", duplicates[1].name.to_string());
+        assert_eq!(10, duplicates[1].original_slot);
+        assert_eq!(
+            "Slot 20 ('EP COPY') is the same as slot 10 ('E.PIANO 1')",
+            duplicates[1].to_string()
+        );
+    }
+
+    #[test]
+    fn dedup() {
+        let mut bank = bank_with_copies();
+        let original = bank.clone();
+        let removed = bank.dedup();
+        assert_eq!(original.duplicates(), removed);
+        assert_eq!(Bank::PRESET_COUNT - 2, bank.len());
+        assert_eq!(original[11], bank[11]);
+        assert_eq!(original[13], bank[12]);
+        assert_eq!(original[21], bank[19]);
+        assert_eq!(original[31], bank[29]);
+        assert!(bank.dedup().is_empty());
+    }
+
+    #[test]
+    fn backfill() {
+        let mut bank = bank_with_copies();
+        let original = bank.clone();
+        let options = DedupOptions { backfill: true };
+        let removed = bank.dedup_with(options);
+        assert_eq!(2, removed.len());
+        assert_eq!(Bank::PRESET_COUNT, bank.len());
+        assert_eq!(Preset::default(), bank[12]);
+        assert_eq!(Preset::default(), bank[20]);
+        assert_eq!(original[13], bank[13]);
+
+        // The filler is now duplicated
+        let removed = bank.dedup_with(options);
+        assert_eq!(vec![20], removed.iter().map(|r| r.slot).collect::<Vec<_>>());
+        assert_eq!(12, removed[0].original_slot);
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt::{Display, Formatter};
+
+use crate::envelope::{EnvelopeNames, OPERATOR_ENVELOPE_NAMES, PITCH_ENVELOPE_NAMES};
+use crate::{Envelope, Operator, Preset};
+
+/// A parameter that differs between two presets.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ParamDiff {
+    /// Index into [`Preset::operators`], or `None` for parameters of the
+    /// whole preset.
+    pub operator: Option<usize>,
+
+    /// Name of the parameter, such as `"output level"`.
+    pub parameter: &'static str,
+
+    /// The value in the preset being compared.
+    pub value: String,
+
+    /// The value in the other preset.
+    pub other: String,
+}
+
+impl Display for ParamDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.operator {
+            // Operators are numbered from one like on the DX7
+            Some(operator) => write!(f, "Operator {} {}", operator + 1, self.parameter)?,
+            None => {
+                let mut chars = self.parameter.chars();
+                if let Some(first) = chars.next() {
+                    write!(f, "{}{}", first.to_uppercase(), chars.as_str())?;
+                }
+            }
+        }
+        write!(f, " is {}, not {}", self.value, self.other)
+    }
+}
+
+/// Every parameter of a preset with its name and value, in a fixed order.
+struct Parameters(Vec<(Option<usize>, &'static str, String)>);
+
+impl Parameters {
+    fn push(&mut self, operator: Option<usize>, parameter: &'static str, value: impl ToString) {
+        self.0.push((operator, parameter, value.to_string()));
+    }
+
+    fn push_envelope(
+        &mut self,
+        operator: Option<usize>,
+        envelope: &Envelope,
+        names: &EnvelopeNames,
+    ) {
+        for (rate, name) in envelope.rates.iter().zip(names.rates) {
+            self.push(operator, name, rate);
+        }
+        for (level, name) in envelope.levels.iter().zip(names.levels) {
+            self.push(operator, name, level);
+        }
+    }
+
+    fn push_operator(&mut self, index: usize, operator: &Operator) {
+        let index = Some(index);
+        self.push_envelope(index, &operator.envelope, &OPERATOR_ENVELOPE_NAMES);
+        self.push(index, "break point", operator.scaling_break_point);
+        self.push(index, "left depth", operator.scaling_left_depth);
+        self.push(index, "right depth", operator.scaling_right_depth);
+        self.push(index, "left curve", operator.scaling_left_curve);
+        self.push(index, "right curve", operator.scaling_right_curve);
+        self.push(index, "detune", operator.detune);
+        self.push(index, "rate scaling", operator.rate_scaling);
+        self.push(index, "velocity sensitivity", operator.velocity_sensitivity);
+        self.push(
+            index,
+            "modulation sensitivity",
+            operator.modulation_sensitivity,
+        );
+        self.push(index, "output level", operator.output_level);
+        self.push(index, "mode", operator.mode);
+        self.push(index, "frequency coarse", operator.frequency_course);
+        self.push(index, "frequency fine", operator.frequency_fine);
+    }
+
+    fn of(preset: &Preset) -> Self {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        let mut parameters = Parameters(Vec::new());
+        parameters.push(None, "name", &preset.name);
+        for (index, operator) in preset.operators.iter().enumerate() {
+            parameters.push_operator(index, operator);
+        }
+        parameters.push_envelope(None, &preset.pitch_envelope, &PITCH_ENVELOPE_NAMES);
+        parameters.push(None, "algorithm", preset.algorithm_id.display());
+        parameters.push(
+            None,
+            "oscillator key sync",
+            on_off(preset.oscillator_key_sync),
+        );
+        parameters.push(None, "feedback level", preset.feedback_level);
+        parameters.push(None, "LFO speed", preset.lfo_speed);
+        parameters.push(None, "LFO delay", preset.lfo_delay);
+        parameters.push(None, "LFO pitch mod depth", preset.lfo_pitch_mod_depth);
+        parameters.push(
+            None,
+            "LFO amplitude mod depth",
+            preset.lfo_amplitude_mod_depth,
+        );
+        parameters.push(None, "LFO waveform", preset.lfo_waveform);
+        parameters.push(None, "LFO key sync", on_off(preset.lfo_key_sync));
+        parameters.push(
+            None,
+            "LFO pitch mod sensitivity",
+            preset.lfo_pitch_mod_sensitivity,
+        );
+        parameters.push(None, "transpose", preset.transpose);
+        parameters
+    }
+}
+
+impl Preset {
+    /// The parameters that differ from another preset, including the name.
+    /// Operators are compared with the operator in the same position.
+    ///
+    /// ```
+    /// use synthahol_dx7::Preset;
+    /// let preset = Preset::default();
+    /// let mut other = preset.clone();
+    /// other.operators[2].output_level = 80;
+    /// let diff = preset.diff(&other);
+    /// assert_eq!(1, diff.len());
+    /// assert_eq!("Operator 3 output level is 0, not 80", diff[0].to_string());
+    /// ```
+    pub fn diff(&self, other: &Preset) -> Vec<ParamDiff> {
+        Parameters::of(self)
+            .0
+            .into_iter()
+            .zip(Parameters::of(other).0)
+            .filter(|((_, _, value), (_, _, other))| value != other)
+            .map(|((operator, parameter, value), (_, _, other))| ParamDiff {
+                operator,
+                parameter,
+                value,
+                other,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::{Bank, Waveform};
+
+    use super::*;
+
+    #[test]
+    fn diff() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        for preset in &presets {
+            assert!(preset.diff(preset).is_empty());
+        }
+
+        let preset = &presets[0];
+        let mut other = preset.clone();
+        other.operators[3].frequency_fine = 14;
+        other.lfo_waveform = Waveform::Triangle;
+        let diff = preset.diff(&other);
+        assert_eq!(
+            vec![
+                ParamDiff {
+                    operator: Some(3),
+                    parameter: "frequency fine",
+                    value: "0".to_owned(),
+                    other: "14".to_owned(),
+                },
+                ParamDiff {
+                    operator: None,
+                    parameter: "LFO waveform",
+                    value: "Sine".to_owned(),
+                    other: "Triangle".to_owned(),
+                },
+            ],
+            diff
+        );
+        assert_eq!("LFO waveform is Sine, not Triangle", diff[1].to_string());
+
+        // Every parameter is compared
+        let diff = presets[0].diff(&presets[1]);
+        assert!(diff.iter().any(|diff| diff.parameter == "name"));
+        assert!(diff.iter().any(|diff| diff.parameter == "EG rate 1"));
+        assert_eq!(diff.len(), presets[1].diff(&presets[0]).len());
+        let count = Parameters::of(&presets[0]).0.len();
+        assert_eq!(1 + 6 * 21 + 8 + 11, count);
+    }
+}
@@ -0,0 +1,237 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::read::{packed_chunks, read_bank_body, unpack_voice, CountingReader};
+use crate::*;
+
+/// Number of voices a DX9 stores. The rest of the bank is unused.
+const DX9_VOICE_COUNT: usize = 20;
+
+/// Number of operators in a DX9 voice.
+const DX9_OPERATOR_COUNT: usize = 4;
+
+/// A voice of the four operator DX9.
+///
+/// The DX9 sends its voices in the same bank layout as the DX7, with its
+/// four operators in place of operators 3 to 6 and the DX9 algorithm number
+/// in place of the DX7 one. The DX9 has no pitch envelope or velocity
+/// sensitivity so those parts of the layout are ignored.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Dx9Voice {
+    pub name: PresetName,
+
+    /// Operators 1 to 4. The velocity sensitivity is always 0.
+    pub operators: [Operator; DX9_OPERATOR_COUNT],
+
+    /// Algorithm from 0 to 7.
+    pub algorithm: u8,
+
+    pub oscillator_key_sync: bool,
+    pub feedback_level: u8,
+    pub lfo_speed: u8,
+    pub lfo_delay: u8,
+    pub lfo_pitch_mod_depth: u8,
+    pub lfo_pitch_mod_sensitivity: u8,
+    pub lfo_amplitude_mod_depth: u8,
+    pub lfo_waveform: Waveform,
+    pub lfo_key_sync: bool,
+    pub transpose: u8,
+}
+
+impl Dx9Voice {
+    /// The DX7 algorithm that routes operators 3 to 6 the same way as each
+    /// DX9 algorithm routes operators 1 to 4.
+    pub const ALGORITHMS: [AlgorithmId; 8] = [
+        AlgorithmId::from_stored(0),
+        AlgorithmId::from_stored(13),
+        AlgorithmId::from_stored(7),
+        AlgorithmId::from_stored(6),
+        AlgorithmId::from_stored(4),
+        AlgorithmId::from_stored(21),
+        AlgorithmId::from_stored(30),
+        AlgorithmId::from_stored(31),
+    ];
+
+    /// Convert to a DX7 preset. The DX9 operators become operators 3 to 6
+    /// and operators 1 and 2 are muted, because no DX7 algorithm routes
+    /// operators 1 to 4 like every DX9 algorithm.
+    ///
+    /// The conversion is lossy. The pitch envelope is flat and the feedback
+    /// is applied to whichever operator the DX7 algorithm feeds back.
+    pub fn to_preset(&self) -> Preset {
+        let mut operators = [Operator::default(); Preset::OPERATOR_COUNT];
+        operators[Preset::OPERATOR_COUNT - DX9_OPERATOR_COUNT..].copy_from_slice(&self.operators);
+
+        let init = Preset::default();
+        Preset {
+            name: self.name.clone(),
+            operators,
+            pitch_envelope: init.pitch_envelope,
+            algorithm_id: Self::ALGORITHMS[self.algorithm as usize],
+            oscillator_key_sync: self.oscillator_key_sync,
+            feedback_level: self.feedback_level,
+            lfo_speed: self.lfo_speed,
+            lfo_delay: self.lfo_delay,
+            lfo_pitch_mod_depth: self.lfo_pitch_mod_depth,
+            lfo_pitch_mod_sensitivity: self.lfo_pitch_mod_sensitivity,
+            lfo_amplitude_mod_depth: self.lfo_amplitude_mod_depth,
+            lfo_waveform: self.lfo_waveform,
+            lfo_key_sync: self.lfo_key_sync,
+            transpose: self.transpose,
+        }
+    }
+
+    /// Take the DX9 parameters from a preset unpacked from a DX9 bank.
+    fn from_unpacked(preset: Preset) -> Self {
+        let mut operators = [Operator::default(); DX9_OPERATOR_COUNT];
+        operators.copy_from_slice(&preset.operators[Preset::OPERATOR_COUNT - DX9_OPERATOR_COUNT..]);
+        for operator in operators.iter_mut() {
+            operator.velocity_sensitivity = 0;
+        }
+
+        Dx9Voice {
+            name: preset.name,
+            operators,
+            algorithm: preset
+                .algorithm_id
+                .stored()
+                .min(Self::ALGORITHMS.len() as u8 - 1),
+            oscillator_key_sync: preset.oscillator_key_sync,
+            feedback_level: preset.feedback_level,
+            lfo_speed: preset.lfo_speed,
+            lfo_delay: preset.lfo_delay,
+            lfo_pitch_mod_depth: preset.lfo_pitch_mod_depth,
+            lfo_pitch_mod_sensitivity: preset.lfo_pitch_mod_sensitivity,
+            lfo_amplitude_mod_depth: preset.lfo_amplitude_mod_depth,
+            lfo_waveform: preset.lfo_waveform,
+            lfo_key_sync: preset.lfo_key_sync,
+            transpose: preset.transpose,
+        }
+    }
+}
+
+/// Banks of DX9 voices.
+pub struct Dx9Bank;
+
+impl Dx9Bank {
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Vec<Preset>, Error> {
+        let input = File::open(&path)?;
+        let mut reader = BufReader::new(input);
+        Self::load(&mut reader)
+    }
+
+    /// Read the 20 voices of a DX9 bank and convert them to DX7 presets.
+    pub fn load<R: Read>(reader: &mut R) -> Result<Vec<Preset>, Error> {
+        Ok(Self::read_voices(reader)?
+            .iter()
+            .map(Dx9Voice::to_preset)
+            .collect())
+    }
+
+    pub fn read_voices_file<P: AsRef<Path>>(path: P) -> Result<Vec<Dx9Voice>, Error> {
+        let input = File::open(&path)?;
+        let mut reader = BufReader::new(input);
+        Self::read_voices(&mut reader)
+    }
+
+    /// Read the 20 voices of a DX9 bank without converting them.
+    pub fn read_voices<R: Read>(reader: &mut R) -> Result<Vec<Dx9Voice>, Error> {
+        let mut reader = CountingReader::new(reader, 0);
+        let options = ReadOptions::default();
+        let mut warnings = Vec::new();
+        let (_, body, body_offset) = read_bank_body(&mut reader, options, &mut warnings)?;
+
+        packed_chunks(&body)
+            .take(DX9_VOICE_COUNT)
+            .enumerate()
+            .map(|(index, packed_voice)| {
+                unpack_voice(packed_voice, index, body_offset, options, &mut warnings)
+                    .map(Dx9Voice::from_unpacked)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+
+    use super::*;
+
+    #[test]
+    fn voices() {
+        let path = test_data_path(&["dx9_bank.syx"]);
+        let presets = Dx9Bank::load_file(&path).unwrap();
+        assert_eq!(20, presets.len());
+
+        let preset = &presets[0];
+        assert_eq!("DX9 01", preset.name.to_string());
+        assert_eq!(0, preset.algorithm_id.stored());
+        assert_eq!(5, preset.feedback_level);
+        assert_eq!(Preset::default().pitch_envelope, preset.pitch_envelope);
+        assert_eq!(0, preset.operators[0].output_level);
+        assert_eq!(0, preset.operators[1].output_level);
+        assert_eq!(99, preset.operators[2].output_level);
+        assert_eq!(72, preset.operators[5].output_level);
+        assert_eq!("DX9 20", presets[19].name.to_string());
+        assert_eq!(Dx9Voice::ALGORITHMS[19 % 8], presets[19].algorithm_id);
+
+        let voices = Dx9Bank::read_voices_file(&path).unwrap();
+        assert_eq!(20, voices.len());
+        assert_eq!(3, voices[3].algorithm);
+        assert_eq!(99, voices[3].operators[0].output_level);
+        assert_eq!(72, voices[3].operators[3].output_level);
+        assert!(voices
+            .iter()
+            .flat_map(|voice| voice.operators)
+            .all(|operator| operator.velocity_sensitivity == 0));
+        assert_eq!(voices[3].to_preset(), presets[3]);
+    }
+
+    #[test]
+    fn algorithms() {
+        // Carriers of each DX9 algorithm, counting operators from 0
+        let carriers: [&[OperatorId]; 8] = [
+            &[0],
+            &[0],
+            &[0],
+            &[0],
+            &[0, 2],
+            &[0, 1, 2],
+            &[0, 1, 2],
+            &[0, 1, 2, 3],
+        ];
+        let voices = Dx9Bank::read_voices_file(test_data_path(&["dx9_bank.syx"])).unwrap();
+        for (dx9_algorithm, carriers) in carriers.iter().enumerate() {
+            let voice = Dx9Voice {
+                algorithm: dx9_algorithm as u8,
+                ..voices[0].clone()
+            };
+            let preset = voice.to_preset();
+            let algorithm = preset.algorithm();
+            let found: Vec<OperatorId> = (0..DX9_OPERATOR_COUNT as OperatorId)
+                .filter(|operator_id| algorithm.is_carrier(operator_id + 2))
+                .collect();
+            assert_eq!(
+                carriers.to_vec(),
+                found,
+                "DX9 algorithm {}",
+                dx9_algorithm + 1
+            );
+
+            // Operators 1 and 2 are silent even when they are carriers
+            assert_eq!(0, preset.operators[0].output_level);
+            assert_eq!(0, preset.operators[1].output_level);
+        }
+    }
+
+    #[test]
+    fn not_a_bank() {
+        let error = Dx9Bank::load_file(test_data_path(&["tub_bells.syx"])).unwrap_err();
+        assert!(matches!(
+            error.root_cause(),
+            Error::IncorrectHeader { .. } | Error::IncorrectByteCount { .. }
+        ));
+    }
+}
@@ -1,15 +1,187 @@
-/// A four stage rate/level envelope
+use std::fmt::{Display, Formatter};
+
+use crate::normalize::Normalizer;
+
+/// Names of the envelope parameters when reporting values out of range.
+pub(crate) struct EnvelopeNames {
+    pub(crate) rates: [&'static str; Envelope::SEGMENT_COUNT],
+    pub(crate) levels: [&'static str; Envelope::SEGMENT_COUNT],
+}
+
+pub(crate) const OPERATOR_ENVELOPE_NAMES: EnvelopeNames = EnvelopeNames {
+    rates: ["EG rate 1", "EG rate 2", "EG rate 3", "EG rate 4"],
+    levels: ["EG level 1", "EG level 2", "EG level 3", "EG level 4"],
+};
+
+pub(crate) const PITCH_ENVELOPE_NAMES: EnvelopeNames = EnvelopeNames {
+    rates: [
+        "pitch EG rate 1",
+        "pitch EG rate 2",
+        "pitch EG rate 3",
+        "pitch EG rate 4",
+    ],
+    levels: [
+        "pitch EG level 1",
+        "pitch EG level 2",
+        "pitch EG level 3",
+        "pitch EG level 4",
+    ],
+};
+
+/// Output of the envelope generator for levels from 0 to 19, which are
+/// steeper than the rest. Higher levels add 28.
+const LOW_LEVELS: [u8; 20] = [
+    0, 5, 9, 13, 17, 20, 23, 25, 27, 29, 31, 33, 35, 37, 39, 41, 42, 43, 45, 46,
+];
+
+/// Pitch of each level of the pitch envelope in 1/32 of an octave, from the
+/// table in the pitch envelope of Dexed. Level 50 is the played pitch. The
+/// levels near the middle change the pitch by 3/8 of a semitone and the
+/// ends are steeper, reaching four octaves either way.
+const PITCH_LEVELS: [i8; 100] = [
+    -128, -116, -104, -95, -85, -76, -68, -61, -56, -52, -49, -46, -43, -41, -39, -37, -35, -33,
+    -32, -31, -30, -29, -28, -27, -26, -25, -24, -23, -22, -21, -20, -19, -18, -17, -16, -15, -14,
+    -13, -12, -11, -10, -9, -8, -7, -6, -5, -4, -3, -2, -1, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11,
+    12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 35, 38,
+    40, 43, 46, 49, 53, 58, 64, 70, 76, 83, 90, 101, 112, 127,
+];
+
+/// Semitones in each step of [`PITCH_LEVELS`].
+const PITCH_LEVEL_SEMITONES: f32 = 12.0 / 32.0;
+
+/// Lowest output of the envelope generator, which is silent.
+const MIN_LOG_LEVEL: f64 = 16.0;
+
+/// Output of the envelope generator at level 99.
+const MAX_LOG_LEVEL: f64 = 3840.0;
+
+/// A rising envelope jumps to at least this level before it starts rising.
+const RISING_START_LOG_LEVEL: f64 = 1716.0;
+
+/// Sample rate the envelope rates are defined at.
+const RATE_SAMPLE_RATE: f64 = 44100.0;
+
+/// Why rates and levels can't be used as an envelope.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EnvelopeError {
+    /// There must be a rate and level for each segment.
+    IncorrectLength { rates: usize, levels: usize },
+
+    /// A rate is above 99. Segments are numbered from 0.
+    RateOutOfRange { segment: usize, rate: u8 },
+
+    /// A level is above 99. Segments are numbered from 0.
+    LevelOutOfRange { segment: usize, level: u8 },
+
+    /// The time of a segment is negative or not a number. Segments are
+    /// numbered from 0.
+    InvalidSeconds { segment: usize },
+}
+
+impl Display for EnvelopeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use EnvelopeError::*;
+        match self {
+            IncorrectLength { rates, levels } => write!(
+                f,
+                "Expected {} rates and levels, found {rates} rates and {levels} levels",
+                Envelope::SEGMENT_COUNT
+            ),
+            RateOutOfRange { segment, rate } => write!(
+                f,
+                "Rate {} is {rate}, which is above {}",
+                segment + 1,
+                Envelope::MAX_VALUE
+            ),
+            LevelOutOfRange { segment, level } => write!(
+                f,
+                "Level {} is {level}, which is above {}",
+                segment + 1,
+                Envelope::MAX_VALUE
+            ),
+            InvalidSeconds { segment } => {
+                write!(f, "Time of segment {} is not valid", segment + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+/// A lossy approximation of an envelope as attack, decay, sustain and
+/// release, for synths with simpler envelopes. See [`Envelope::to_adsr`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Adsr {
+    pub attack_seconds: f32,
+    pub decay_seconds: f32,
+
+    /// Amplitude from 0.0 to 1.0.
+    pub sustain: f32,
+
+    pub release_seconds: f32,
+}
+
+/// A segment of an envelope, which moves at the rate until it reaches the
+/// level.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Segment {
+    pub rate: u8,
+    pub level: u8,
+}
+
+/// A four stage rate/level envelope. The segments are commonly called
+/// attack, first decay, second decay and release, with the envelope
+/// holding at the level of the second decay until the note is released.
 ///
 /// # Resources
 /// * [Discussion about levels and timing](https://groups.google.com/g/music-synthesizer-for-android/c/QD2KGEj7QIk?pli=1)
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "crate::serde_support::EnvelopeFields")
+)]
 pub struct Envelope {
     pub rates: [u8; Envelope::SEGMENT_COUNT],
     pub levels: [u8; Envelope::SEGMENT_COUNT],
 }
 
 impl Envelope {
-    const SEGMENT_COUNT: usize = 4;
+    pub const SEGMENT_COUNT: usize = 4;
+
+    /// Rates and levels are from 0 to 99.
+    pub const MAX_VALUE: u8 = 99;
+
+    /// Create an envelope, checking that the rates and levels are in range.
+    ///
+    /// A percussive envelope that attacks immediately and decays to silence:
+    ///
+    /// ```
+    /// use synthahol_dx7::Envelope;
+    /// let envelope = Envelope::new([99, 40, 30, 60], [99, 50, 0, 0]).unwrap();
+    /// assert_eq!(0, envelope.levels[2]);
+    /// assert!(Envelope::new([99, 40, 30, 60], [99, 50, 0, 100]).is_err());
+    /// ```
+    pub fn new(
+        rates: [u8; Envelope::SEGMENT_COUNT],
+        levels: [u8; Envelope::SEGMENT_COUNT],
+    ) -> Result<Self, EnvelopeError> {
+        if let Some((segment, &rate)) = rates
+            .iter()
+            .enumerate()
+            .find(|(_, &rate)| rate > Self::MAX_VALUE)
+        {
+            return Err(EnvelopeError::RateOutOfRange { segment, rate });
+        }
+        if let Some((segment, &level)) = levels
+            .iter()
+            .enumerate()
+            .find(|(_, &level)| level > Self::MAX_VALUE)
+        {
+            return Err(EnvelopeError::LevelOutOfRange { segment, level });
+        }
+        Ok(Self::from_rates_and_levels(rates, levels))
+    }
 
     /// Create a new envelope where each segment has the same rate and level.
     pub(crate) fn from_rate_and_level(rate: u8, level: u8) -> Self {
@@ -26,25 +198,548 @@ impl Envelope {
         Self { rates, levels }
     }
 
-    /// Arguments must be exactly [`Envelope::SEGMENT_COUNT`] long.
-    pub(crate) fn try_from_rates_and_levels(rates: &[u8], levels: &[u8]) -> Option<Self> {
-        (rates.len() == Envelope::SEGMENT_COUNT && levels.len() == Envelope::SEGMENT_COUNT).then(
-            || {
-                let mut fixed_rates = [0_u8; Envelope::SEGMENT_COUNT];
-                fixed_rates.copy_from_slice(rates);
-                let mut fixed_levels = [0_u8; Envelope::SEGMENT_COUNT];
-                fixed_levels.copy_from_slice(levels);
-                Self::from_rates_and_levels(fixed_rates, fixed_levels)
-            },
-        )
+    /// Create an envelope from slices that must be exactly
+    /// [`Envelope::SEGMENT_COUNT`] long. Rates and levels out of range are
+    /// kept, like when reading a preset, and are clamped when the preset is
+    /// normalized. Use [`Envelope::new`] to reject them.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Envelope, EnvelopeError};
+    /// let envelope = Envelope::try_from_rates_and_levels(&[99, 40, 30, 60], &[99, 50, 0, 0]);
+    /// assert!(envelope.is_ok());
+    /// assert_eq!(
+    ///     Err(EnvelopeError::IncorrectLength { rates: 2, levels: 4 }),
+    ///     Envelope::try_from_rates_and_levels(&[99, 40], &[99, 50, 0, 0])
+    /// );
+    /// ```
+    pub fn try_from_rates_and_levels(rates: &[u8], levels: &[u8]) -> Result<Self, EnvelopeError> {
+        let incorrect_length = || EnvelopeError::IncorrectLength {
+            rates: rates.len(),
+            levels: levels.len(),
+        };
+        let rates = rates.try_into().map_err(|_| incorrect_length())?;
+        let levels = levels.try_into().map_err(|_| incorrect_length())?;
+        Ok(Self::from_rates_and_levels(rates, levels))
     }
 
-    /// Clamp all parameters to valid ranges.
-    pub(crate) fn normalize(&self) -> Self {
-        Self {
-            rates: self.rates.map(|rate| rate.clamp(0, 99)),
-            levels: self.levels.map(|level| level.clamp(0, 99)),
+    /// Rate 1, of the attack segment.
+    pub fn attack_rate(&self) -> u8 {
+        self.rates[0]
+    }
+
+    pub fn set_attack_rate(&mut self, rate: u8) -> Result<(), EnvelopeError> {
+        self.set_rate(0, rate)
+    }
+
+    /// Rate 2, of the first decay segment.
+    pub fn decay1_rate(&self) -> u8 {
+        self.rates[1]
+    }
+
+    pub fn set_decay1_rate(&mut self, rate: u8) -> Result<(), EnvelopeError> {
+        self.set_rate(1, rate)
+    }
+
+    /// Rate 3, of the second decay segment.
+    pub fn decay2_rate(&self) -> u8 {
+        self.rates[2]
+    }
+
+    pub fn set_decay2_rate(&mut self, rate: u8) -> Result<(), EnvelopeError> {
+        self.set_rate(2, rate)
+    }
+
+    /// Rate 4, of the release segment.
+    pub fn release_rate(&self) -> u8 {
+        self.rates[3]
+    }
+
+    pub fn set_release_rate(&mut self, rate: u8) -> Result<(), EnvelopeError> {
+        self.set_rate(3, rate)
+    }
+
+    /// Level 1, reached at the end of the attack segment.
+    pub fn attack_level(&self) -> u8 {
+        self.levels[0]
+    }
+
+    pub fn set_attack_level(&mut self, level: u8) -> Result<(), EnvelopeError> {
+        self.set_level(0, level)
+    }
+
+    /// Level 2, reached at the end of the first decay segment.
+    pub fn decay1_level(&self) -> u8 {
+        self.levels[1]
+    }
+
+    pub fn set_decay1_level(&mut self, level: u8) -> Result<(), EnvelopeError> {
+        self.set_level(1, level)
+    }
+
+    /// Level 3, reached at the end of the second decay segment and held
+    /// until the note is released.
+    pub fn sustain_level(&self) -> u8 {
+        self.levels[2]
+    }
+
+    pub fn set_sustain_level(&mut self, level: u8) -> Result<(), EnvelopeError> {
+        self.set_level(2, level)
+    }
+
+    /// Level 4, reached at the end of the release segment. The envelope
+    /// also starts at this level.
+    pub fn release_level(&self) -> u8 {
+        self.levels[3]
+    }
+
+    pub fn set_release_level(&mut self, level: u8) -> Result<(), EnvelopeError> {
+        self.set_level(3, level)
+    }
+
+    fn set_rate(&mut self, segment: usize, rate: u8) -> Result<(), EnvelopeError> {
+        if rate > Self::MAX_VALUE {
+            return Err(EnvelopeError::RateOutOfRange { segment, rate });
+        }
+        self.rates[segment] = rate;
+        Ok(())
+    }
+
+    fn set_level(&mut self, segment: usize, level: u8) -> Result<(), EnvelopeError> {
+        if level > Self::MAX_VALUE {
+            return Err(EnvelopeError::LevelOutOfRange { segment, level });
+        }
+        self.levels[segment] = level;
+        Ok(())
+    }
+
+    /// Returns `true` if the level held while the note is played is at most
+    /// the threshold, so the sound dies away like a plucked or struck
+    /// instrument.
+    pub fn is_percussive(&self, threshold: u8) -> bool {
+        self.sustain_level() <= threshold
+    }
+
+    /// The rate and level of each segment.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Envelope, Segment};
+    /// let envelope = Envelope::new([99, 40, 30, 60], [99, 50, 0, 0]).unwrap();
+    /// assert_eq!(Segment { rate: 40, level: 50 }, envelope.segments()[1]);
+    /// ```
+    pub fn segments(&self) -> [Segment; Envelope::SEGMENT_COUNT] {
+        std::array::from_fn(|segment| Segment {
+            rate: self.rates[segment],
+            level: self.levels[segment],
+        })
+    }
+
+    /// How long a segment at a rate takes to move between two levels, using
+    /// the same model as [`Envelope::render`]. Rising segments jump to about
+    /// 40 dB below full level and then rise quicker the quieter they are.
+    ///
+    /// ```
+    /// use synthahol_dx7::Envelope;
+    /// let slow = Envelope::segment_seconds(20, 99, 0);
+    /// let fast = Envelope::segment_seconds(80, 99, 0);
+    /// assert!(slow > fast);
+    /// ```
+    pub fn segment_seconds(rate: u8, from_level: u8, to_level: u8) -> f32 {
+        let increment = Self::increment(rate);
+        let from = Self::log_level(from_level);
+        let to = Self::log_level(to_level);
+        let samples = if to > from {
+            // Rising speeds up as the level passes each doubling
+            let mut level = from.max(RISING_START_LOG_LEVEL);
+            let mut samples = 0.0;
+            while level < to {
+                let doublings = (level / 256.0).floor();
+                let next = ((doublings + 1.0) * 256.0).min(to);
+                samples += (next - level) / ((17.0 - doublings) * increment);
+                level = next;
+            }
+            samples
+        } else {
+            (from - to) / increment
+        };
+        (samples / RATE_SAMPLE_RATE) as f32
+    }
+
+    /// The rate whose segment between two levels takes closest to a number
+    /// of seconds. Segments that take no time are given rate 99.
+    pub fn rate_for_seconds(seconds: f32, from_level: u8, to_level: u8) -> u8 {
+        if Self::segment_seconds(0, from_level, to_level) == 0.0 {
+            return Self::MAX_VALUE;
         }
+        let distance = |rate: &u8| {
+            let found = Self::segment_seconds(*rate, from_level, to_level);
+            (found.ln() - seconds.max(f32::MIN_POSITIVE).ln()).abs()
+        };
+        (0..=Self::MAX_VALUE)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or(Self::MAX_VALUE)
+    }
+
+    /// Approximate the envelope as an ADSR envelope.
+    ///
+    /// The envelope starts at level 4. The attack lasts until the highest of
+    /// level 1 and 2, so a second segment that rises is part of the attack.
+    /// The decay is the rest of the time to reach level 3, which is the
+    /// sustain. The release is the time from level 3 to level 4, and any
+    /// level the release ends on is lost because ADSR envelopes release to
+    /// silence.
+    ///
+    /// ```
+    /// use synthahol_dx7::Envelope;
+    /// let envelope = Envelope::new([99, 40, 30, 60], [99, 50, 0, 0]).unwrap();
+    /// let adsr = envelope.to_adsr();
+    /// assert_eq!(0.0, adsr.sustain);
+    /// assert!(adsr.decay_seconds > adsr.attack_seconds);
+    /// ```
+    pub fn to_adsr(&self) -> Adsr {
+        let [rate1, rate2, rate3, rate4] = self.rates;
+        let [level1, level2, level3, level4] = self.levels;
+        let segment1 = Self::segment_seconds(rate1, level4, level1);
+        let segment2 = Self::segment_seconds(rate2, level1, level2);
+        let segment3 = Self::segment_seconds(rate3, level2, level3);
+        let (attack_seconds, decay_seconds) = if level2 > level1 {
+            (segment1 + segment2, segment3)
+        } else {
+            (segment1, segment2 + segment3)
+        };
+        Adsr {
+            attack_seconds,
+            decay_seconds,
+            sustain: Self::level_to_amplitude(level3),
+            release_seconds: Self::segment_seconds(rate4, level3, level4),
+        }
+    }
+
+    /// Create an envelope that approximates an ADSR envelope. The attack
+    /// rises from silence to full level, the decay falls to the sustain in
+    /// the second segment and the third segment holds it. A sustain of 0.0
+    /// is already silent when released so the release time has no effect.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Adsr, Envelope};
+    /// let adsr = Adsr {
+    ///     attack_seconds: 0.01,
+    ///     decay_seconds: 0.5,
+    ///     sustain: 0.25,
+    ///     release_seconds: 1.0,
+    /// };
+    /// let envelope = Envelope::from_adsr(&adsr);
+    /// assert_eq!(99, envelope.attack_level());
+    /// assert_eq!(0, envelope.release_level());
+    /// ```
+    pub fn from_adsr(adsr: &Adsr) -> Self {
+        let sustain_level = Self::amplitude_to_level(adsr.sustain);
+        let rates = [
+            Self::rate_for_seconds(adsr.attack_seconds, 0, Self::MAX_VALUE),
+            Self::rate_for_seconds(adsr.decay_seconds, Self::MAX_VALUE, sustain_level),
+            Self::MAX_VALUE,
+            Self::rate_for_seconds(adsr.release_seconds, sustain_level, 0),
+        ];
+        let levels = [Self::MAX_VALUE, sustain_level, sustain_level, 0];
+        Self::from_rates_and_levels(rates, levels)
+    }
+
+    /// The amplitude of an operator at full output level with this envelope,
+    /// from 0.0 to 1.0 for each sample. Segments 1 to 3 are played while
+    /// the gate is held and then the envelope stays at level 3. Segment 4
+    /// starts when the gate is released and the rendering stops when it
+    /// reaches level 4, or after `max_samples` for envelopes that sustain.
+    ///
+    /// The envelope starts at level 4 like the DX7. Levels and rates follow
+    /// the envelope generator of Dexed, which moves in steps of 0.0235 dB
+    /// and rises faster the quieter it is.
+    ///
+    /// ```
+    /// use synthahol_dx7::Envelope;
+    /// let envelope = Envelope {
+    ///     rates: [99, 50, 50, 60],
+    ///     levels: [99, 80, 70, 0],
+    /// };
+    /// let samples = envelope.render(44100.0, 0.5, 44100 * 10);
+    /// assert!(samples.len() > 22050);
+    /// assert_eq!(Some(&0.0), samples.last());
+    /// ```
+    pub fn render(&self, sample_rate: f32, gate_seconds: f32, max_samples: usize) -> Vec<f32> {
+        let gate_samples = (gate_seconds.max(0.0) * sample_rate) as usize;
+        let rate_ratio = RATE_SAMPLE_RATE / sample_rate as f64;
+
+        let mut level = Self::log_level(self.levels[Self::SEGMENT_COUNT - 1]);
+        let mut segment = 0;
+        let mut released = false;
+        let mut samples = Vec::new();
+        while samples.len() < max_samples {
+            if !released && samples.len() >= gate_samples {
+                released = true;
+                segment = Self::SEGMENT_COUNT - 1;
+            }
+
+            // Hold at level 3 until the gate is released
+            if segment < Self::SEGMENT_COUNT - 1 || released {
+                let target = Self::log_level(self.levels[segment]);
+                let increment = Self::increment(self.rates[segment]) * rate_ratio;
+                let reached = if target > level {
+                    level = level.max(RISING_START_LOG_LEVEL);
+                    level += (17.0 - (level / 256.0).floor()) * increment;
+                    level >= target
+                } else {
+                    level -= increment;
+                    level <= target
+                };
+                if reached {
+                    level = target;
+                    segment += 1;
+                }
+            }
+
+            samples.push(Self::amplitude(level));
+            if segment == Self::SEGMENT_COUNT {
+                break;
+            }
+        }
+        samples
+    }
+
+    /// The change in pitch of a level of the pitch envelope in semitones.
+    /// Level 50 doesn't change the pitch, 0 is four octaves down and 99 is
+    /// almost four octaves up. Levels above 99 are treated as 99.
+    ///
+    /// ```
+    /// use synthahol_dx7::Envelope;
+    /// assert_eq!(0.0, Envelope::pitch_offset_semitones(50));
+    /// assert_eq!(-48.0, Envelope::pitch_offset_semitones(0));
+    /// assert_eq!(12.0, Envelope::pitch_offset_semitones(82));
+    /// ```
+    pub fn pitch_offset_semitones(level: u8) -> f32 {
+        let level = level.min(Self::MAX_VALUE) as usize;
+        PITCH_LEVELS[level] as f32 * PITCH_LEVEL_SEMITONES
+    }
+
+    /// The level of the pitch envelope closest to a change in pitch in
+    /// semitones. The inverse of [`Envelope::pitch_offset_semitones`].
+    pub fn pitch_level(semitones: f32) -> u8 {
+        let distance = |level: &u8| (Self::pitch_offset_semitones(*level) - semitones).abs();
+        (0..=Self::MAX_VALUE)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or(50)
+    }
+
+    /// The amplitude of a level from 0.0 to 1.0, relative to level 99.
+    /// Level 0 is silent.
+    ///
+    /// Levels follow the table of the envelope generator reverse
+    /// engineered for Dexed. Each pair of levels from 20 up is 1.5 dB and
+    /// the levels below 20 fall faster.
+    ///
+    /// ```
+    /// use synthahol_dx7::Envelope;
+    /// assert_eq!(1.0, Envelope::level_to_amplitude(99));
+    /// assert_eq!(0.0, Envelope::level_to_amplitude(0));
+    /// ```
+    pub fn level_to_amplitude(level: u8) -> f32 {
+        Self::amplitude(Self::log_level(level))
+    }
+
+    /// The gain of a level in decibels relative to level 99. Level 0 is
+    /// negative infinity.
+    pub fn level_to_db(level: u8) -> f32 {
+        20.0 * Self::level_to_amplitude(level).log10()
+    }
+
+    /// The lowest level closest to an amplitude from 0.0 to 1.0.
+    pub fn amplitude_to_level(amplitude: f32) -> u8 {
+        let distance = |level: &u8| {
+            let found = Self::level_to_amplitude(*level);
+            if amplitude <= 0.0 || found == 0.0 {
+                (amplitude.max(0.0) - found).abs()
+            } else {
+                (amplitude.log2() - found.log2()).abs()
+            }
+        };
+        (0..=Self::MAX_VALUE)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or_default()
+    }
+
+    /// Output of the envelope generator for a level, in 1/256 of a doubling
+    /// of amplitude.
+    fn log_level(level: u8) -> f64 {
+        let level = level.min(99);
+        let scaled = match LOW_LEVELS.get(level as usize) {
+            Some(scaled) => *scaled,
+            None => level + 28,
+        };
+        ((scaled >> 1) as f64 * 64.0 - 192.0).max(MIN_LOG_LEVEL)
+    }
+
+    /// Change of the output of the envelope generator for each sample.
+    fn increment(rate: u8) -> f64 {
+        let rate = (rate.min(99) as i32 * 41) >> 6;
+        (4 + (rate & 3)) as f64 * 2.0_f64.powi(rate >> 2) / 16384.0
+    }
+
+    fn amplitude(log_level: f64) -> f32 {
+        if log_level <= MIN_LOG_LEVEL {
+            0.0
+        } else {
+            2.0_f64.powf((log_level - MAX_LOG_LEVEL) / 256.0) as f32
+        }
+    }
+
+    /// Clamp all parameters to valid ranges, recording the ones that were
+    /// out of range.
+    pub(crate) fn normalize_with(
+        &self,
+        normalizer: &mut Normalizer,
+        names: &EnvelopeNames,
+    ) -> Self {
+        let mut envelope = *self;
+        for (rate, name) in envelope.rates.iter_mut().zip(names.rates) {
+            *rate = normalizer.clamp(name, *rate, 99);
+        }
+        for (level, name) in envelope.levels.iter_mut().zip(names.levels) {
+            *level = normalizer.clamp(name, *level, 99);
+        }
+        envelope
+    }
+}
+
+/// Shows the rates and then the levels like "R 99 62 35 48 / L 99 85 70 0".
+/// The alternate form labels each segment with its rate and level like
+/// "A 99/99 D1 62/85 D2 35/70 R 48/0".
+impl Display for Envelope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let join = |values: &[u8]| {
+            values
+                .iter()
+                .map(u8::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        if f.alternate() {
+            let labels = ["A", "D1", "D2", "R"];
+            let segments: Vec<String> = labels
+                .iter()
+                .zip(self.segments())
+                .map(|(label, segment)| format!("{label} {}/{}", segment.rate, segment.level))
+                .collect();
+            f.write_str(&segments.join(" "))
+        } else {
+            write!(f, "R {} / L {}", join(&self.rates), join(&self.levels))
+        }
+    }
+}
+
+/// Builds an envelope one segment at a time, starting from
+/// [`Envelope::default`]. Rates and levels are checked when the envelope is
+/// built.
+///
+/// ```
+/// use synthahol_dx7::EnvelopeBuilder;
+/// let envelope = EnvelopeBuilder::new()
+///     .attack(99, 99)
+///     .decay1(60, 80)
+///     .decay2(40, 70)
+///     .release_seconds(2.0)
+///     .build()
+///     .unwrap();
+/// assert_eq!([99, 80, 70, 99], envelope.levels);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EnvelopeBuilder {
+    envelope: Envelope,
+
+    /// Times that replace the rates once the levels are known.
+    seconds: [Option<f32>; Envelope::SEGMENT_COUNT],
+}
+
+impl EnvelopeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn segment(mut self, segment: usize, rate: u8, level: u8) -> Self {
+        self.envelope.rates[segment] = rate;
+        self.envelope.levels[segment] = level;
+        self.seconds[segment] = None;
+        self
+    }
+
+    fn segment_seconds(mut self, segment: usize, seconds: f32) -> Self {
+        self.seconds[segment] = Some(seconds);
+        self
+    }
+
+    pub fn attack(self, rate: u8, level: u8) -> Self {
+        self.segment(0, rate, level)
+    }
+
+    pub fn decay1(self, rate: u8, level: u8) -> Self {
+        self.segment(1, rate, level)
+    }
+
+    pub fn decay2(self, rate: u8, level: u8) -> Self {
+        self.segment(2, rate, level)
+    }
+
+    pub fn release(self, rate: u8, level: u8) -> Self {
+        self.segment(3, rate, level)
+    }
+
+    /// Set the rate of the attack to the one closest to a time in seconds,
+    /// moving from the release level to the attack level.
+    pub fn attack_seconds(self, seconds: f32) -> Self {
+        self.segment_seconds(0, seconds)
+    }
+
+    pub fn decay1_seconds(self, seconds: f32) -> Self {
+        self.segment_seconds(1, seconds)
+    }
+
+    pub fn decay2_seconds(self, seconds: f32) -> Self {
+        self.segment_seconds(2, seconds)
+    }
+
+    pub fn release_seconds(self, seconds: f32) -> Self {
+        self.segment_seconds(3, seconds)
+    }
+
+    /// Start from an envelope that strikes immediately and fades to
+    /// silence while the note is held.
+    pub fn percussive(self) -> Self {
+        self.attack(99, 99)
+            .decay1(45, 60)
+            .decay2(35, 0)
+            .release(50, 0)
+    }
+
+    /// Start from an envelope that is at full level while the note is held
+    /// and stops quickly, like an organ.
+    pub fn organ(self) -> Self {
+        self.attack(99, 99)
+            .decay1(99, 99)
+            .decay2(99, 99)
+            .release(70, 0)
+    }
+
+    pub fn build(&self) -> Result<Envelope, EnvelopeError> {
+        let mut envelope = Envelope::new(self.envelope.rates, self.envelope.levels)?;
+        for (segment, seconds) in self.seconds.iter().enumerate() {
+            let Some(seconds) = *seconds else {
+                continue;
+            };
+            if !(seconds >= 0.0 && seconds.is_finite()) {
+                return Err(EnvelopeError::InvalidSeconds { segment });
+            }
+            let from =
+                envelope.levels[(segment + Envelope::SEGMENT_COUNT - 1) % Envelope::SEGMENT_COUNT];
+            let to = envelope.levels[segment];
+            envelope.rates[segment] = Envelope::rate_for_seconds(seconds, from, to);
+        }
+        Ok(envelope)
     }
 }
 
@@ -56,3 +751,350 @@ impl Default for Envelope {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new() {
+        let envelope = Envelope::new([99, 40, 30, 60], [99, 50, 0, 0]).unwrap();
+        assert_eq!([99, 40, 30, 60], envelope.rates);
+        assert_eq!([99, 50, 0, 0], envelope.levels);
+        assert_eq!(
+            Err(EnvelopeError::RateOutOfRange {
+                segment: 1,
+                rate: 100
+            }),
+            Envelope::new([99, 100, 30, 60], [99, 50, 0, 0])
+        );
+        assert_eq!(
+            Err(EnvelopeError::LevelOutOfRange {
+                segment: 3,
+                level: 255
+            }),
+            Envelope::new([99, 40, 30, 60], [99, 50, 0, 255])
+        );
+        assert_eq!(
+            "Level 4 is 255, which is above 99",
+            EnvelopeError::LevelOutOfRange {
+                segment: 3,
+                level: 255
+            }
+            .to_string()
+        );
+
+        assert_eq!(
+            Ok(Envelope {
+                rates: [99, 40, 30, 100],
+                levels: [99, 50, 0, 0],
+            }),
+            Envelope::try_from_rates_and_levels(&[99, 40, 30, 100], &[99, 50, 0, 0])
+        );
+        assert_eq!(
+            Err(EnvelopeError::IncorrectLength {
+                rates: 4,
+                levels: 5
+            }),
+            Envelope::try_from_rates_and_levels(&[99, 40, 30, 60], &[99, 50, 0, 0, 0])
+        );
+    }
+
+    #[test]
+    fn segments() {
+        let mut envelope = Envelope::new([1, 2, 3, 4], [5, 6, 7, 8]).unwrap();
+        assert_eq!(
+            [1, 2, 3, 4],
+            [
+                envelope.attack_rate(),
+                envelope.decay1_rate(),
+                envelope.decay2_rate(),
+                envelope.release_rate()
+            ]
+        );
+        assert_eq!(
+            [5, 6, 7, 8],
+            [
+                envelope.attack_level(),
+                envelope.decay1_level(),
+                envelope.sustain_level(),
+                envelope.release_level()
+            ]
+        );
+        assert_eq!(Segment { rate: 3, level: 7 }, envelope.segments()[2]);
+
+        envelope.set_attack_rate(11).unwrap();
+        envelope.set_decay1_rate(12).unwrap();
+        envelope.set_decay2_rate(13).unwrap();
+        envelope.set_release_rate(99).unwrap();
+        envelope.set_attack_level(15).unwrap();
+        envelope.set_decay1_level(16).unwrap();
+        envelope.set_sustain_level(17).unwrap();
+        envelope.set_release_level(0).unwrap();
+        assert_eq!([11, 12, 13, 99], envelope.rates);
+        assert_eq!([15, 16, 17, 0], envelope.levels);
+
+        assert_eq!(
+            Err(EnvelopeError::RateOutOfRange {
+                segment: 1,
+                rate: 100
+            }),
+            envelope.set_decay1_rate(100)
+        );
+        assert_eq!(
+            Err(EnvelopeError::LevelOutOfRange {
+                segment: 2,
+                level: 255
+            }),
+            envelope.set_sustain_level(255)
+        );
+        assert_eq!([11, 12, 13, 99], envelope.rates);
+        assert_eq!([15, 16, 17, 0], envelope.levels);
+    }
+
+    #[test]
+    fn display() {
+        let envelope = Envelope::default();
+        assert_eq!("R 99 99 99 99 / L 99 99 99 99", envelope.to_string());
+        assert_eq!("A 99/99 D1 99/99 D2 99/99 R 99/99", format!("{envelope:#}"));
+
+        let envelope = Envelope::new([99, 62, 35, 48], [99, 85, 70, 0]).unwrap();
+        assert_eq!("R 99 62 35 48 / L 99 85 70 0", envelope.to_string());
+        assert_eq!("A 99/99 D1 62/85 D2 35/70 R 48/0", format!("{envelope:#}"));
+    }
+
+    #[test]
+    fn percussive() {
+        let envelope = Envelope::new([99, 40, 30, 60], [99, 50, 0, 0]).unwrap();
+        assert!(envelope.is_percussive(0));
+        assert!(!Envelope::default().is_percussive(98));
+        assert!(Envelope::default().is_percussive(99));
+    }
+
+    #[test]
+    fn level_to_amplitude() {
+        assert_eq!(0.0, Envelope::level_to_db(99));
+        assert_eq!(f32::NEG_INFINITY, Envelope::level_to_db(0));
+        assert!((Envelope::level_to_db(97) + 1.5).abs() < 0.01);
+        assert!(Envelope::level_to_db(1) < -80.0);
+
+        for level in 0..=99 {
+            let amplitude = Envelope::level_to_amplitude(level);
+            assert!(Envelope::amplitude_to_level(amplitude).abs_diff(level) <= 1);
+            if level > 0 {
+                assert!(amplitude >= Envelope::level_to_amplitude(level - 1));
+            }
+        }
+        assert_eq!(0, Envelope::amplitude_to_level(0.0));
+        assert_eq!(0, Envelope::amplitude_to_level(-1.0));
+        assert_eq!(98, Envelope::amplitude_to_level(2.0));
+    }
+
+    #[test]
+    fn segment_seconds() {
+        // The time matches the rendering
+        let envelope = Envelope::new([40, 99, 99, 30], [99, 99, 99, 0]).unwrap();
+        let samples = envelope.render(44100.0, 10.0, 44100 * 100);
+        let attack = samples.iter().position(|&sample| sample == 1.0).unwrap();
+        let seconds = Envelope::segment_seconds(40, 0, 99);
+        assert!((attack as f32 - seconds * 44100.0).abs() < 2.0);
+        let release = samples.len() - 44100 * 10;
+        let seconds = Envelope::segment_seconds(30, 99, 0);
+        assert!((release as f32 - seconds * 44100.0).abs() < 2.0);
+
+        assert_eq!(0.0, Envelope::segment_seconds(50, 70, 70));
+        assert_eq!(99, Envelope::rate_for_seconds(1.0, 70, 70));
+        for rate in 0..=99 {
+            let seconds = Envelope::segment_seconds(rate, 99, 20);
+            let found = Envelope::rate_for_seconds(seconds, 99, 20);
+            assert_eq!(seconds, Envelope::segment_seconds(found, 99, 20));
+        }
+    }
+
+    #[test]
+    fn adsr() {
+        let close = |expected: f32, found: f32| (found - expected).abs() <= expected * 0.15 + 0.002;
+        for (attack, decay, sustain, release) in [
+            (0.01, 0.5, 0.25, 1.0),
+            (0.2, 2.0, 0.0, 0.3),
+            (1.0, 0.1, 0.5, 4.0),
+        ] {
+            let adsr = Adsr {
+                attack_seconds: attack,
+                decay_seconds: decay,
+                sustain,
+                release_seconds: release,
+            };
+            let found = Envelope::from_adsr(&adsr).to_adsr();
+            assert!(close(attack, found.attack_seconds), "{adsr:?} {found:?}");
+            assert!(close(decay, found.decay_seconds), "{adsr:?} {found:?}");
+            assert!((found.sustain - sustain).abs() < 0.05, "{adsr:?} {found:?}");
+            if sustain > 0.0 {
+                assert!(close(release, found.release_seconds), "{adsr:?} {found:?}");
+            } else {
+                // Already silent when released
+                assert_eq!(0.0, found.release_seconds);
+            }
+        }
+
+        // A second segment that rises is part of the attack
+        let envelope = Envelope::new([50, 60, 70, 80], [50, 99, 80, 0]).unwrap();
+        let adsr = envelope.to_adsr();
+        assert_eq!(
+            Envelope::segment_seconds(50, 0, 50) + Envelope::segment_seconds(60, 50, 99),
+            adsr.attack_seconds
+        );
+        assert_eq!(Envelope::segment_seconds(70, 99, 80), adsr.decay_seconds);
+
+        // A third segment that rises is part of the decay
+        let envelope = Envelope::new([50, 60, 70, 80], [99, 50, 80, 0]).unwrap();
+        let adsr = envelope.to_adsr();
+        assert_eq!(Envelope::segment_seconds(50, 0, 99), adsr.attack_seconds);
+        assert_eq!(
+            Envelope::segment_seconds(60, 99, 50) + Envelope::segment_seconds(70, 50, 80),
+            adsr.decay_seconds
+        );
+
+        // The release level is lost
+        let envelope = Envelope::new([99, 99, 99, 99], [99, 99, 99, 99]).unwrap();
+        assert_eq!(
+            Adsr {
+                attack_seconds: 0.0,
+                decay_seconds: 0.0,
+                sustain: 1.0,
+                release_seconds: 0.0
+            },
+            envelope.to_adsr()
+        );
+    }
+
+    #[test]
+    fn builder() {
+        let envelope = EnvelopeBuilder::new().build().unwrap();
+        assert_eq!(Envelope::default(), envelope);
+
+        let envelope = EnvelopeBuilder::new()
+            .attack(90, 99)
+            .decay1(80, 70)
+            .decay2(70, 60)
+            .release(60, 0)
+            .build()
+            .unwrap();
+        assert_eq!([90, 80, 70, 60], envelope.rates);
+        assert_eq!([99, 70, 60, 0], envelope.levels);
+
+        let envelope = EnvelopeBuilder::new().percussive().build().unwrap();
+        assert!(envelope.is_percussive(0));
+        let envelope = EnvelopeBuilder::new().organ().build().unwrap();
+        assert!(!envelope.is_percussive(98));
+
+        // Times are converted once the levels are known
+        let envelope = EnvelopeBuilder::new()
+            .attack_seconds(0.5)
+            .release(99, 0)
+            .decay2(99, 80)
+            .release_seconds(1.5)
+            .build()
+            .unwrap();
+        let attack = Envelope::segment_seconds(envelope.attack_rate(), 0, 99);
+        assert!((attack - 0.5).abs() < 0.1);
+        let release = Envelope::segment_seconds(envelope.release_rate(), 80, 0);
+        assert!((release - 1.5).abs() < 0.25);
+
+        // Setting the rate replaces the time
+        let envelope = EnvelopeBuilder::new()
+            .attack_seconds(0.5)
+            .attack(10, 99)
+            .build()
+            .unwrap();
+        assert_eq!(10, envelope.attack_rate());
+
+        assert_eq!(
+            Err(EnvelopeError::RateOutOfRange {
+                segment: 0,
+                rate: 100
+            }),
+            EnvelopeBuilder::new().attack(100, 99).build()
+        );
+        assert_eq!(
+            Err(EnvelopeError::LevelOutOfRange {
+                segment: 3,
+                level: 120
+            }),
+            EnvelopeBuilder::new().release(50, 120).build()
+        );
+        assert_eq!(
+            Err(EnvelopeError::InvalidSeconds { segment: 1 }),
+            EnvelopeBuilder::new().decay1_seconds(-1.0).build()
+        );
+        assert_eq!(
+            Err(EnvelopeError::InvalidSeconds { segment: 2 }),
+            EnvelopeBuilder::new().decay2_seconds(f32::NAN).build()
+        );
+    }
+
+    #[test]
+    fn pitch() {
+        assert_eq!(0.0, Envelope::pitch_offset_semitones(50));
+        assert_eq!(-48.0, Envelope::pitch_offset_semitones(0));
+        assert!((Envelope::pitch_offset_semitones(99) - 48.0).abs() < 0.5);
+        assert_eq!(
+            Envelope::pitch_offset_semitones(99),
+            Envelope::pitch_offset_semitones(127)
+        );
+        for level in 0..=99 {
+            let semitones = Envelope::pitch_offset_semitones(level);
+            assert_eq!(level, Envelope::pitch_level(semitones));
+            if level > 0 {
+                assert!(semitones > Envelope::pitch_offset_semitones(level - 1));
+            }
+        }
+        assert_eq!(99, Envelope::pitch_level(100.0));
+        assert_eq!(0, Envelope::pitch_level(-100.0));
+    }
+
+    #[test]
+    fn render() {
+        // The default envelope is at full level until the gate is released
+        let samples = Envelope::default().render(48000.0, 0.5, 48000);
+        assert_eq!(24001, samples.len());
+        assert!(samples.iter().all(|&sample| sample == 1.0));
+
+        // Sustain at level 3, then release towards level 4
+        let envelope = Envelope {
+            rates: [80, 60, 70, 40],
+            levels: [99, 90, 70, 0],
+        };
+        let samples = envelope.render(44100.0, 1.0, 44100 * 60);
+        let sustain = Envelope::amplitude(Envelope::log_level(70));
+        assert_eq!(sustain, samples[44099]);
+        assert!(samples[..44100].iter().all(|&sample| sample <= 1.0));
+        assert!(samples[44100..]
+            .windows(2)
+            .all(|pair| pair[1] <= pair[0] && pair[1] < sustain));
+        assert_eq!(Some(&0.0), samples.last());
+        assert!(samples.len() < 44100 * 60);
+
+        // Rendering is deterministic and halving the sample rate halves the
+        // length
+        assert_eq!(samples, envelope.render(44100.0, 1.0, 44100 * 60));
+        let half = envelope.render(22050.0, 1.0, 44100 * 60);
+        assert!(half.len().abs_diff(samples.len() / 2) < 2);
+
+        // Envelopes that sustain are limited
+        let samples = envelope.render(44100.0, f32::INFINITY, 1000);
+        assert_eq!(1000, samples.len());
+        assert!(envelope.render(44100.0, 1.0, 0).is_empty());
+    }
+
+    #[test]
+    fn levels() {
+        assert_eq!(0.0, Envelope::amplitude(Envelope::log_level(0)));
+        assert_eq!(1.0, Envelope::amplitude(Envelope::log_level(99)));
+
+        // Each level above 20 is 0.75 dB, in pairs
+        let ratio = Envelope::amplitude(Envelope::log_level(91))
+            / Envelope::amplitude(Envelope::log_level(89));
+        assert!((ratio - 2.0_f32.powf(0.25)).abs() < 0.0001);
+    }
+}
@@ -0,0 +1,257 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use crate::{Format, OutOfRange, PresetName};
+
+/// Errors from reading and writing presets.
+///
+/// Errors can be converted into a [`std::io::Error`] to ease migrating from
+/// earlier versions of the crate.
+#[derive(Debug)]
+pub enum Error {
+    /// The header is not for the expected type of message.
+    IncorrectHeader {
+        found: [u8; Format::HEADER_LEN],
+    },
+
+    /// The data identifier at the start of a universal bulk dump is not for
+    /// the expected type of data.
+    IncorrectIdentifier {
+        found: Vec<u8>,
+    },
+
+    /// The byte count in the header is not the length of the message body.
+    IncorrectByteCount {
+        expected: usize,
+        declared: usize,
+    },
+
+    /// The checksum in the message does not match the data.
+    ChecksumMismatch {
+        expected: u8,
+        computed: u8,
+    },
+
+    /// The message does not end with the End of SysEx marker.
+    MissingEndOfSysex,
+
+    /// No End of SysEx marker was found within the longest possible
+    /// message.
+    MessageTooLong {
+        limit: usize,
+    },
+
+    /// The input continues after the end of the message.
+    TrailingData,
+
+    /// The input ended and more bytes were needed.
+    UnexpectedEof {
+        needed: usize,
+    },
+
+    /// The length of data without a header is not correct.
+    IncorrectLength {
+        expected: usize,
+        found: usize,
+    },
+
+    /// Data expected without a header looks like a complete SysEx message.
+    UnexpectedSysex,
+
+    /// The envelope rates and levels are not the correct length.
+    InvalidEnvelope,
+
+    InvalidWaveform(u8),
+
+    /// The keyboard level scaling curve of an operator is not from 0 to 3.
+    InvalidScalingCurve(u8),
+
+    /// The play mode of a performance is not single, dual or split.
+    InvalidPlayMode(u8),
+
+    /// A parameter is outside of its valid range when reading strictly.
+    OutOfRange(OutOfRange),
+
+    /// A voice could not be read. The offset is of the byte that could not
+    /// be decoded, or of the start of the voice when a parameter is out of
+    /// range.
+    Voice {
+        index: usize,
+        name: Option<PresetName>,
+        offset: u64,
+        source: Box<Error>,
+    },
+
+    /// An error at a byte offset into the input.
+    At {
+        offset: u64,
+        source: Box<Error>,
+    },
+
+    /// More presets were supplied than fit in a bank.
+    TooManyPresets {
+        count: usize,
+    },
+
+    /// The MIDI channel is not between 0 and 15.
+    InvalidChannel(u8),
+
+    /// JSON could not be read. The path is of the field with the problem,
+    /// such as `presets.3.algorithm_id`.
+    Json {
+        path: String,
+        message: String,
+    },
+
+    Io(io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use Error::*;
+        match self {
+            IncorrectHeader { found } => write!(f, "Incorrect header {found:02X?}"),
+            IncorrectIdentifier { found } => write!(
+                f,
+                "Incorrect data identifier {:?}",
+                String::from_utf8_lossy(found)
+            ),
+            IncorrectByteCount { expected, declared } => write!(
+                f,
+                "Expected a {expected} byte body, header declares {declared}"
+            ),
+            ChecksumMismatch { expected, computed } => write!(
+                f,
+                "Computed checksum {computed} does not match expected checksum {expected}"
+            ),
+            MissingEndOfSysex => f.write_str("Missing End of SysEx marker"),
+            MessageTooLong { limit } => {
+                write!(f, "No End of SysEx marker in the first {limit} bytes")
+            }
+            TrailingData => f.write_str("Unexpected data after the End of SysEx marker"),
+            UnexpectedEof { needed } => {
+                write!(f, "Unexpected end of input, {needed} more bytes needed")
+            }
+            IncorrectLength { expected, found } => {
+                write!(f, "Expected {expected} bytes, not {found}")
+            }
+            UnexpectedSysex => f.write_str("Data is a SysEx message, use Bank::read instead"),
+            InvalidEnvelope => f.write_str("Invalid envelope"),
+            InvalidWaveform(waveform) => write!(f, "Invalid LFO waveform {waveform}"),
+            InvalidScalingCurve(curve) => write!(f, "Invalid scaling curve {curve}"),
+            OutOfRange(out_of_range) => out_of_range.fmt(f),
+            InvalidPlayMode(mode) => write!(f, "Invalid play mode {mode}"),
+            Voice {
+                index,
+                name: Some(name),
+                offset,
+                source,
+            } => write!(
+                f,
+                "Voice {index} ('{name}') at offset {offset:#X}: {source}"
+            ),
+            Voice {
+                index,
+                name: None,
+                offset,
+                source,
+            } => write!(f, "Voice {index} at offset {offset:#X}: {source}"),
+            At { offset, source } => write!(f, "At offset {offset:#X}: {source}"),
+            TooManyPresets { count } => write!(
+                f,
+                "A bank can contain at most {} presets, not {count}",
+                crate::Bank::PRESET_COUNT
+            ),
+            InvalidChannel(channel) => write!(f, "MIDI channel {channel} is not between 0 and 15"),
+            Json { path, message } if path.is_empty() || path == "." => f.write_str(message),
+            Json { path, message } => write!(f, "{path}: {message}"),
+            Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error {
+    /// The underlying error without the voice or offset it occurred at.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use synthahol_dx7::{Bank, Error};
+    ///
+    /// let error = Bank::load(&mut [0xF0, 0x43].as_slice(), None).unwrap_err();
+    /// assert!(matches!(error.root_cause(), Error::UnexpectedEof { .. }));
+    /// ```
+    pub fn root_cause(&self) -> &Error {
+        match self {
+            Error::Voice { source, .. } | Error::At { source, .. } => source.root_cause(),
+            _ => self,
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Voice { source, .. } | Error::At { source, .. } => Some(source.as_ref()),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match err.root_cause() {
+            Error::Io(err) => err.kind(),
+            Error::UnexpectedEof { .. } => io::ErrorKind::UnexpectedEof,
+            Error::TooManyPresets { .. } | Error::InvalidChannel(_) => io::ErrorKind::InvalidInput,
+            _ => io::ErrorKind::InvalidData,
+        };
+        match err {
+            Error::Io(err) => err,
+            err => io::Error::new(kind, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn into_io_error() {
+        let err: io::Error = Error::UnexpectedEof { needed: 3 }.into();
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+
+        let err: io::Error = Error::TooManyPresets { count: 33 }.into();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        assert_eq!(
+            "A bank can contain at most 32 presets, not 33",
+            err.to_string()
+        );
+
+        let err: io::Error = Error::MissingEndOfSysex.into();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+        let original = io::Error::new(io::ErrorKind::NotFound, "missing");
+        let err: io::Error = Error::from(original).into();
+        assert_eq!(io::ErrorKind::NotFound, err.kind());
+
+        let err: io::Error = Error::At {
+            offset: 0x1006,
+            source: Box::new(Error::UnexpectedEof { needed: 2 }),
+        }
+        .into();
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+        assert_eq!(
+            "At offset 0x1006: Unexpected end of input, 2 more bytes needed",
+            err.to_string()
+        );
+    }
+}
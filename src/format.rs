@@ -1,12 +1,79 @@
 use std::path::Path;
 
-use crate::SYSEX_HEADER;
+use crate::performance::PERFORMANCE_IDENTIFIER;
+use crate::read::{byte_count, format_number};
+use crate::supplement::SUPPLEMENT_SYSEX_HEADER;
+use crate::tuning::{KEYBOARD_TUNING_IDENTIFIER, OCTAVE_TUNING_IDENTIFIER};
+use crate::tx802::TX802_PERFORMANCE_IDENTIFIER;
+use crate::voice::{UNPACKED_VOICE_LEN, VOICE_SYSEX_HEADER};
+use crate::{Bank, SYSEX_HEADER};
 
-const FORMAT_IDENTIFIER: [u8; 6] = SYSEX_HEADER;
+/// Headers that files can start with. The DX7II and TX802 may send the
+/// additional voice bank before the bank.
+const FORMAT_IDENTIFIERS: [[u8; Format::HEADER_LEN]; 2] = [SYSEX_HEADER, SUPPLEMENT_SYSEX_HEADER];
+
+/// Format number of universal bulk dumps, whose body starts with a
+/// classification such as `LM  8952PM` that identifies the data.
+const UNIVERSAL_FORMAT: u8 = 0x7E;
+
+/// The checksum and End of SysEx marker that end a message.
+const FOOTER_LEN: usize = 2;
+
+/// The kinds of Yamaha bulk dump that [`Format::detect`] recognizes. More
+/// kinds, such as function data, may be recognized in the future.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum FormatKind {
+    /// A bank of 32 voices, read by [`Bank::load`](crate::Bank::load).
+    VoiceBank32,
+
+    /// A single voice, read by [`Voice::load`](crate::Voice::load).
+    SingleVoice,
+
+    /// The DX7II additional voice bank, read by
+    /// [`Supplement::load`](crate::Supplement::load).
+    Dx7IISupplement,
+
+    /// A DX7II performance bank or a TX802 performance.
+    Performance,
+
+    /// Read by [`MicroTuning::load`](crate::MicroTuning::load).
+    MicroTuning,
+
+    /// Any other message, including ones from other manufacturers.
+    Unknown,
+}
 
 pub struct Format;
 
 impl Format {
+    /// The number of bytes in the header of a message, from the start of
+    /// SysEx marker to the byte count.
+    pub const HEADER_LEN: usize = 6;
+
+    /// The number of bytes of a voice in a bank.
+    pub const PACKED_VOICE_LEN: usize = 128;
+
+    /// The number of bytes of voices in a bank, between the header and the
+    /// checksum.
+    pub const BANK_BODY_LEN: usize = Self::PACKED_VOICE_LEN * Bank::PRESET_COUNT;
+
+    /// The number of bytes in a bank message, which is the size of a file
+    /// with one bank.
+    pub const BANK_MESSAGE_LEN: usize = Self::HEADER_LEN + Self::BANK_BODY_LEN + FOOTER_LEN;
+
+    /// The number of bytes in a single voice message.
+    pub const VOICE_MESSAGE_LEN: usize = Self::HEADER_LEN + UNPACKED_VOICE_LEN + FOOTER_LEN;
+
+    /// The number of bytes in the longest bulk dump message. The byte count
+    /// in the header has 14 bits.
+    pub const MAX_MESSAGE_LEN: usize = Self::HEADER_LEN + 0x3FFF + FOOTER_LEN;
+
+    /// The number of bytes [`Format::is_format`], [`Format::is_single_voice`]
+    /// and [`Format::matches`] need to recognize a file, which is the length
+    /// of the SysEx header.
+    pub const MIN_PROBE_LEN: usize = Self::HEADER_LEN;
+
     pub fn name() -> &'static str {
         "Yamaha DX7"
     }
@@ -15,18 +82,126 @@ impl Format {
         "syx"
     }
 
-    pub fn is_format(_path: &Path, header: &[u8]) -> bool {
-        header.starts_with(&FORMAT_IDENTIFIER)
+    /// Returns `true` if the data starts with a bank on any MIDI channel.
+    ///
+    /// The header must be a Yamaha bulk dump, `F0 43 0n` where `n` is the
+    /// channel, followed by the format number and byte count of a bank of 32
+    /// voices, `09 20 00` for 4096 bytes. The additional voice bank that the
+    /// DX7II and TX802 send before a bank, `06 08 60` for 1120 bytes, is
+    /// also accepted.
+    ///
+    /// At least [`Format::MIN_PROBE_LEN`] bytes are needed. When the data is
+    /// long enough to hold the whole message the End of SysEx marker must
+    /// follow the body and checksum. Shorter data is assumed to be the start
+    /// of a file, so a truncated file is only rejected once it is read.
+    pub fn is_format(_path: &Path, data: &[u8]) -> bool {
+        FORMAT_IDENTIFIERS
+            .iter()
+            .any(|expected| starts_with_dump(data, expected))
+    }
+
+    /// Returns `true` if the data starts with a single voice dump, also known
+    /// as VCED, on any MIDI channel.
+    ///
+    /// The header must be `F0 43 0n 00 01 1B`, a Yamaha bulk dump on channel
+    /// `n` with format number 0 and a byte count of 155. Parameter changes,
+    /// which start with `F0 43 1n`, don't match. As with
+    /// [`Format::is_format`], the End of SysEx marker must follow the voice
+    /// when the data is long enough to hold the whole message.
+    pub fn is_single_voice(data: &[u8]) -> bool {
+        starts_with_dump(data, &VOICE_SYSEX_HEADER)
     }
+
+    /// Returns `true` if the data starts with a bank or a single voice on any
+    /// MIDI channel, the messages recognized by [`Format::is_format`] and
+    /// [`Format::is_single_voice`].
+    ///
+    /// ```
+    /// use synthahol_dx7::Format;
+    /// let bank = std::fs::read("tests/rom1a.syx").unwrap();
+    /// let voice = std::fs::read("tests/tub_bells.syx").unwrap();
+    /// assert!(Format::matches(&bank));
+    /// assert!(Format::matches(&voice));
+    /// assert!(!Format::matches(&[0xF0, 0x43, 0x10, 0x01, 0x06, 0x04, 0xF7]));
+    /// ```
+    pub fn matches(data: &[u8]) -> bool {
+        Self::is_format(Path::new(""), data) || Self::is_single_voice(data)
+    }
+
+    /// The kind of message the data starts with, to choose how to read it.
+    /// Returns `None` if the data is too short to tell, which is less than
+    /// [`Format::MIN_PROBE_LEN`] bytes or 16 bytes for a universal bulk
+    /// dump.
+    ///
+    /// Yamaha bulk dumps, `F0 43 0n` on channel `n`, are told apart by the
+    /// format number in the fourth byte. Universal bulk dumps, with format
+    /// number 0x7E, are told apart by the classification at the start of
+    /// the body. The byte count isn't checked so a message that declares the
+    /// wrong count is reported when it is read rather than being skipped.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Format, FormatKind};
+    /// let voice = std::fs::read("tests/tub_bells.syx").unwrap();
+    /// assert_eq!(Some(FormatKind::SingleVoice), Format::detect(&voice));
+    /// ```
+    pub fn detect(data: &[u8]) -> Option<FormatKind> {
+        if data.len() < Self::MIN_PROBE_LEN {
+            return None;
+        }
+        let Some(format) = format_number(data) else {
+            return Some(FormatKind::Unknown);
+        };
+        let kind = match format {
+            format if format == SYSEX_HEADER[3] => FormatKind::VoiceBank32,
+            format if format == VOICE_SYSEX_HEADER[3] => FormatKind::SingleVoice,
+            format if format == SUPPLEMENT_SYSEX_HEADER[3] => FormatKind::Dx7IISupplement,
+            UNIVERSAL_FORMAT => {
+                let classification: [u8; 10] = data.get(6..16)?.try_into().unwrap();
+                match classification {
+                    PERFORMANCE_IDENTIFIER | TX802_PERFORMANCE_IDENTIFIER => {
+                        FormatKind::Performance
+                    }
+                    KEYBOARD_TUNING_IDENTIFIER | OCTAVE_TUNING_IDENTIFIER => {
+                        FormatKind::MicroTuning
+                    }
+                    _ => FormatKind::Unknown,
+                }
+            }
+            _ => FormatKind::Unknown,
+        };
+        Some(kind)
+    }
+}
+
+/// Whether the data starts with a message with the header on any MIDI
+/// channel, ending where the byte count says if the data is long enough.
+fn starts_with_dump(data: &[u8], expected: &[u8; Format::HEADER_LEN]) -> bool {
+    let Some(header) = data.get(..Format::MIN_PROBE_LEN) else {
+        return false;
+    };
+    let mut header: [u8; Format::HEADER_LEN] = header.try_into().unwrap();
+    header[2] &= 0xF0;
+
+    // The format number and byte count are compared with the rest
+    if header != *expected {
+        return false;
+    }
+
+    // The header, body and checksum come before the End of SysEx marker
+    let end = header.len() + byte_count(&header) + FOOTER_LEN - 1;
+    data.get(end).map_or(true, |byte| *byte == 0xF7)
 }
 
 #[cfg(test)]
 mod test {
     use std::fs::read;
 
+    use crate::read::byte_count;
     use crate::tests::test_data_path;
+    use crate::voice::VOICE_SYSEX_HEADER;
+    use crate::{Bank, Hardware, Preset, SYSEX_HEADER};
 
-    use super::Format;
+    use super::{Format, FormatKind};
 
     #[test]
     fn filename_extension() {
@@ -38,6 +213,46 @@ mod test {
         assert_eq!(Format::name(), "Yamaha DX7");
     }
 
+    #[test]
+    fn lengths() {
+        assert_eq!(6, Format::HEADER_LEN);
+        assert_eq!(128, Format::PACKED_VOICE_LEN);
+        assert_eq!(4096, Format::BANK_BODY_LEN);
+        assert_eq!(4104, Format::BANK_MESSAGE_LEN);
+        assert_eq!(163, Format::VOICE_MESSAGE_LEN);
+        assert_eq!(16391, Format::MAX_MESSAGE_LEN);
+
+        // Header, body, checksum and End of SysEx
+        assert_eq!(
+            Format::BANK_MESSAGE_LEN,
+            Format::HEADER_LEN + Format::BANK_BODY_LEN + 1 + 1
+        );
+        assert_eq!(
+            Format::BANK_BODY_LEN,
+            Format::PACKED_VOICE_LEN * Hardware::VOICES_PER_BANK
+        );
+        assert_eq!(Format::BANK_BODY_LEN, byte_count(&SYSEX_HEADER));
+        assert_eq!(
+            Format::VOICE_MESSAGE_LEN,
+            Format::HEADER_LEN + byte_count(&VOICE_SYSEX_HEADER) + 1 + 1
+        );
+        assert_eq!(Format::HEADER_LEN, SYSEX_HEADER.len());
+
+        let bank = read(test_data_path(&["rom1a.syx"])).unwrap();
+        assert_eq!(Format::BANK_MESSAGE_LEN, bank.len());
+        let voice = read(test_data_path(&["tub_bells.syx"])).unwrap();
+        assert_eq!(Format::VOICE_MESSAGE_LEN, voice.len());
+        assert_eq!(
+            Format::PACKED_VOICE_LEN,
+            Preset::default().to_packed().len()
+        );
+
+        assert_eq!(6, Hardware::OPERATOR_COUNT);
+        assert_eq!(32, Hardware::VOICES_PER_BANK);
+        assert_eq!(32, Hardware::ALGORITHM_COUNT);
+        assert_eq!(Bank::PRESET_COUNT, Hardware::VOICES_PER_BANK);
+    }
+
     #[test]
     fn init_version_1() {
         let path = test_data_path(&["rom1a.syx"]);
@@ -45,11 +260,123 @@ mod test {
         assert!(Format::is_format(&path, &contents));
     }
 
+    #[test]
+    fn channel_and_supplement() {
+        let path = test_data_path(&["tx802_bank.syx"]);
+        let contents = read(&path).unwrap();
+        assert!(Format::is_format(&path, &contents));
+
+        let path = test_data_path(&["rom1a_amem.syx"]);
+        let contents = read(&path).unwrap();
+        assert!(Format::is_format(&path, &contents));
+
+        let path = test_data_path(&["tub_bells.syx"]);
+        let contents = read(&path).unwrap();
+        assert!(!Format::is_format(&path, &contents));
+    }
+
     #[test]
     fn short() {
         let path = test_data_path(&["rom1a.syx"]);
         let contents = read(&path).unwrap();
         let shortened = &contents[..3];
-        assert!(!Format::is_format(&path, &shortened));
+        assert!(!Format::is_format(&path, shortened));
+
+        // Enough to check the header
+        assert!(Format::is_format(&path, &contents[..100]));
+        assert!(Format::is_format(&path, &contents[..Format::MIN_PROBE_LEN]));
+        assert!(!Format::is_format(
+            &path,
+            &contents[..Format::MIN_PROBE_LEN - 1]
+        ));
+    }
+
+    #[test]
+    fn channel() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let mut contents = read(&path).unwrap();
+        contents[2] = 0x03;
+        assert!(Format::is_format(&path, &contents));
+
+        // Parameter changes have a sub-status of 1
+        contents[2] = 0x13;
+        assert!(!Format::is_format(&path, &contents));
+    }
+
+    #[test]
+    fn single_voice() {
+        let bank = read(test_data_path(&["rom1a.syx"])).unwrap();
+        assert!(!Format::is_single_voice(&bank));
+        assert!(Format::matches(&bank));
+
+        let mut voice = read(test_data_path(&["tub_bells.syx"])).unwrap();
+        assert!(Format::is_single_voice(&voice));
+        assert!(Format::matches(&voice));
+        assert!(Format::matches(&voice[..Format::MIN_PROBE_LEN]));
+        voice[2] = 0x09;
+        assert!(Format::is_single_voice(&voice));
+        assert!(Format::matches(&voice));
+
+        // A parameter change on channel 10
+        let parameter_change = [0xF0, 0x43, 0x19, 0x01, 0x1B, 0x00, 0xF7];
+        assert!(!Format::is_single_voice(&parameter_change));
+        assert!(!Format::matches(&parameter_change));
+
+        // The same bytes as a voice header but with the sub-status of a
+        // parameter change
+        let parameter_change = [0xF0, 0x43, 0x10, 0x00, 0x01, 0x1B, 0xF7];
+        assert!(!Format::matches(&parameter_change));
+        assert!(!Format::matches(&[]));
+    }
+
+    #[test]
+    fn detect() {
+        for (file, kind) in [
+            ("rom1a.syx", FormatKind::VoiceBank32),
+            ("dx9_bank.syx", FormatKind::VoiceBank32),
+            ("tub_bells.syx", FormatKind::SingleVoice),
+            ("rom1a_amem.syx", FormatKind::Dx7IISupplement),
+            ("tx802_bank.syx", FormatKind::Dx7IISupplement),
+            ("dx7ii_performances.syx", FormatKind::Performance),
+            ("just_intonation.syx", FormatKind::MicroTuning),
+            ("tx81z_bank.syx", FormatKind::Unknown),
+        ] {
+            let contents = read(test_data_path(&[file])).unwrap();
+            assert_eq!(Some(kind), Format::detect(&contents), "{file}");
+        }
+
+        // Roland data set
+        let roland = [
+            0xF0, 0x41, 0x10, 0x42, 0x12, 0x40, 0x00, 0x7F, 0x00, 0x41, 0xF7,
+        ];
+        assert_eq!(Some(FormatKind::Unknown), Format::detect(&roland));
+        assert_eq!(Some(FormatKind::Unknown), Format::detect(b"not sysex"));
+
+        // Too short to tell
+        let contents = read(test_data_path(&["dx7ii_performances.syx"])).unwrap();
+        assert_eq!(None, Format::detect(&contents[..15]));
+        assert_eq!(None, Format::detect(&contents[..2]));
+        assert_eq!(None, Format::detect(&[]));
+    }
+
+    #[test]
+    fn layout() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let contents = read(&path).unwrap();
+
+        // Byte count of a single voice with the format number of a bank
+        let mut wrong_count = contents.clone();
+        wrong_count[4..6].copy_from_slice(&[0x01, 0x1B]);
+        assert!(!Format::is_format(&path, &wrong_count));
+
+        // The message doesn't end after 32 voices
+        let mut unterminated = contents.clone();
+        unterminated[4103] = 0x00;
+        assert!(!Format::is_format(&path, &unterminated));
+        assert!(Format::is_format(&path, &unterminated[..4103]));
+
+        // Anything may follow the message
+        let twice = read(test_data_path(&["rom1a_twice.syx"])).unwrap();
+        assert!(Format::is_format(&path, &twice));
     }
 }
@@ -0,0 +1,174 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::read::{packed_chunks, read_exact, read_footer, read_header};
+use crate::*;
+
+/// Header of a DX7II fractional scaling bank. The low nibble of the third
+/// byte is the MIDI channel.
+const FRACTIONAL_SCALING_SYSEX_HEADER: [u8; Format::HEADER_LEN] =
+    [0xF0, 0x43, 0x00, 0x7E, 0x60, 0x0A];
+
+/// Identifies the type of data at the start of the body of messages with
+/// the universal bulk dump format number.
+pub(crate) const FRACTIONAL_SCALING_IDENTIFIER: [u8; 10] = *b"LM  8973FS";
+
+/// Number of bytes for the fractional scaling of each voice. Every offset
+/// is stored as two four bit nibbles, most significant first.
+const PACKED_FRACTIONAL_SCALING_LEN: usize =
+    Preset::OPERATOR_COUNT * FractionalScaling::KEY_GROUP_COUNT * 2;
+
+/// Per-key output level offsets of a DX7II voice, which replace the
+/// curves of the keyboard level scaling for operators in fractional mode.
+/// Whether an operator uses them is set by
+/// [`AcedSupplement::scaling_modes`].
+///
+/// The layout follows the DX7II fractional scaling bulk data as described
+/// by patch librarians. No dump from a DX7II was available to verify it.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FractionalScaling {
+    /// Output level offset of each key group from 0 to 255, for each
+    /// operator in the same order as [`Preset::operators`].
+    pub offsets: [[u8; FractionalScaling::KEY_GROUP_COUNT]; Preset::OPERATOR_COUNT],
+}
+
+impl FractionalScaling {
+    /// The keyboard is divided into groups of four keys, starting from the
+    /// lowest MIDI note.
+    pub const KEY_GROUP_COUNT: usize = 32;
+
+    /// Number of keys in each key group.
+    pub const KEY_GROUP_SIZE: u8 = 4;
+
+    /// Output level offset of an operator for a MIDI note.
+    pub fn offset(&self, operator_index: usize, note: u8) -> u8 {
+        let key_group = (note / Self::KEY_GROUP_SIZE) as usize;
+        self.offsets[operator_index][key_group.min(Self::KEY_GROUP_COUNT - 1)]
+    }
+
+    /// Unpack the fractional scaling of a voice.
+    pub fn from_packed(packed: &[u8; PACKED_FRACTIONAL_SCALING_LEN]) -> Self {
+        // Operators are stored last-operator-first like in a bank
+        let mut offsets = [[0; Self::KEY_GROUP_COUNT]; Preset::OPERATOR_COUNT];
+        let packed_operators = packed.chunks_exact(Self::KEY_GROUP_COUNT * 2);
+        for (packed_operator, operator_offsets) in packed_operators.zip(offsets.iter_mut().rev()) {
+            for (nibbles, offset) in packed_operator
+                .chunks_exact(2)
+                .zip(operator_offsets.iter_mut())
+            {
+                *offset = (nibbles[0] & 0x0F) << 4 | (nibbles[1] & 0x0F);
+            }
+        }
+        FractionalScaling { offsets }
+    }
+}
+
+impl Default for FractionalScaling {
+    fn default() -> Self {
+        FractionalScaling {
+            offsets: [[0; Self::KEY_GROUP_COUNT]; Preset::OPERATOR_COUNT],
+        }
+    }
+}
+
+/// Banks of DX7II fractional scaling.
+pub struct FractionalScalingBank;
+
+impl FractionalScalingBank {
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Vec<FractionalScaling>, Error> {
+        let input = File::open(&path)?;
+        let mut reader = BufReader::new(input);
+        Self::load(&mut reader)
+    }
+
+    /// Read the fractional scaling for the 32 presets of a bank.
+    pub fn load<R: Read>(reader: &mut R) -> Result<Vec<FractionalScaling>, Error> {
+        // Header, ignoring the MIDI channel
+        read_header(reader, &FRACTIONAL_SCALING_SYSEX_HEADER)?;
+
+        // Body, starting with the identifier of the type of data
+        let mut body = vec![
+            0;
+            FRACTIONAL_SCALING_IDENTIFIER.len()
+                + Bank::PRESET_COUNT * PACKED_FRACTIONAL_SCALING_LEN
+        ];
+        read_exact(reader, &mut body)?;
+        let (identifier, packed_voices) = body.split_at(FRACTIONAL_SCALING_IDENTIFIER.len());
+        if identifier != FRACTIONAL_SCALING_IDENTIFIER {
+            return Err(Error::IncorrectIdentifier {
+                found: identifier.to_vec(),
+            });
+        }
+
+        // Body checksum and the end of SysEx marker
+        read_footer(reader, &body)?;
+
+        Ok(packed_chunks(packed_voices)
+            .map(FractionalScaling::from_packed)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::read::byte_count;
+    use crate::tests::test_data_path;
+
+    use super::*;
+
+    #[test]
+    fn header() {
+        assert_eq!(
+            FRACTIONAL_SCALING_IDENTIFIER.len()
+                + Bank::PRESET_COUNT * PACKED_FRACTIONAL_SCALING_LEN,
+            byte_count(&FRACTIONAL_SCALING_SYSEX_HEADER)
+        );
+    }
+
+    #[test]
+    fn key_groups() {
+        let path = test_data_path(&["rom1a_fractional.syx"]);
+        let contents = std::fs::read(path).unwrap();
+        let bank_len = 4104;
+        let fractional_scaling = FractionalScalingBank::load(&mut &contents[bank_len..]).unwrap();
+        assert_eq!(32, fractional_scaling.len());
+
+        // Operator 1 of the first voice falls by 8 for each key group
+        let scaling = fractional_scaling[0];
+        assert_eq!(0xFC, scaling.offsets[0][0]);
+        assert_eq!(0x7C, scaling.offsets[0][16]);
+        assert_eq!(0x7C, scaling.offset(0, 64));
+        assert_eq!(0x7C, scaling.offset(0, 67));
+        assert_eq!(0x74, scaling.offset(0, 68));
+        assert_eq!(0x04, scaling.offset(0, 127));
+
+        // Operator 6 only scales the top key group
+        assert_eq!(0, scaling.offsets[5][0]);
+        assert_eq!(0xA5, scaling.offsets[5][31]);
+
+        assert_eq!(FractionalScaling::default(), fractional_scaling[1]);
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let path = test_data_path(&["rom1a_fractional.syx"]);
+        let contents = std::fs::read(path).unwrap();
+        let mut data = contents[4104..].to_vec();
+        let checksum_index = data.len() - 2;
+        data[checksum_index] ^= 1;
+        let error = FractionalScalingBank::load(&mut data.as_slice()).unwrap_err();
+        let body = &data[6..checksum_index];
+        assert_eq!(
+            Error::ChecksumMismatch {
+                expected: data[checksum_index],
+                computed: checksum(body),
+            }
+            .to_string(),
+            error.to_string()
+        );
+
+        let error = FractionalScalingBank::load_file(test_data_path(&["rom1a.syx"])).unwrap_err();
+        assert!(matches!(error, Error::IncorrectHeader { .. }));
+    }
+}
@@ -0,0 +1,297 @@
+//! Banks stored as JSON, enabled by the `json` feature.
+//!
+//! The format is meant for archiving presets in version control, where a
+//! text format shows what changed between revisions:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "name": "ROM1A",
+//!   "source": "rom1a.syx",
+//!   "presets": {
+//!     "0": { "name": "BRASS   1", ... },
+//!     "1": { "name": "BRASS   2", ... }
+//!   }
+//! }
+//! ```
+//!
+//! Presets use the format of the `serde` feature and are keyed by their slot
+//! in the bank, from 0 to 31. Fields that aren't recognized are ignored so
+//! files written by later versions can still be read, as long as the schema
+//! version is supported.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Bank, Error, Preset};
+
+/// A bank read from JSON with where it came from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JsonBank {
+    /// Name of the bank, such as the name of the cartridge.
+    pub name: Option<String>,
+
+    /// Where the presets were originally read from, such as a file name.
+    pub source: Option<String>,
+
+    pub presets: Vec<Preset>,
+}
+
+#[derive(Serialize)]
+struct JsonBankRef<'a> {
+    version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<&'a str>,
+    presets: BTreeMap<usize, Preset>,
+}
+
+#[derive(Deserialize)]
+struct JsonBankFields {
+    version: u32,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    source: Option<String>,
+    presets: BTreeMap<usize, Preset>,
+}
+
+impl JsonBank {
+    /// Write the bank as pretty printed JSON. Presets are normalized before
+    /// being written.
+    pub fn to_json(&self) -> Result<String, Error> {
+        if self.presets.len() > Bank::PRESET_COUNT {
+            return Err(Error::TooManyPresets {
+                count: self.presets.len(),
+            });
+        }
+        let bank = JsonBankRef {
+            version: Bank::JSON_VERSION,
+            name: self.name.as_deref(),
+            source: self.source.as_deref(),
+            presets: self
+                .presets
+                .iter()
+                .map(|preset| preset.normalize())
+                .enumerate()
+                .collect(),
+        };
+        let json = serde_json::to_string_pretty(&bank).expect("presets serialize to JSON");
+        Ok(json)
+    }
+
+    /// Read a bank from JSON. Slots missing before the last preset are
+    /// filled with the default preset so every preset keeps its slot.
+    pub fn from_json(json: &str) -> Result<JsonBank, Error> {
+        let deserializer = &mut serde_json::Deserializer::from_str(json);
+        let fields: JsonBankFields =
+            serde_path_to_error::deserialize(deserializer).map_err(|err| Error::Json {
+                path: err.path().to_string(),
+                message: err.into_inner().to_string(),
+            })?;
+        if fields.version != Bank::JSON_VERSION {
+            return Err(Error::Json {
+                path: "version".to_owned(),
+                message: format!(
+                    "version {} is not supported, expected {}",
+                    fields.version,
+                    Bank::JSON_VERSION
+                ),
+            });
+        }
+        if fields.presets.len() > Bank::PRESET_COUNT {
+            return Err(Error::TooManyPresets {
+                count: fields.presets.len(),
+            });
+        }
+        if let Some(&slot) = fields
+            .presets
+            .keys()
+            .find(|&&slot| slot >= Bank::PRESET_COUNT)
+        {
+            return Err(Error::Json {
+                path: format!("presets.{slot}"),
+                message: format!(
+                    "slot {slot} is not between 0 and {}",
+                    Bank::PRESET_COUNT - 1
+                ),
+            });
+        }
+
+        let mut presets = Vec::with_capacity(fields.presets.len());
+        for (slot, preset) in fields.presets {
+            presets.resize_with(slot, Preset::default);
+            presets.push(preset);
+        }
+        Ok(JsonBank {
+            name: fields.name,
+            source: fields.source,
+            presets,
+        })
+    }
+}
+
+impl Bank {
+    /// The version of the JSON format written by [`Bank::to_json`].
+    pub const JSON_VERSION: u32 = 1;
+
+    /// Write up to [`Bank::PRESET_COUNT`] presets as JSON without a name or
+    /// source. See [`JsonBank::to_json`].
+    ///
+    /// ```
+    /// use synthahol_dx7::{Bank, Preset};
+    /// let presets = vec![Preset::default(); 3];
+    /// let json = Bank::to_json(&presets).unwrap();
+    /// assert_eq!(presets, Bank::from_json(&json).unwrap());
+    /// ```
+    pub fn to_json(presets: &[Preset]) -> Result<String, Error> {
+        JsonBank {
+            presets: presets.to_vec(),
+            ..Default::default()
+        }
+        .to_json()
+    }
+
+    /// Read the presets of a bank from JSON, ignoring the name and source.
+    /// See [`JsonBank::from_json`].
+    pub fn from_json(json: &str) -> Result<Vec<Preset>, Error> {
+        Ok(JsonBank::from_json(json)?.presets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    #[test]
+    fn round_trip() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"]))
+            .unwrap()
+            .presets;
+        let json = Bank::to_json(&presets).unwrap();
+        assert_eq!(presets, Bank::from_json(&json).unwrap());
+
+        let bank = JsonBank {
+            name: Some("ROM1A".to_owned()),
+            source: Some("rom1a.syx".to_owned()),
+            presets,
+        };
+        let json = bank.to_json().unwrap();
+        assert_eq!(bank, JsonBank::from_json(&json).unwrap());
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(1, value["version"]);
+        assert_eq!("ROM1A", value["name"]);
+        assert_eq!("TAKE OFF", value["presets"]["31"]["name"]);
+    }
+
+    #[test]
+    fn unknown_fields() {
+        let preset = serde_json::to_value(Preset::default()).unwrap();
+        let mut operator = preset["operators"][0].clone();
+        operator["comment"] = "loud".into();
+        let mut preset = preset.clone();
+        preset["operators"][0] = operator;
+        preset["author"] = "Someone".into();
+        let json = serde_json::json!({
+            "version": 1,
+            "name": "CUSTOM",
+            "created": "2024-01-01",
+            "tags": ["archive"],
+            "presets": {"0": preset},
+        });
+        let bank = JsonBank::from_json(&json.to_string()).unwrap();
+        assert_eq!(Some("CUSTOM".to_owned()), bank.name);
+        assert_eq!(None, bank.source);
+        assert_eq!(vec![Preset::default()], bank.presets);
+    }
+
+    #[test]
+    fn missing_slots() {
+        let mut preset = Preset::default();
+        preset.set_name("SLOT 3").unwrap();
+        let json = serde_json::json!({
+            "version": 1,
+            "presets": {"3": preset},
+        });
+        let presets = Bank::from_json(&json.to_string()).unwrap();
+        assert_eq!(4, presets.len());
+        assert_eq!(Preset::default(), presets[0]);
+        assert_eq!(preset, presets[3]);
+    }
+
+    #[test]
+    fn invalid() {
+        let preset = serde_json::to_value(Preset::default()).unwrap();
+        let error = |json: serde_json::Value| Bank::from_json(&json.to_string()).unwrap_err();
+
+        let mut malformed = preset.clone();
+        malformed["algorithm_id"] = "twelve".into();
+        let err = error(serde_json::json!({
+            "version": 1,
+            "presets": {"0": preset, "5": malformed},
+        }));
+        let Error::Json { path, message } = &err else {
+            panic!("{err:?}");
+        };
+        assert_eq!("presets.5.algorithm_id", path);
+        assert!(
+            message.starts_with("invalid type: string \"twelve\""),
+            "{message}"
+        );
+        assert!(err
+            .to_string()
+            .starts_with("presets.5.algorithm_id: invalid type"));
+
+        let mut out_of_range = preset.clone();
+        out_of_range["algorithm_id"] = 40.into();
+        let err = error(serde_json::json!({
+            "version": 1,
+            "presets": {"2": out_of_range},
+        }));
+        // serde_json adds where the preset ends
+        let message = err.to_string();
+        assert!(
+            message.starts_with("presets.2: Algorithm is 40, not between 0 and 31 at line 1"),
+            "{message}"
+        );
+
+        let err = error(serde_json::json!({"version": 2, "presets": {}}));
+        assert_eq!(
+            "version: version 2 is not supported, expected 1",
+            err.to_string()
+        );
+
+        let err = error(serde_json::json!({"presets": {}}));
+        assert!(err.to_string().contains("missing field `version`"), "{err}");
+
+        let err = error(serde_json::json!({"version": 1, "presets": {"32": preset}}));
+        assert_eq!(
+            "presets.32: slot 32 is not between 0 and 31",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn too_many_presets() {
+        let presets = vec![Preset::default(); Bank::PRESET_COUNT + 1];
+        assert!(matches!(
+            Bank::to_json(&presets),
+            Err(Error::TooManyPresets { count: 33 })
+        ));
+
+        let preset = serde_json::to_value(Preset::default()).unwrap();
+        let mut presets = serde_json::Map::new();
+        for slot in 0..=Bank::PRESET_COUNT {
+            presets.insert(slot.to_string(), preset.clone());
+        }
+        let json = serde_json::json!({"version": 1, "presets": presets});
+        assert!(matches!(
+            Bank::from_json(&json.to_string()),
+            Err(Error::TooManyPresets { count: 33 })
+        ));
+    }
+}
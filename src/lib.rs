@@ -9,18 +9,76 @@
 //! by Ken Sherriff is an useful reference on the hardware.
 
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use normalize::Normalizer;
 
 pub use algorithms::*;
+pub use bank::*;
+pub use category::*;
+pub use checksum::*;
+pub use dedup::*;
+pub use diff::*;
+pub use dx9::*;
 pub use envelope::*;
-pub use format::Format;
+pub use error::*;
+pub use format::{Format, FormatKind};
+pub use fractional::*;
+#[cfg(feature = "json")]
+pub use json::*;
+pub use message::*;
+pub use normalize::OutOfRange;
+pub use operator::*;
+pub use parameter::*;
+pub use performance::*;
 pub use read::*;
+pub use slots::*;
+pub use sort::*;
+pub use stats::*;
+pub use supplement::*;
+pub use text::*;
+pub use tuning::*;
+pub use tx802::*;
+pub use tx81z::*;
+pub use validate::*;
+pub use voice::*;
 
 mod algorithms;
+mod bank;
+mod category;
+mod checksum;
+mod dedup;
+mod diff;
+mod dx9;
 mod envelope;
+mod error;
 mod format;
+mod fractional;
+#[cfg(feature = "json")]
+mod json;
+mod merge;
+mod message;
+mod morph;
+mod normalize;
+mod operator;
+mod parameter;
+mod performance;
 mod read;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod slots;
+mod sort;
+mod stats;
+mod supplement;
+mod text;
+mod tuning;
+mod tx802;
+mod tx81z;
+mod validate;
+mod voice;
+mod write;
 
-const SYSEX_HEADER: [u8; 6] = [0xF0, 0x43, 0x00, 0x09, 0x20, 0x00];
+const SYSEX_HEADER: [u8; Format::HEADER_LEN] = [0xF0, 0x43, 0x00, 0x09, 0x20, 0x00];
 
 pub type OperatorId = u8;
 
@@ -29,17 +87,34 @@ pub struct Hardware;
 impl Hardware {
     /// The DX7 had 16 voice polyphony.
     pub const POLYPHONY: u32 = 16;
+
+    /// Each voice has six operators.
+    pub const OPERATOR_COUNT: usize = Preset::OPERATOR_COUNT;
+
+    /// A cartridge or the internal memory holds 32 voices.
+    pub const VOICES_PER_BANK: usize = Bank::PRESET_COUNT;
+
+    /// The operators can be connected by 32 algorithms.
+    pub const ALGORITHM_COUNT: usize = Algorithms::COUNT;
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct PresetName(String);
 
 impl PresetName {
     /// A preset name has a fixed length
     pub const MAX_LENGTH: usize = 10;
 
-    /// Normalize and trim the preset name. Unsupported characters are replaced
-    /// with a space.
+    /// The characters shown on the display of the DX7 for the character codes
+    /// 0x20 to 0x7F, in order, for pickers in editors. The display follows
+    /// ASCII except for a yen sign (`¥`) at 0x5C, a right arrow (`→`) at
+    /// 0x7E and a left arrow (`←`) at 0x7F. These are the only characters a
+    /// name can contain.
+    pub const CHARSET: [char; CHARSET_LEN] = lcd_charset();
+
+    /// Normalize and trim the preset name. Character codes are converted to
+    /// the characters of [`PresetName::CHARSET`] and unsupported codes are
+    /// replaced with a space.
     ///
     /// # Example
     ///
@@ -50,33 +125,238 @@ impl PresetName {
     /// assert_eq!("! 8X", PresetName::from_lossy("! 8X".as_bytes()).to_string());
     /// assert_eq!("ABC def", PresetName::from_lossy("ABC\x07def".as_bytes()).to_string());
     /// assert_eq!("abcdefghij", PresetName::from_lossy("abcdefghijklmnopqrstuvwxyz".as_bytes()).to_string());
+    /// assert_eq!("100¥", PresetName::from_lossy(b"100\\").to_string());
     /// ```
     pub fn from_lossy(data: &[u8]) -> PresetName {
-        let ascii = data
+        let name: String = data
             .iter()
-            .map(|c| match c & 0x7F {
-                c if (0x20..0x7f).contains(&c) => c, // Printable ASCII range
-                _ => b' ',
-            })
             .take(PresetName::MAX_LENGTH)
-            .collect::<Vec<u8>>();
-        PresetName(String::from_utf8_lossy(&ascii).trim_end().to_string())
+            .map(|code| lcd_char(code & 0x7F).unwrap_or(' '))
+            .collect();
+        PresetName(name.trim_end().to_owned())
+    }
+
+    /// A name of up to [`PresetName::MAX_LENGTH`] characters from
+    /// [`PresetName::CHARSET`]. Unlike [`PresetName::from_lossy`] nothing is replaced or
+    /// truncated, the first character that can't be used is reported
+    /// instead. Trailing spaces are trimmed because the DX7 pads names with
+    /// spaces, so they are counted towards the length but not kept.
+    ///
+    /// ```
+    /// use synthahol_dx7::{NameError, PresetName};
+    /// assert_eq!("SOLID BASS", PresetName::new("SOLID BASS").unwrap().to_string());
+    /// assert_eq!("E.PIANO 1", PresetName::new("E.PIANO 1 ").unwrap().to_string());
+    /// assert_eq!(
+    ///     Err(NameError::TooLong { length: 11 }),
+    ///     PresetName::new("SOLID BASS!")
+    /// );
+    /// ```
+    pub fn new(name: &str) -> Result<PresetName, NameError> {
+        if let Some((index, character)) = name
+            .chars()
+            .enumerate()
+            .find(|(_, character)| lcd_code(*character).is_none())
+        {
+            return Err(NameError::InvalidCharacter { index, character });
+        }
+        let length = name.chars().count();
+        if length > PresetName::MAX_LENGTH {
+            return Err(NameError::TooLong { length });
+        }
+        Ok(PresetName(name.trim_end().to_owned()))
+    }
+
+    /// The name without trailing spaces.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// A name from raw data, such as the name of a voice in a dump, with the
+    /// same checks as [`PresetName::new`]. Each byte is a character code so
+    /// the index of an invalid character is the index of its byte. Codes
+    /// that aren't in [`PresetName::CHARSET`] are reported as the Latin-1
+    /// character with that code.
+    ///
+    /// ```
+    /// use synthahol_dx7::{NameError, PresetName};
+    /// assert_eq!("BRASS   1", PresetName::try_from_bytes(b"BRASS   1 ").unwrap().to_string());
+    /// assert_eq!(
+    ///     Err(NameError::InvalidCharacter { index: 1, character: '\t' }),
+    ///     PresetName::try_from_bytes(b"A\tB")
+    /// );
+    /// ```
+    pub fn try_from_bytes(data: &[u8]) -> Result<PresetName, NameError> {
+        let name: String = data
+            .iter()
+            .map(|code| lcd_char(*code).unwrap_or(*code as char))
+            .collect();
+        PresetName::new(&name)
+    }
+
+    /// The name as it is stored in a voice, the character codes padded with
+    /// spaces to the maximum length. This is the reverse of
+    /// [`PresetName::from_lossy`]. Characters that aren't in
+    /// [`PresetName::CHARSET`] become spaces so every byte is less than
+    /// 0x80.
+    ///
+    /// ```
+    /// use synthahol_dx7::PresetName;
+    /// let name = PresetName::new("¥EN").unwrap();
+    /// assert_eq!(*b"\x5CEN       ", name.to_padded_bytes());
+    /// assert_eq!(name, PresetName::from_lossy(&name.to_padded_bytes()));
+    /// ```
+    pub fn to_padded_bytes(&self) -> [u8; PresetName::MAX_LENGTH] {
+        let mut bytes = [b' '; PresetName::MAX_LENGTH];
+        for (dest, character) in bytes.iter_mut().zip(self.0.chars()) {
+            *dest = lcd_code(character).unwrap_or(b' ');
+        }
+        bytes
     }
 }
 
+/// The number of characters the display of the DX7 can show in a name.
+const CHARSET_LEN: usize = 96;
+
+/// The first character code of [`PresetName::CHARSET`], a space.
+const CHARSET_START: u8 = 0x20;
+
+/// The character codes the display of the DX7 shows differently than ASCII,
+/// as found in the character generator of the HD44780 display controller.
+const LCD_CHARACTERS: [(u8, char); 3] = [
+    // Backslash in ASCII
+    (0x5C, '¥'),
+    // Tilde in ASCII
+    (0x7E, '→'),
+    // Delete in ASCII
+    (0x7F, '←'),
+];
+
+const fn lcd_charset() -> [char; CHARSET_LEN] {
+    let mut charset = [' '; CHARSET_LEN];
+    let mut index = 0;
+    while index < CHARSET_LEN {
+        charset[index] = (CHARSET_START + index as u8) as char;
+        index += 1;
+    }
+    let mut index = 0;
+    while index < LCD_CHARACTERS.len() {
+        let (code, character) = LCD_CHARACTERS[index];
+        charset[(code - CHARSET_START) as usize] = character;
+        index += 1;
+    }
+    charset
+}
+
+/// The character shown for a character code, if any.
+pub(crate) fn lcd_char(code: u8) -> Option<char> {
+    code.checked_sub(CHARSET_START)
+        .and_then(|index| PresetName::CHARSET.get(index as usize))
+        .copied()
+}
+
+/// The character code of a character on the display, if it can be shown.
+pub(crate) fn lcd_code(character: char) -> Option<u8> {
+    PresetName::CHARSET
+        .iter()
+        .position(|c| *c == character)
+        .map(|index| CHARSET_START + index as u8)
+}
+
+/// Why a name can't be used for a preset.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NameError {
+    /// The name is longer than [`PresetName::MAX_LENGTH`].
+    TooLong { length: usize },
+
+    /// The character at the index, counting characters from 0, is not in
+    /// [`PresetName::CHARSET`].
+    InvalidCharacter { index: usize, character: char },
+}
+
+impl Display for NameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use NameError::*;
+        match self {
+            TooLong { length } => write!(
+                f,
+                "Name is {length} characters, more than {}",
+                PresetName::MAX_LENGTH
+            ),
+            InvalidCharacter { index, character } => write!(
+                f,
+                "Character {character:?} at index {index} can't be used in a name"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NameError {}
+
+/// Convert to printable ASCII and trim the end. Unsupported characters are
+/// replaced with a space.
+pub(crate) fn lossy_ascii(data: &[u8]) -> String {
+    let ascii = data
+        .iter()
+        .map(|c| match c & 0x7F {
+            c if (0x20..0x7f).contains(&c) => c, // Printable ASCII range
+            _ => b' ',
+        })
+        .collect::<Vec<u8>>();
+    String::from_utf8_lossy(&ascii).trim_end().to_string()
+}
+
 impl Default for PresetName {
     fn default() -> Self {
         PresetName("INIT VOICE".to_owned())
     }
 }
 
+/// Parse a name with the checks of [`PresetName::new`].
+///
+/// ```
+/// use synthahol_dx7::{NameError, PresetName};
+/// let name: PresetName = "SOLID BASS".parse().unwrap();
+/// assert_eq!("SOLID BASS", name.as_str());
+/// assert_eq!(
+///     Err(NameError::TooLong { length: 11 }),
+///     "SOLID BASS!".parse::<PresetName>()
+/// );
+/// assert_eq!(
+///     Err(NameError::InvalidCharacter { index: 4, character: '~' }),
+///     PresetName::try_from("BASS~")
+/// );
+/// ```
+impl FromStr for PresetName {
+    type Err = NameError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        PresetName::new(name)
+    }
+}
+
+impl TryFrom<&str> for PresetName {
+    type Error = NameError;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        name.parse()
+    }
+}
+
+impl AsRef<str> for PresetName {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
 impl Display for PresetName {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str(&self.0)
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 #[repr(u8)]
 pub enum Waveform {
     Triangle = 0,
@@ -98,99 +378,29 @@ impl TryFrom<u8> for Waveform {
             3 => Ok(Waveform::Square),
             4 => Ok(Waveform::Sine),
             5 => Ok(Waveform::SampleAndHold),
-            _ => Err("Unknown waveform {value}"),
+            _ => Err("Unknown waveform"),
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
-pub enum OperatorMode {
-    Fixed = 0,
-    Ratio = 1,
-}
-
-impl Display for OperatorMode {
+impl Display for Waveform {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        use OperatorMode::*;
+        use Waveform::*;
         let txt = match self {
-            Fixed => "Fixed",
-            Ratio => "Ratio",
+            Triangle => "Triangle",
+            SawDown => "Saw down",
+            SawUp => "Saw up",
+            Square => "Square",
+            Sine => "Sine",
+            SampleAndHold => "Sample and hold",
         };
         f.write_str(txt)
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct Operator {
-    // In the DX7 the operator ON/OFF state is not stored in the preset and
-    // is only used in parameter change sysex messages while editing a voice.
-    pub envelope: Envelope,
-    pub scaling_break_point: u8,
-    pub scaling_left_depth: u8,
-    pub scaling_right_depth: u8,
-    pub scaling_left_curve: u8,
-    pub scaling_right_curve: u8,
-
-    // -7 to 7. Stored as 0-14 in the preset.
-    pub detune: i8,
-
-    pub rate_scaling: u8,
-    pub velocity_sensitivity: u8,
-    pub modulation_sensitivity: u8,
-    pub output_level: u8,
-    pub mode: OperatorMode,
-    pub frequency_course: u8,
-    pub frequency_fine: u8,
-}
-
-impl Operator {
-    /// Clamp all parameters to valid ranges.
-    fn normalize(&self) -> Self {
-        Self {
-            scaling_break_point: self.scaling_break_point.clamp(0, 99),
-            scaling_left_depth: self.scaling_left_depth.clamp(0, 99),
-            scaling_right_depth: self.scaling_right_depth.clamp(0, 99),
-            scaling_left_curve: self.scaling_left_curve.clamp(0, 3),
-            scaling_right_curve: self.scaling_right_curve.clamp(0, 3),
-            detune: self.detune.clamp(0, 14),
-            rate_scaling: self.rate_scaling.clamp(0, 7),
-            velocity_sensitivity: self.velocity_sensitivity.clamp(0, 7),
-            modulation_sensitivity: self.modulation_sensitivity.clamp(0, 3),
-            output_level: self.output_level.clamp(0, 99),
-            frequency_course: self.frequency_course.clamp(0, 31),
-            frequency_fine: self.frequency_fine.clamp(0, 99),
-            ..*self
-        }
-    }
-}
-
-impl Default for Operator {
-    fn default() -> Self {
-        // The last envelope generator has a different default level according to
-        // the DX7 II manual.
-        let mut envelope = Envelope::default();
-        envelope.levels[envelope.levels.len() - 1] = 0;
-
-        Operator {
-            envelope,
-            scaling_break_point: 39,
-            scaling_left_depth: 0,
-            scaling_right_depth: 0,
-            scaling_left_curve: 0,
-            scaling_right_curve: 0,
-            detune: 0,
-            rate_scaling: 0,
-            velocity_sensitivity: 0,
-            modulation_sensitivity: 0,
-            output_level: 0,
-            mode: OperatorMode::Fixed,
-            frequency_course: 1,
-            frequency_fine: 0,
-        }
-    }
-}
-
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "serde_support::PresetFields"))]
 pub struct Preset {
     pub name: PresetName,
     pub operators: [Operator; Preset::OPERATOR_COUNT],
@@ -203,7 +413,10 @@ pub struct Preset {
     pub lfo_speed: u8,
     pub lfo_delay: u8,
     pub lfo_pitch_mod_depth: u8,
+
+    // 0-7. Stored in three bits of the packed preset.
     pub lfo_pitch_mod_sensitivity: u8,
+
     pub lfo_amplitude_mod_depth: u8,
     pub lfo_waveform: Waveform,
     pub lfo_key_sync: bool,
@@ -213,30 +426,17 @@ pub struct Preset {
 impl Preset {
     const OPERATOR_COUNT: usize = 6;
 
-    /// Clamp all parameters to valid ranges.
-    fn normalize(&self) -> Self {
-        // Normalization is done outside of reading to enable reuse.
-        Preset {
-            name: self.name.clone(),
-            operators: self.operators.map(|operator| operator.normalize()),
-            pitch_envelope: self.pitch_envelope.normalize(),
-            algorithm_id: self.algorithm_id.clamp(0, 31),
-            oscillator_key_sync: self.oscillator_key_sync,
-            feedback_level: self.feedback_level.clamp(0, 7),
-            lfo_speed: self.lfo_speed.clamp(0, 99),
-            lfo_delay: self.lfo_delay.clamp(0, 99),
-            lfo_pitch_mod_depth: self.lfo_pitch_mod_depth.clamp(0, 99),
-            lfo_pitch_mod_sensitivity: self.lfo_pitch_mod_sensitivity.clamp(0, 99),
-            lfo_amplitude_mod_depth: self.lfo_amplitude_mod_depth.clamp(0, 99),
-            lfo_waveform: self.lfo_waveform,
-            lfo_key_sync: self.lfo_key_sync,
-            transpose: self.transpose.clamp(0, 48),
-        }
-    }
-}
+    /// Transpose that leaves notes unchanged, C3 on the DX7.
+    const TRANSPOSE_CENTER: u8 = 24;
 
-impl Default for Preset {
-    fn default() -> Self {
+    /// The key that plays middle C, which is MIDI note 60.
+    const MIDDLE_C: u8 = 60;
+
+    /// The INIT VOICE of a DX7, the voice it creates when a voice is
+    /// initialized.
+    ///
+    /// Only operator 1 can be heard, at full output level with a ratio of 1.
+    pub fn init() -> Self {
         let mut operators = [Operator::default(); Preset::OPERATOR_COUNT];
         operators[0] = Operator {
             output_level: 99,
@@ -248,7 +448,7 @@ impl Default for Preset {
             name: PresetName::default(),
             operators,
             pitch_envelope,
-            algorithm_id: 0,
+            algorithm_id: AlgorithmId::from_stored(0),
             oscillator_key_sync: true,
             feedback_level: 0,
             lfo_speed: 35,
@@ -261,6 +461,292 @@ impl Default for Preset {
             transpose: 24,
         }
     }
+
+    /// Clamp all parameters to valid ranges.
+    fn normalize(&self) -> Self {
+        self.normalize_with(&mut Vec::new())
+    }
+
+    /// Clamp all parameters to valid ranges, recording the ones that were
+    /// out of range.
+    pub(crate) fn normalize_with(&self, out_of_range: &mut Vec<OutOfRange>) -> Self {
+        // Normalization is done outside of reading to enable reuse.
+        let mut n = Normalizer::new(out_of_range);
+        let mut operators = self.operators;
+        for (index, operator) in operators.iter_mut().enumerate() {
+            n.operator = Some(index);
+            *operator = operator.normalize_with(&mut n);
+        }
+        n.operator = None;
+
+        Preset {
+            name: self.name.clone(),
+            operators,
+            pitch_envelope: self
+                .pitch_envelope
+                .normalize_with(&mut n, &envelope::PITCH_ENVELOPE_NAMES),
+            algorithm_id: AlgorithmId::from_stored(n.clamp(
+                "algorithm",
+                self.algorithm_id.stored(),
+                31,
+            )),
+            oscillator_key_sync: self.oscillator_key_sync,
+            feedback_level: n.clamp("feedback level", self.feedback_level, 7),
+            lfo_speed: n.clamp("LFO speed", self.lfo_speed, 99),
+            lfo_delay: n.clamp("LFO delay", self.lfo_delay, 99),
+            lfo_pitch_mod_depth: n.clamp("LFO pitch mod depth", self.lfo_pitch_mod_depth, 99),
+            lfo_pitch_mod_sensitivity: n.clamp(
+                "LFO pitch mod sensitivity",
+                self.lfo_pitch_mod_sensitivity,
+                7,
+            ),
+            lfo_amplitude_mod_depth: n.clamp(
+                "LFO amplitude mod depth",
+                self.lfo_amplitude_mod_depth,
+                99,
+            ),
+            lfo_waveform: self.lfo_waveform,
+            lfo_key_sync: self.lfo_key_sync,
+            transpose: n.clamp("transpose", self.transpose, 48),
+        }
+    }
+
+    /// The algorithm of the preset. Algorithm IDs above the last algorithm
+    /// use the last algorithm, as they do when the preset is normalized.
+    ///
+    /// ```
+    /// use synthahol_dx7::Preset;
+    /// assert_eq!(vec![0, 2], Preset::default().algorithm().carriers());
+    /// ```
+    pub fn algorithm(&self) -> &'static Algorithm {
+        let index = (self.algorithm_id.stored() as usize).min(Algorithms::COUNT - 1);
+        &Algorithms::all()[index]
+    }
+
+    /// The operators that output to the amplifier.
+    pub fn carriers(&self) -> Vec<OperatorId> {
+        self.algorithm().carriers()
+    }
+
+    /// Returns `true` if the operator outputs to the amplifier.
+    pub fn is_operator_carrier(&self, operator_id: OperatorId) -> bool {
+        self.algorithm().is_carrier(operator_id)
+    }
+
+    /// A hash of the parameters that shape the sound, ignoring the name, to
+    /// find copies of a voice saved under different names.
+    ///
+    /// The fingerprint is the 64-bit FNV-1a hash of the normalized preset in
+    /// the packed voice layout without the name. It is the same in every
+    /// version of this crate.
+    ///
+    /// ```
+    /// use synthahol_dx7::Preset;
+    /// let mut renamed = Preset::default();
+    /// renamed.set_name("RENAMED").unwrap();
+    /// assert_eq!(Preset::default().fingerprint(), renamed.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        let packed = self.normalize().to_packed();
+        packed[..118].iter().fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(PRIME)
+        })
+    }
+
+    /// Returns `true` if every carrier dies away while the note is held,
+    /// like a plucked or struck instrument.
+    ///
+    /// The sustain level of each carrier is scaled by its output level.
+    /// Both are roughly 0.75 dB a step, so a carrier at output level 90 and
+    /// sustain level 50 holds at about the volume of sustain level 41 at
+    /// full output. Carriers holding below level 40, around 45 dB down,
+    /// are considered silent.
+    pub fn is_percussive(&self) -> bool {
+        const THRESHOLD: u8 = 40;
+        self.carriers().iter().all(|&carrier| {
+            let operator = &self.operators[carrier as usize];
+            let attenuation = 99_u8.saturating_sub(operator.output_level);
+            operator
+                .envelope
+                .is_percussive(THRESHOLD.saturating_add(attenuation))
+        })
+    }
+
+    /// Transpose in semitones from -24 to 24. It is stored from 0 to 48.
+    pub fn transpose_semitones(&self) -> i8 {
+        let center = Preset::TRANSPOSE_CENTER;
+        (self.transpose.min(2 * center) as i16 - center as i16) as i8
+    }
+
+    /// Set the transpose in semitones from -24 to 24. Other values are
+    /// rejected and the transpose is unchanged.
+    ///
+    /// ```
+    /// use synthahol_dx7::Preset;
+    /// let mut preset = Preset::default();
+    /// preset.set_transpose_semitones(-12).unwrap();
+    /// assert_eq!(12, preset.transpose);
+    /// assert_eq!("C2", preset.transpose_note_name());
+    /// assert!(preset.set_transpose_semitones(25).is_err());
+    /// ```
+    pub fn set_transpose_semitones(&mut self, semitones: i8) -> Result<(), OutOfRange> {
+        let center = Preset::TRANSPOSE_CENTER as i16;
+        let semitones = semitones as i16;
+        if !(-center..=center).contains(&semitones) {
+            return Err(OutOfRange {
+                operator: None,
+                parameter: "transpose",
+                value: semitones,
+                min: -center,
+                max: center,
+            });
+        }
+        self.transpose = (semitones + center) as u8;
+        Ok(())
+    }
+
+    /// The transpose as shown on the DX7, the note played by middle C from
+    /// C1 to C5. Without transposing it is C3.
+    pub fn transpose_note_name(&self) -> String {
+        let note = Preset::MIDDLE_C as i16 + self.transpose_semitones() as i16;
+        operator::note_name(note as u8)
+    }
+
+    /// Rename the preset. Names longer than 10 characters or with characters
+    /// that aren't in [`PresetName::CHARSET`] are rejected and the name is unchanged.
+    /// Trailing spaces are trimmed.
+    ///
+    /// ```
+    /// use synthahol_dx7::{NameError, Preset};
+    /// let mut preset = Preset::default();
+    /// preset.set_name("SOLID BASS").unwrap();
+    /// assert_eq!("SOLID BASS", preset.name.to_string());
+    /// assert_eq!(
+    ///     Err(NameError::InvalidCharacter { index: 2, character: '\t' }),
+    ///     preset.set_name("AB\tC")
+    /// );
+    /// ```
+    pub fn set_name(&mut self, name: &str) -> Result<(), NameError> {
+        self.name = PresetName::new(name)?;
+        Ok(())
+    }
+
+    /// Rename the preset, replacing characters that aren't in
+    /// [`PresetName::CHARSET`] with spaces and truncating to 10 characters. Returns the name that
+    /// was stored.
+    pub fn set_name_lossy(&mut self, name: &str) -> &PresetName {
+        let name: String = name
+            .chars()
+            .take(PresetName::MAX_LENGTH)
+            .map(|character| {
+                if lcd_code(character).is_some() {
+                    character
+                } else {
+                    ' '
+                }
+            })
+            .collect();
+        self.name = PresetName(name.trim_end().to_owned());
+        &self.name
+    }
+
+    /// Returns `true` if the operator can be heard, either directly or
+    /// through the operators it modulates.
+    ///
+    /// An operator with an output level of 0 is silent, and so is one whose
+    /// every path to the amplifier passes through an operator with an output
+    /// level of 0. Feedback is ignored. Returns `false` if the operator
+    /// doesn't exist.
+    pub fn is_operator_audible(&self, operator_id: OperatorId) -> bool {
+        fn reaches_amplifier(
+            preset: &Preset,
+            algorithm: &Algorithm,
+            operator_id: OperatorId,
+            visited: &mut [bool; Preset::OPERATOR_COUNT],
+        ) -> bool {
+            let Some(operator) = preset.operators.get(operator_id as usize) else {
+                return false;
+            };
+            if operator.output_level == 0 || visited[operator_id as usize] {
+                return false;
+            }
+            visited[operator_id as usize] = true;
+            algorithm
+                .routing_slice(operator_id)
+                .unwrap_or_default()
+                .iter()
+                .any(|output| match output.operator_id() {
+                    Some(target) => reaches_amplifier(preset, algorithm, target, visited),
+                    None => true,
+                })
+        }
+
+        let mut visited = [false; Preset::OPERATOR_COUNT];
+        reaches_amplifier(self, self.algorithm(), operator_id, &mut visited)
+    }
+
+    /// The levels of the pitch envelope as changes in pitch in semitones.
+    pub fn pitch_envelope_semitones(&self) -> [f32; Envelope::SEGMENT_COUNT] {
+        self.pitch_envelope
+            .levels
+            .map(Envelope::pitch_offset_semitones)
+    }
+}
+
+/// A patch sheet listing every parameter, with a line for each operator.
+/// The alternate form is a single line with the name and algorithm.
+impl Display for Preset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        if f.alternate() {
+            return write!(
+                f,
+                "{} ({}, feedback {})",
+                self.name, self.algorithm_id, self.feedback_level
+            );
+        }
+        writeln!(f, "{}", self.name)?;
+        writeln!(
+            f,
+            "{}, feedback {}, oscillator key sync {}",
+            self.algorithm_id,
+            self.feedback_level,
+            on_off(self.oscillator_key_sync)
+        )?;
+        writeln!(
+            f,
+            "LFO {}, speed {}, delay {}, pitch mod depth {}, amplitude mod depth {}, \
+            key sync {}, pitch mod sensitivity {}",
+            self.lfo_waveform,
+            self.lfo_speed,
+            self.lfo_delay,
+            self.lfo_pitch_mod_depth,
+            self.lfo_amplitude_mod_depth,
+            on_off(self.lfo_key_sync),
+            self.lfo_pitch_mod_sensitivity
+        )?;
+        let semitones = self.transpose_semitones();
+        let sign = if semitones > 0 { "+" } else { "" };
+        writeln!(
+            f,
+            "Transpose {sign}{semitones} ({})",
+            self.transpose_note_name()
+        )?;
+        write!(f, "Pitch EG {}", self.pitch_envelope)?;
+        for (index, operator) in self.operators.iter().enumerate() {
+            write!(f, "\nOP{} {operator}", index + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// The same as [`Preset::init`].
+impl Default for Preset {
+    fn default() -> Self {
+        Preset::init()
+    }
 }
 
 #[cfg(test)]
@@ -275,6 +761,173 @@ mod tests {
         parts.iter().collect::<PathBuf>()
     }
 
+    #[test]
+    fn percussive() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let is_percussive = |name: &str| {
+            presets
+                .iter()
+                .find(|preset| preset.name.to_string() == name)
+                .unwrap()
+                .is_percussive()
+        };
+        assert!(is_percussive("TUB BELLS"));
+        assert!(is_percussive("PIANO   1"));
+        assert!(is_percussive("MARIMBA"));
+        assert!(!is_percussive("STRINGS 1"));
+        assert!(!is_percussive("BRASS   1"));
+        assert!(!is_percussive("E.ORGAN 1"));
+        assert!(!Preset::default().is_percussive());
+    }
+
+    #[test]
+    fn display() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let expected = [
+            "BRASS   1",
+            "Algorithm 22, feedback 7, oscillator key sync on",
+            "LFO Sine, speed 37, delay 0, pitch mod depth 5, amplitude mod depth 0, key sync off, pitch mod sensitivity 3",
+            "Transpose 0 (C3)",
+            "Pitch EG R 84 95 95 60 / L 50 50 50 50",
+            "OP1 Ratio 0.50     level 98 detune +7 scaling C3   +LIN  0 +LIN 14 EG R 72 76 99 71 / L 99 88 96 0",
+            "OP2 Ratio 0.50     level 86 detune +7 scaling C2   +LIN  0 -EXP  7 EG R 62 51 29 71 / L 82 95 96 0",
+            "OP3 Ratio 1.00     level 99 detune -2 scaling C3   +LIN  0 +LIN  0 EG R 77 76 82 71 / L 99 98 98 0",
+            "OP4 Ratio 1.00     level 99 detune  0 scaling C3   +LIN  0 +LIN  0 EG R 77 36 41 71 / L 99 98 98 0",
+            "OP5 Ratio 1.00     level 98 detune +1 scaling C3   +LIN  0 +LIN  0 EG R 77 36 41 71 / L 99 98 98 0",
+            "OP6 Ratio 1.00     level 82 detune  0 scaling C3   -EXP 54 -EXP 50 EG R 49 99 28 68 / L 98 98 91 0",
+        ];
+        assert_eq!(expected.join("\n"), presets[0].to_string());
+        assert_eq!(
+            "BRASS   1 (Algorithm 22, feedback 7)",
+            format!("{:#}", presets[0])
+        );
+
+        // Fixed frequencies are shown like on the DX7
+        let tub_bells = presets[25].to_string();
+        assert!(tub_bells.contains("\nOP5 Fixed 323.6 Hz level 99 "));
+        assert!(tub_bells.contains("\nLFO Saw down, "));
+    }
+
+    #[test]
+    fn algorithm() {
+        let preset = Preset::default();
+        assert_eq!(Algorithms::all()[0], *preset.algorithm());
+        assert_eq!(vec![0, 2], preset.carriers());
+        assert!(preset.is_operator_carrier(0));
+        assert!(!preset.is_operator_carrier(1));
+        assert!(!preset.is_operator_carrier(6));
+
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let brass = &presets[0];
+        assert_eq!(
+            Algorithms::get(brass.algorithm_id).unwrap(),
+            brass.algorithm()
+        );
+        assert_eq!(vec![0, 2, 3, 4], brass.carriers());
+
+        for stored in 0..=u8::MAX {
+            let preset = Preset {
+                algorithm_id: AlgorithmId::from_stored(stored),
+                ..Default::default()
+            };
+            let algorithm = preset.algorithm();
+            assert_eq!(preset.normalize().algorithm(), algorithm);
+            if let Some(expected) = Algorithms::get(preset.algorithm_id) {
+                assert_eq!(expected, algorithm);
+            }
+        }
+    }
+
+    #[test]
+    fn transpose() {
+        let mut preset = Preset::default();
+        assert_eq!(0, preset.transpose_semitones());
+        assert_eq!("C3", preset.transpose_note_name());
+
+        preset.transpose = 0;
+        assert_eq!(-24, preset.transpose_semitones());
+        assert_eq!("C1", preset.transpose_note_name());
+        preset.transpose = 48;
+        assert_eq!(24, preset.transpose_semitones());
+        assert_eq!("C5", preset.transpose_note_name());
+        preset.transpose = 25;
+        assert_eq!(1, preset.transpose_semitones());
+        assert_eq!("C#3", preset.transpose_note_name());
+        assert!(preset.to_string().contains("\nTranspose +1 (C#3)\n"));
+
+        for semitones in -24..=24 {
+            preset.set_transpose_semitones(semitones).unwrap();
+            assert_eq!(semitones, preset.transpose_semitones());
+        }
+        assert_eq!(48, preset.transpose);
+        let error = preset.set_transpose_semitones(25).unwrap_err();
+        assert_eq!("Transpose is 25, not between -24 and 24", error.to_string());
+        assert!(preset.set_transpose_semitones(-25).is_err());
+        assert_eq!(48, preset.transpose);
+    }
+
+    #[test]
+    fn fingerprint() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let brass = &presets[0];
+        // Pinned to catch changes to the fingerprint
+        assert_eq!(0x9be8_493d_25a8_9725, brass.fingerprint());
+        assert_ne!(brass.fingerprint(), presets[1].fingerprint());
+
+        let mut renamed = brass.clone();
+        renamed.set_name("TRUMPETS").unwrap();
+        assert_eq!(brass.fingerprint(), renamed.fingerprint());
+
+        let mut changed = brass.clone();
+        changed.operators[2].frequency_fine += 1;
+        assert_ne!(brass.fingerprint(), changed.fingerprint());
+
+        // Out of range values are the same as the values they are clamped to
+        let mut clamped = brass.clone();
+        clamped.transpose = 48;
+        let mut out_of_range = brass.clone();
+        out_of_range.transpose = 100;
+        assert_eq!(clamped.fingerprint(), out_of_range.fingerprint());
+
+        // Hashing agrees with equality
+        let hashes: std::collections::HashSet<_> = presets.iter().chain(&presets).collect();
+        assert_eq!(presets.len(), hashes.len());
+    }
+
+    #[test]
+    fn audible() {
+        // Algorithm 1 has operator 2 modulating operator 1 and a stack from
+        // operator 6, which feeds back into itself, to operator 3
+        let audible = |muted: &[usize]| {
+            let mut preset = Preset::default();
+            for (index, operator) in preset.operators.iter_mut().enumerate() {
+                operator.output_level = if muted.contains(&index) { 0 } else { 99 };
+            }
+            (0..Preset::OPERATOR_COUNT as OperatorId)
+                .map(|operator_id| preset.is_operator_audible(operator_id))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![true; 6], audible(&[]));
+        assert_eq!(vec![false, false, true, true, true, true], audible(&[0]));
+        assert_eq!(vec![true, false, true, true, true, true], audible(&[1]));
+        assert_eq!(vec![true, true, true, false, false, false], audible(&[3]));
+        assert_eq!(vec![true, true, true, true, false, false], audible(&[4]));
+        assert_eq!(vec![true, true, false, false, false, false], audible(&[2]));
+        assert_eq!(vec![false; 6], audible(&[0, 2]));
+
+        let preset = Preset::default();
+        assert!(preset.is_operator_audible(0));
+        assert!(!preset.is_operator_audible(1));
+        assert!(!preset.is_operator_audible(6));
+
+        // Operator 4 of FLUTE 1 has an output level of 0
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let flute = &presets[23];
+        assert_eq!("FLUTE   1", flute.name.to_string());
+        let audible: Vec<_> = (0..6).map(|id| flute.is_operator_audible(id)).collect();
+        assert_eq!(vec![true, true, true, false, true, true], audible);
+    }
+
     #[test]
     fn default() {
         let preset = Preset::default();
@@ -287,9 +940,10 @@ mod tests {
         // Pitch envelope generators.
         assert_eq!(99, preset.pitch_envelope.rates[0]);
         assert_eq!(50, preset.pitch_envelope.levels[0]);
+        assert_eq!([0.0; 4], preset.pitch_envelope_semitones());
 
         // General parameters.
-        assert_eq!(0, preset.algorithm_id);
+        assert_eq!(0, preset.algorithm_id.stored());
         assert!(preset.lfo_key_sync);
         assert!(preset.oscillator_key_sync);
         assert_eq!(35, preset.lfo_speed);
@@ -308,12 +962,209 @@ mod tests {
         // General operator parameters.
         assert_eq!(0, preset.operators[0].rate_scaling);
         assert_eq!(39, preset.operators[0].scaling_break_point);
-        assert_eq!(0, preset.operators[0].detune);
-        assert_eq!(OperatorMode::Fixed, preset.operators[0].mode);
+        assert_eq!(Detune::default(), preset.operators[0].detune);
+        assert_eq!(OperatorMode::Ratio, preset.operators[0].mode);
+        assert!(preset
+            .operators
+            .iter()
+            .all(|operator| operator.mode == OperatorMode::Ratio));
         assert_eq!(1, preset.operators[0].frequency_course);
         assert_eq!(0, preset.operators[0].frequency_fine);
     }
 
+    #[test]
+    fn preset_name_length() {
+        let name = PresetName::from_lossy(b"ORCH-CHIME");
+        assert_eq!(PresetName::MAX_LENGTH, name.to_string().len());
+        assert_eq!(*b"ORCH-CHIME", name.to_padded_bytes());
+
+        let name = PresetName::from_lossy(b"SYN-LEAD 1 AND MORE");
+        assert_eq!("SYN-LEAD 1", name.to_string());
+    }
+
+    #[test]
+    fn preset_name_new() {
+        assert_eq!("ORCH-CHIME", PresetName::new("ORCH-CHIME").unwrap().0);
+        assert_eq!(
+            Err(NameError::TooLong { length: 11 }),
+            PresetName::new("ORCH-CHIMES")
+        );
+        assert_eq!(
+            Err(NameError::InvalidCharacter {
+                index: 3,
+                character: '\t'
+            }),
+            PresetName::new("ABC\tD")
+        );
+
+        // Trailing spaces are trimmed but count towards the length
+        assert_eq!("  LEAD", PresetName::new("  LEAD    ").unwrap().0);
+        assert!(PresetName::new("LEAD       ").is_err());
+        assert_eq!("", PresetName::new("").unwrap().0);
+
+        assert_eq!(
+            PresetName::new("ORCH-CHIME"),
+            PresetName::try_from_bytes(b"ORCH-CHIME")
+        );
+        assert_eq!(
+            Err(NameError::TooLong { length: 11 }),
+            PresetName::try_from_bytes(b"ORCH-CHIMES")
+        );
+        assert_eq!(
+            Err(NameError::InvalidCharacter {
+                index: 3,
+                character: '\t'
+            }),
+            PresetName::try_from_bytes(b"ABC\tD")
+        );
+        assert_eq!(
+            Err(NameError::InvalidCharacter {
+                index: 0,
+                character: '\u{C1}'
+            }),
+            PresetName::try_from_bytes(&[0xC1])
+        );
+        assert_eq!("BASS", PresetName::try_from_bytes(b"BASS      ").unwrap().0);
+    }
+
+    #[test]
+    fn preset_name_charset() {
+        assert_eq!(96, PresetName::CHARSET.len());
+        assert_eq!(' ', PresetName::CHARSET[0]);
+        assert_eq!('A', PresetName::CHARSET[0x41 - 0x20]);
+        assert_eq!('¥', PresetName::CHARSET[0x5C - 0x20]);
+        assert_eq!('}', PresetName::CHARSET[0x7D - 0x20]);
+        assert_eq!('→', PresetName::CHARSET[0x7E - 0x20]);
+        assert_eq!('←', PresetName::CHARSET[0x7F - 0x20]);
+
+        let name = PresetName::from_lossy(b"100\x5C \x7EUP\x7F");
+        assert_eq!("100¥ →UP←", name.to_string());
+        assert_eq!(*b"100\x5C \x7EUP\x7F ", name.to_padded_bytes());
+        assert_eq!(name, PresetName::from_lossy(&name.to_padded_bytes()));
+        assert_eq!(Ok(name.clone()), PresetName::new("100¥ →UP←"));
+        assert_eq!(Ok(name), PresetName::try_from_bytes(b"100\x5C \x7EUP\x7F"));
+
+        let mut preset = Preset::default();
+        preset.set_name("¥EN").unwrap();
+        assert_eq!(0x5C, preset.to_packed()[118]);
+        let read = Preset::from_packed(&preset.to_packed()).unwrap();
+        assert_eq!("¥EN", read.name.to_string());
+
+        // ASCII characters that the display doesn't have
+        assert_eq!(
+            Err(NameError::InvalidCharacter {
+                index: 2,
+                character: '\\'
+            }),
+            PresetName::new("C:\\")
+        );
+        assert!(PresetName::new("~").is_err());
+        assert_eq!("A B", preset.set_name_lossy("A\\B").to_string());
+        assert_eq!(
+            *b"A B       ",
+            PresetName("A\\B".to_owned()).to_padded_bytes()
+        );
+    }
+
+    #[test]
+    fn preset_name_bytes() {
+        assert_eq!(*b"INIT VOICE", PresetName::default().to_padded_bytes());
+        assert_eq!(
+            *b"SAX       ",
+            PresetName::new("SAX").unwrap().to_padded_bytes()
+        );
+        assert_eq!(
+            *b"          ",
+            PresetName::new("").unwrap().to_padded_bytes()
+        );
+
+        // Characters that are only possible without checks
+        let unchecked = PresetName("ÄRGER\u{7}".to_owned());
+        assert!(unchecked.to_padded_bytes().iter().all(|byte| *byte < 0x80));
+        assert_eq!(*b" RGER     ", unchecked.to_padded_bytes());
+
+        for file in ["rom1a.syx", "dx9_bank.syx"] {
+            let bank = Bank::load_file(test_data_path(&[file])).unwrap();
+            for preset in &bank {
+                let bytes = preset.name.to_padded_bytes();
+                assert_eq!(preset.name, PresetName::from_lossy(&bytes));
+                assert_eq!(preset.name.to_string(), preset.name.as_str());
+            }
+        }
+        assert_eq!("INIT VOICE", PresetName::default().as_str());
+    }
+
+    #[test]
+    fn preset_name_parse() {
+        let name: PresetName = "E.PIANO 1".parse().unwrap();
+        assert_eq!("E.PIANO 1", name.as_str());
+        assert_eq!(Ok(name), PresetName::try_from("E.PIANO 1 "));
+        assert_eq!(
+            Err(NameError::TooLong { length: 12 }),
+            "E.PIANO 1 2 ".parse::<PresetName>()
+        );
+        assert_eq!(
+            Err(NameError::InvalidCharacter {
+                index: 7,
+                character: 'é'
+            }),
+            PresetName::try_from("E.PIANOé")
+        );
+        assert_eq!(PresetName::new("BASS ¥"), "BASS ¥".parse());
+    }
+
+    #[test]
+    fn set_name() {
+        let mut preset = Preset::default();
+        preset.set_name("ORCH-CHIME").unwrap();
+        assert_eq!("ORCH-CHIME", preset.name.to_string());
+        let read = Preset::from_packed(&preset.to_packed()).unwrap();
+        assert_eq!(preset.name, read.name);
+
+        preset.set_name("E.PIANO 1  ").unwrap_err();
+        preset.set_name("BASS  ").unwrap();
+        assert_eq!("BASS", preset.name.to_string());
+        preset.set_name("").unwrap();
+        assert_eq!("", preset.name.to_string());
+
+        preset.set_name("ORCH-CHIME").unwrap();
+        assert_eq!(
+            Err(NameError::TooLong { length: 11 }),
+            preset.set_name("SYN-LEAD 10")
+        );
+        assert_eq!(
+            Err(NameError::InvalidCharacter {
+                index: 3,
+                character: '\x07'
+            }),
+            preset.set_name("ABC\x07DEF")
+        );
+        assert_eq!(
+            Err(NameError::InvalidCharacter {
+                index: 5,
+                character: 'é'
+            }),
+            preset.set_name("CAFE é")
+        );
+        assert_eq!(
+            Err(NameError::InvalidCharacter {
+                index: 1,
+                character: '\x7F'
+            }),
+            preset.set_name("A\x7F")
+        );
+        assert_eq!("ORCH-CHIME", preset.name.to_string());
+
+        assert_eq!("ABC DEF", preset.set_name_lossy("ABC\x07DEF").to_string());
+        assert_eq!("CAFE", preset.set_name_lossy("CAFE é").to_string());
+        assert_eq!(
+            "caf  au la",
+            preset.set_name_lossy("café au lait").to_string()
+        );
+        let read = Preset::from_packed(&preset.to_packed()).unwrap();
+        assert_eq!(preset.name, read.name);
+    }
+
     #[test]
     fn normalize() {
         let preset = Preset {
@@ -326,5 +1177,52 @@ mod tests {
         assert_eq!(7, preset.feedback_level);
         assert_eq!(99, preset.lfo_delay);
         assert_eq!(48, preset.transpose);
+
+        let mut operators = [Operator::default(); Preset::OPERATOR_COUNT];
+        operators[2].output_level = 123;
+        let preset = Preset {
+            operators,
+            transpose: 200,
+            ..Default::default()
+        };
+        let mut out_of_range = Vec::new();
+        let normalized = preset.normalize_with(&mut out_of_range);
+        assert_eq!(preset.normalize(), normalized);
+        let out_of_range: Vec<_> = out_of_range.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            vec![
+                "Operator 3 output level is 123, not between 0 and 99",
+                "Transpose is 200, not between 0 and 48",
+            ],
+            out_of_range
+        );
+
+        let mut out_of_range = Vec::new();
+        Preset::default().normalize_with(&mut out_of_range);
+        assert!(out_of_range.is_empty());
+
+        // Ranges match the width of the packed bit fields
+        let mut operators = [Operator::default(); Preset::OPERATOR_COUNT];
+        operators[0].modulation_sensitivity = 4;
+        operators[0].rate_scaling = 8;
+        operators[0].velocity_sensitivity = 8;
+        operators[0].frequency_course = 32;
+        let preset = Preset {
+            operators,
+            algorithm_id: AlgorithmId::from_stored(40),
+            feedback_level: 8,
+            lfo_pitch_mod_sensitivity: 50,
+            ..Default::default()
+        }
+        .normalize();
+        assert_eq!(7, preset.lfo_pitch_mod_sensitivity);
+        assert_eq!(31, preset.algorithm_id.stored());
+        assert_eq!(7, preset.feedback_level);
+        let op1 = preset.operators[0];
+        assert_eq!(3, op1.modulation_sensitivity);
+        assert_eq!(7, op1.rate_scaling);
+        assert_eq!(7, op1.velocity_sensitivity);
+        assert_eq!(31, op1.frequency_course);
+        assert_eq!(preset, Preset::from_packed(&preset.to_packed()).unwrap());
     }
 }
@@ -0,0 +1,125 @@
+use crate::{Bank, Error, Preset};
+
+impl Bank {
+    /// Combine the presets chosen by the selector from several banks into
+    /// one bank, in the order of the banks and their slots. Presets that
+    /// sound the same as an earlier preset are left out, see
+    /// [`Bank::dedup`].
+    ///
+    /// An error is returned when more than [`Bank::PRESET_COUNT`] presets
+    /// remain. Use [`Bank::merge_into_banks`] to split them into several
+    /// banks instead.
+    ///
+    /// ```
+    /// use synthahol_dx7::Bank;
+    /// let rom1a = Bank::load_file("tests/rom1a.syx").unwrap();
+    /// let dx9 = Bank::load_file("tests/dx9_bank.syx").unwrap();
+    /// let merged = Bank::merge(&[rom1a, dx9], |preset| preset.is_percussive()).unwrap();
+    /// assert!(merged.iter().all(|preset| preset.is_percussive()));
+    /// ```
+    pub fn merge(sources: &[Bank], selector: impl Fn(&Preset) -> bool) -> Result<Bank, Error> {
+        let merged = Self::merge_unique(sources, selector);
+        if merged.len() > Self::PRESET_COUNT {
+            return Err(Error::TooManyPresets {
+                count: merged.len(),
+            });
+        }
+        Ok(merged)
+    }
+
+    /// Combine every preset from several banks into one bank. See
+    /// [`Bank::merge`].
+    pub fn merge_all(sources: &[Bank]) -> Result<Bank, Error> {
+        Self::merge(sources, |_| true)
+    }
+
+    /// Combine every preset from several banks, leaving out duplicates, and
+    /// split them into as many banks as are needed. Every bank is full
+    /// except the last.
+    pub fn merge_into_banks(sources: &[Bank]) -> Vec<Bank> {
+        Self::merge_unique(sources, |_| true)
+            .presets
+            .chunks(Self::PRESET_COUNT)
+            .map(|presets| Bank::new(presets.to_vec()))
+            .collect()
+    }
+
+    fn merge_unique(sources: &[Bank], selector: impl Fn(&Preset) -> bool) -> Bank {
+        let presets = sources
+            .iter()
+            .flatten()
+            .filter(|preset| selector(preset))
+            .cloned()
+            .collect();
+        let mut merged = Bank::new(presets);
+        merged.dedup();
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    /// ROM1A with oscillator key sync switched on or off in every voice, so
+    /// it shares no voices with ROM1A.
+    fn resynced(rom1a: &Bank) -> Bank {
+        let mut bank = rom1a.clone();
+        for preset in &mut bank {
+            preset.oscillator_key_sync = !preset.oscillator_key_sync;
+        }
+        bank
+    }
+
+    #[test]
+    fn same_bank() {
+        let rom1a = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let merged = Bank::merge_all(&[rom1a.clone(), rom1a.clone()]).unwrap();
+        assert_eq!(None, merged.name);
+        assert_eq!(rom1a.presets, merged.presets);
+
+        let banks = Bank::merge_into_banks(&[rom1a.clone(), rom1a.clone()]);
+        assert_eq!(1, banks.len());
+        assert_eq!(rom1a.presets, banks[0].presets);
+    }
+
+    #[test]
+    fn different_banks() {
+        let rom1a = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let sources = [rom1a.clone(), resynced(&rom1a)];
+        assert!(matches!(
+            Bank::merge_all(&sources),
+            Err(Error::TooManyPresets { count: 64 })
+        ));
+
+        let banks = Bank::merge_into_banks(&sources);
+        assert_eq!(2, banks.len());
+        assert_eq!(rom1a.presets, banks[0].presets);
+        assert_eq!(sources[1].presets, banks[1].presets);
+
+        // Duplicates are left out before splitting
+        let dx9 = Bank::load_file(test_data_path(&["dx9_bank.syx"])).unwrap();
+        let banks = Bank::merge_into_banks(&[rom1a.clone(), dx9.clone(), rom1a.clone()]);
+        assert_eq!(2, banks.len());
+        assert_eq!(Bank::PRESET_COUNT, banks[0].len());
+        let mut unique = dx9.clone();
+        unique.dedup();
+        assert_eq!(unique.presets, banks[1].presets);
+        assert!(Bank::merge_into_banks(&[]).is_empty());
+    }
+
+    #[test]
+    fn selector() {
+        let rom1a = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let sources = [resynced(&rom1a), rom1a.clone()];
+        let brass = Bank::merge(&sources, |preset| {
+            preset.name.to_string().starts_with("BRASS")
+        })
+        .unwrap();
+        let names: Vec<String> = brass.iter().map(|preset| preset.name.to_string()).collect();
+        assert_eq!(["BRASS   1", "BRASS   2", "BRASS   3"].repeat(2), names);
+        assert_eq!(sources[0][0], brass[0]);
+        assert_eq!(rom1a[0], brass[3]);
+    }
+}
@@ -0,0 +1,92 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::read::{read_message, CountingReader};
+use crate::*;
+
+/// A SysEx message from a DX7, identified by [`Format::detect`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    Bank(BankDump),
+    Voice(Preset),
+    MicroTuning(Box<MicroTuning>),
+
+    /// A message that is not decoded, including its header and end marker.
+    Other(Vec<u8>),
+}
+
+impl Message {
+    pub fn read_all_file<P: AsRef<Path>>(path: P) -> Result<Vec<Message>, Error> {
+        let input = File::open(&path)?;
+        let mut reader = BufReader::new(input);
+        Self::read_all(&mut reader)
+    }
+
+    /// Read every SysEx message in a file. Data between messages is
+    /// skipped.
+    pub fn read_all<R: Read>(reader: &mut R) -> Result<Vec<Message>, Error> {
+        let mut reader = CountingReader::new(reader, 0);
+        let mut messages = Vec::new();
+        while let Some((offset, message)) = read_message(&mut reader)? {
+            let mut message_reader = CountingReader::new(message.as_slice(), offset);
+            let read = match Format::detect(&message) {
+                Some(FormatKind::VoiceBank32) => {
+                    Bank::read_bank(&mut message_reader, ReadOptions::default()).map(Message::Bank)
+                }
+                Some(FormatKind::SingleVoice) => {
+                    Voice::load(&mut message_reader).map(Message::Voice)
+                }
+                Some(FormatKind::MicroTuning) => MicroTuning::load(&mut message_reader)
+                    .map(|tuning| Message::MicroTuning(Box::new(tuning))),
+                _ => Ok(Message::Other(message.clone())),
+            };
+            messages.push(read.map_err(|err| match err {
+                Error::At { .. } | Error::Voice { .. } => err,
+                err => message_reader.locate(err, offset),
+            })?);
+        }
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+
+    use super::*;
+
+    #[test]
+    fn read_all() {
+        let data = [
+            std::fs::read(test_data_path(&["rom1a.syx"])).unwrap(),
+            std::fs::read(test_data_path(&["tub_bells.syx"])).unwrap(),
+            std::fs::read(test_data_path(&["just_intonation.syx"])).unwrap(),
+            vec![0xF0, 0x7E, 0x00, 0x06, 0x01, 0xF7],
+        ]
+        .concat();
+
+        let messages = Message::read_all(&mut data.as_slice()).unwrap();
+        assert_eq!(4, messages.len());
+        let Message::Bank(dump) = &messages[0] else {
+            panic!("Expected a bank, found {:?}", messages[0]);
+        };
+        assert_eq!("BRASS   1", dump.presets[0].name.to_string());
+        let Message::Voice(preset) = &messages[1] else {
+            panic!("Expected a voice, found {:?}", messages[1]);
+        };
+        assert_eq!("TUB BELLS", preset.name.to_string());
+        assert!(matches!(&messages[2], Message::MicroTuning(_)));
+        assert_eq!(
+            Message::Other(vec![0xF0, 0x7E, 0x00, 0x06, 0x01, 0xF7]),
+            messages[3]
+        );
+
+        // Errors are located in the file
+        let mut data = data;
+        data[4104 + 70] ^= 1;
+        let error = Message::read_all(&mut data.as_slice()).unwrap_err();
+        assert!(matches!(error, Error::At { offset: 4104, .. }));
+        assert!(matches!(error.root_cause(), Error::ChecksumMismatch { .. }));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{Detune, Envelope, Operator, Preset, PresetName};
+
+/// Interpolate between two values, rounding to the nearest step.
+fn lerp(from: u8, to: u8, t: f32) -> u8 {
+    (from as f32 + (to as f32 - from as f32) * t).round() as u8
+}
+
+/// Choose between two values that can't be interpolated, switching half way.
+fn switch<T>(from: T, to: T, t: f32) -> T {
+    if t < 0.5 {
+        from
+    } else {
+        to
+    }
+}
+
+fn morph_envelope(from: &Envelope, to: &Envelope, t: f32) -> Envelope {
+    let mut envelope = *from;
+    for (rate, to) in envelope.rates.iter_mut().zip(to.rates) {
+        *rate = lerp(*rate, to, t);
+    }
+    for (level, to) in envelope.levels.iter_mut().zip(to.levels) {
+        *level = lerp(*level, to, t);
+    }
+    envelope
+}
+
+fn morph_operator(from: &Operator, to: &Operator, t: f32) -> Operator {
+    // Frequencies in different modes have no meaningful midpoint, so they
+    // switch with the mode.
+    let (frequency_course, frequency_fine) = if from.mode == to.mode {
+        (
+            lerp(from.frequency_course, to.frequency_course, t),
+            lerp(from.frequency_fine, to.frequency_fine, t),
+        )
+    } else {
+        switch(
+            (from.frequency_course, from.frequency_fine),
+            (to.frequency_course, to.frequency_fine),
+            t,
+        )
+    };
+    let detune =
+        from.detune.value() as f32 + (to.detune.value() as f32 - from.detune.value() as f32) * t;
+
+    Operator {
+        envelope: morph_envelope(&from.envelope, &to.envelope, t),
+        scaling_break_point: lerp(from.scaling_break_point, to.scaling_break_point, t),
+        scaling_left_depth: lerp(from.scaling_left_depth, to.scaling_left_depth, t),
+        scaling_right_depth: lerp(from.scaling_right_depth, to.scaling_right_depth, t),
+        scaling_left_curve: switch(from.scaling_left_curve, to.scaling_left_curve, t),
+        scaling_right_curve: switch(from.scaling_right_curve, to.scaling_right_curve, t),
+        detune: Detune::new(detune.round() as i8),
+        rate_scaling: lerp(from.rate_scaling, to.rate_scaling, t),
+        velocity_sensitivity: lerp(from.velocity_sensitivity, to.velocity_sensitivity, t),
+        modulation_sensitivity: lerp(from.modulation_sensitivity, to.modulation_sensitivity, t),
+        output_level: lerp(from.output_level, to.output_level, t),
+        mode: switch(from.mode, to.mode, t),
+        frequency_course,
+        frequency_fine,
+    }
+}
+
+impl Preset {
+    /// A preset part way between this preset and another, from 0 for this
+    /// preset to 1 for the other. Values of `t` outside that range are
+    /// clamped.
+    ///
+    /// Levels, rates, frequencies, depths, sensitivities and detune are
+    /// interpolated. The algorithm, LFO waveform, operator modes, scaling
+    /// curves and key syncs switch to the other preset half way. The name
+    /// is like `MORPH 42%` and can be changed with [`Preset::set_name`].
+    ///
+    /// ```
+    /// use synthahol_dx7::Preset;
+    /// let preset = Preset::default();
+    /// let mut other = preset.clone();
+    /// other.operators[0].output_level = 79;
+    /// let morph = preset.morph(&other, 0.5);
+    /// assert_eq!(89, morph.operators[0].output_level);
+    /// assert_eq!("MORPH 50%", morph.name.to_string());
+    /// ```
+    pub fn morph(&self, other: &Preset, t: f32) -> Preset {
+        // Also replaces NaN
+        let t = if t >= 0.0 { t.min(1.0) } else { 0.0 };
+
+        let mut operators = self.operators;
+        for (operator, other) in operators.iter_mut().zip(&other.operators) {
+            *operator = morph_operator(operator, other, t);
+        }
+        let name = format!("MORPH {}%", (t * 100.0).round());
+
+        Preset {
+            name: PresetName::from_lossy(name.as_bytes()),
+            operators,
+            pitch_envelope: morph_envelope(&self.pitch_envelope, &other.pitch_envelope, t),
+            algorithm_id: switch(self.algorithm_id, other.algorithm_id, t),
+            oscillator_key_sync: switch(self.oscillator_key_sync, other.oscillator_key_sync, t),
+            feedback_level: lerp(self.feedback_level, other.feedback_level, t),
+            lfo_speed: lerp(self.lfo_speed, other.lfo_speed, t),
+            lfo_delay: lerp(self.lfo_delay, other.lfo_delay, t),
+            lfo_pitch_mod_depth: lerp(self.lfo_pitch_mod_depth, other.lfo_pitch_mod_depth, t),
+            lfo_pitch_mod_sensitivity: lerp(
+                self.lfo_pitch_mod_sensitivity,
+                other.lfo_pitch_mod_sensitivity,
+                t,
+            ),
+            lfo_amplitude_mod_depth: lerp(
+                self.lfo_amplitude_mod_depth,
+                other.lfo_amplitude_mod_depth,
+                t,
+            ),
+            lfo_waveform: switch(self.lfo_waveform, other.lfo_waveform, t),
+            lfo_key_sync: switch(self.lfo_key_sync, other.lfo_key_sync, t),
+            transpose: lerp(self.transpose, other.transpose, t),
+        }
+        .normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::{Bank, OperatorMode};
+
+    use super::*;
+
+    #[test]
+    fn morph() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let (brass, tub_bells) = (&presets[0], &presets[25]);
+
+        let morph = brass.morph(tub_bells, 0.0);
+        assert_eq!("MORPH 0%", morph.name.to_string());
+        assert_eq!(vec!["name"], parameters(&brass.diff(&morph)));
+        let morph = brass.morph(tub_bells, 1.0);
+        assert_eq!("MORPH 100%", morph.name.to_string());
+        assert_eq!(vec!["name"], parameters(&tub_bells.diff(&morph)));
+
+        // Out of range is clamped
+        assert_eq!(brass.morph(tub_bells, 0.0), brass.morph(tub_bells, -1.0));
+        assert_eq!(
+            brass.morph(tub_bells, 0.0),
+            brass.morph(tub_bells, f32::NAN)
+        );
+        assert_eq!(brass.morph(tub_bells, 1.0), brass.morph(tub_bells, 7.0));
+
+        // Discrete values only take one of the two values
+        for step in 0..=20 {
+            let t = step as f32 / 20.0;
+            let morph = brass.morph(tub_bells, t);
+            assert_eq!(morph, morph.normalize());
+            let expected = if t < 0.5 { brass } else { tub_bells };
+            assert_eq!(expected.algorithm_id, morph.algorithm_id);
+            assert_eq!(expected.lfo_waveform, morph.lfo_waveform);
+            for (index, operator) in morph.operators.iter().enumerate() {
+                assert_eq!(expected.operators[index].mode, operator.mode);
+            }
+        }
+
+        // Fixed frequencies don't mix with ratios
+        let morph = brass.morph(tub_bells, 0.75);
+        assert_eq!(OperatorMode::Fixed, morph.operators[4].mode);
+        assert_eq!(
+            tub_bells.operators[4].fixed_frequency_hz(),
+            morph.operators[4].fixed_frequency_hz()
+        );
+    }
+
+    #[test]
+    fn midpoint() {
+        let mut from = Preset::default();
+        from.operators[0].output_level = 60;
+        let mut to = from.clone();
+        to.operators[0].output_level = 80;
+        to.operators[0].detune = Detune::new(4);
+
+        let morph = from.morph(&to, 0.5);
+        assert_eq!(70, morph.operators[0].output_level);
+        assert_eq!(2, morph.operators[0].detune.value());
+        assert_eq!(
+            vec!["name", "detune", "output level"],
+            parameters(&from.diff(&morph))
+        );
+    }
+
+    fn parameters(diff: &[crate::ParamDiff]) -> Vec<&'static str> {
+        diff.iter().map(|diff| diff.parameter).collect()
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt::{Display, Formatter};
+
+/// A parameter with a value outside of its valid range.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct OutOfRange {
+    /// Index into [`Preset::operators`](crate::Preset::operators), or `None`
+    /// for parameters of the whole preset.
+    pub operator: Option<usize>,
+
+    /// Name of the parameter, such as `"output level"`.
+    pub parameter: &'static str,
+
+    pub value: i16,
+    pub min: i16,
+    pub max: i16,
+}
+
+impl OutOfRange {
+    /// The value clamped to the valid range.
+    pub fn clamped(&self) -> i16 {
+        self.value.clamp(self.min, self.max)
+    }
+}
+
+impl Display for OutOfRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.operator {
+            // Operators are numbered from one like on the DX7
+            Some(operator) => write!(f, "Operator {} {}", operator + 1, self.parameter)?,
+            None => {
+                let mut chars = self.parameter.chars();
+                if let Some(first) = chars.next() {
+                    write!(f, "{}{}", first.to_uppercase(), chars.as_str())?;
+                }
+            }
+        }
+        write!(
+            f,
+            " is {}, not between {} and {}",
+            self.value, self.min, self.max
+        )
+    }
+}
+
+/// Clamps parameters to their valid ranges while recording the ones that
+/// were out of range.
+pub(crate) struct Normalizer<'a> {
+    pub(crate) operator: Option<usize>,
+    out_of_range: &'a mut Vec<OutOfRange>,
+}
+
+impl<'a> Normalizer<'a> {
+    pub(crate) fn new(out_of_range: &'a mut Vec<OutOfRange>) -> Self {
+        Self {
+            operator: None,
+            out_of_range,
+        }
+    }
+
+    pub(crate) fn clamp(&mut self, parameter: &'static str, value: u8, max: u8) -> u8 {
+        self.clamp_signed(parameter, value as i16, 0, max as i16) as u8
+    }
+
+    pub(crate) fn clamp_signed(
+        &mut self,
+        parameter: &'static str,
+        value: i16,
+        min: i16,
+        max: i16,
+    ) -> i16 {
+        if (min..=max).contains(&value) {
+            return value;
+        }
+        let out_of_range = OutOfRange {
+            operator: self.operator,
+            parameter,
+            value,
+            min,
+            max,
+        };
+        self.out_of_range.push(out_of_range);
+        out_of_range.clamped()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let out_of_range = OutOfRange {
+            operator: Some(2),
+            parameter: "output level",
+            value: 123,
+            min: 0,
+            max: 99,
+        };
+        assert_eq!(
+            "Operator 3 output level is 123, not between 0 and 99",
+            out_of_range.to_string()
+        );
+        assert_eq!(99, out_of_range.clamped());
+
+        let out_of_range = OutOfRange {
+            operator: None,
+            parameter: "transpose",
+            value: 200,
+            min: 0,
+            max: 48,
+        };
+        assert_eq!(
+            "Transpose is 200, not between 0 and 48",
+            out_of_range.to_string()
+        );
+    }
+}
@@ -0,0 +1,1249 @@
+use std::fmt::{Display, Formatter};
+
+use crate::normalize::Normalizer;
+use crate::{envelope, Envelope, EnvelopeError, OutOfRange, Preset};
+
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum OperatorMode {
+    Ratio = 0,
+    Fixed = 1,
+}
+
+impl Display for OperatorMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use OperatorMode::*;
+        let txt = match self {
+            Ratio => "Ratio",
+            Fixed => "Fixed",
+        };
+        f.write_str(txt)
+    }
+}
+
+/// Shape of the keyboard level scaling on one side of the break point.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[repr(u8)]
+pub enum ScalingCurve {
+    NegativeLinear = 0,
+    NegativeExponential = 1,
+    PositiveExponential = 2,
+    PositiveLinear = 3,
+}
+
+impl TryFrom<u8> for ScalingCurve {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        use ScalingCurve::*;
+        match value {
+            0 => Ok(NegativeLinear),
+            1 => Ok(NegativeExponential),
+            2 => Ok(PositiveExponential),
+            3 => Ok(PositiveLinear),
+            _ => Err("Unknown scaling curve"),
+        }
+    }
+}
+
+impl From<ScalingCurve> for u8 {
+    fn from(curve: ScalingCurve) -> Self {
+        curve as u8
+    }
+}
+
+/// The abbreviation shown on the DX7, such as `-LIN`.
+impl Display for ScalingCurve {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use ScalingCurve::*;
+        let txt = match self {
+            NegativeLinear => "-LIN",
+            NegativeExponential => "-EXP",
+            PositiveExponential => "+EXP",
+            PositiveLinear => "+LIN",
+        };
+        f.write_str(txt)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "crate::serde_support::OperatorFields")
+)]
+pub struct Operator {
+    // In the DX7 the operator ON/OFF state is not stored in the preset and
+    // is only used in parameter change sysex messages while editing a voice.
+    pub envelope: Envelope,
+    pub scaling_break_point: u8,
+    pub scaling_left_depth: u8,
+    pub scaling_right_depth: u8,
+    pub scaling_left_curve: ScalingCurve,
+    pub scaling_right_curve: ScalingCurve,
+
+    pub detune: Detune,
+
+    pub rate_scaling: u8,
+    pub velocity_sensitivity: u8,
+    pub modulation_sensitivity: u8,
+    pub output_level: u8,
+    pub mode: OperatorMode,
+    pub frequency_course: u8,
+    pub frequency_fine: u8,
+}
+
+/// Fine tuning of an operator from -7 to 7, stored as 0 to 14 in presets.
+/// Values out of range are clamped.
+///
+/// ```
+/// use synthahol_dx7::Detune;
+/// let detune = Detune::from_stored(4);
+/// assert_eq!(-3, detune.value());
+/// assert_eq!("-3", detune.to_string());
+/// assert_eq!("+7", Detune::new(9).to_string());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Detune(i8);
+
+impl Detune {
+    pub const MIN: i8 = -7;
+    pub const MAX: i8 = 7;
+
+    /// Detune of each step from the center, in cents.
+    ///
+    /// The DX7 detunes by about a cent a step around A440, less for higher
+    /// notes and more for lower ones. This uses the value around A440 for
+    /// all notes.
+    const CENTS_PER_STEP: f32 = 1.0;
+
+    /// Create from a value from -7 to 7.
+    pub const fn new(value: i8) -> Self {
+        if value < Self::MIN {
+            Self(Self::MIN)
+        } else if value > Self::MAX {
+            Self(Self::MAX)
+        } else {
+            Self(value)
+        }
+    }
+
+    /// Create from the value stored in a preset, from 0 to 14 where 7 is
+    /// no detune.
+    pub const fn from_stored(stored: u8) -> Self {
+        if stored > (Self::MAX - Self::MIN) as u8 {
+            Self(Self::MAX)
+        } else {
+            Self(stored as i8 + Self::MIN)
+        }
+    }
+
+    /// The value stored in a preset, from 0 to 14.
+    pub const fn stored(&self) -> u8 {
+        (self.0 - Self::MIN) as u8
+    }
+
+    /// The value from -7 to 7.
+    pub const fn value(&self) -> i8 {
+        self.0
+    }
+
+    /// Approximate pitch offset in cents.
+    pub fn cents(&self) -> f32 {
+        self.0 as f32 * Self::CENTS_PER_STEP
+    }
+
+    /// Ratio of the detuned frequency to the frequency without detune.
+    fn ratio(&self) -> f64 {
+        2f64.powf(self.cents() as f64 / 1200.0)
+    }
+}
+
+impl Display for Detune {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.0 > 0 {
+            write!(f, "+{}", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+/// MIDI note of A4, tuned to 440 Hz.
+const A4_NOTE: i16 = 69;
+
+/// MIDI note of the lowest break point, A-1 on the DX7.
+pub(crate) const BREAK_POINT_LOWEST_NOTE: u8 = 21;
+
+/// Highest break point, C8 on the DX7.
+const BREAK_POINT_MAX: u8 = 99;
+
+/// Exponential keyboard level scaling for each group of three semitones
+/// from the break point, from the
+/// [Music Synthesizer for Android](https://github.com/google/music-synthesizer-for-android)
+/// emulation of the DX7.
+const EXPONENTIAL_SCALING: [u8; 33] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 11, 14, 16, 19, 23, 27, 33, 39, 47, 56, 66, 80, 94, 110, 126,
+    142, 158, 174, 190, 206, 222, 238, 250,
+];
+
+/// Velocity response for every second MIDI velocity, from the Music
+/// Synthesizer for Android. A value of 239 leaves the level unchanged.
+const VELOCITY_RESPONSE: [u8; 64] = [
+    0, 70, 86, 97, 106, 114, 121, 126, 132, 138, 142, 148, 152, 156, 160, 163, 166, 170, 173, 174,
+    178, 181, 184, 186, 189, 190, 194, 196, 198, 200, 202, 205, 206, 209, 211, 214, 216, 218, 220,
+    222, 224, 225, 227, 229, 230, 232, 233, 235, 237, 238, 240, 241, 242, 243, 244, 246, 246, 248,
+    249, 250, 251, 252, 253, 254,
+];
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Why a keyboard level scaling break point can't be set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BreakPointError {
+    /// The MIDI note is outside A-1 to C8, MIDI notes 21 to 120.
+    NoteOutOfRange(u8),
+
+    /// The name is not a note like `C3` or `A#-1`.
+    InvalidName(String),
+}
+
+impl Display for BreakPointError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use BreakPointError::*;
+        match self {
+            NoteOutOfRange(note) => write!(
+                f,
+                "MIDI note {note} is not between {BREAK_POINT_LOWEST_NOTE} and {}",
+                BREAK_POINT_LOWEST_NOTE + BREAK_POINT_MAX
+            ),
+            InvalidName(name) => write!(f, "'{name}' is not a note name"),
+        }
+    }
+}
+
+impl std::error::Error for BreakPointError {}
+
+/// Name of a MIDI note using the Yamaha convention where middle C, MIDI
+/// note 60, is C3.
+pub(crate) fn note_name(note: u8) -> String {
+    let octave = (note / 12) as i8 - 2;
+    format!("{}{octave}", NOTE_NAMES[note as usize % 12])
+}
+
+/// MIDI note of a name like `C3` or `A#-1` using the Yamaha convention.
+pub(crate) fn note_from_name(name: &str) -> Option<u8> {
+    let split = name.find(|c: char| c == '-' || c.is_ascii_digit())?;
+    let (pitch, octave) = name.split_at(split);
+    let pitch = NOTE_NAMES
+        .iter()
+        .position(|note| note.eq_ignore_ascii_case(pitch))?;
+    let octave: i16 = octave.parse().ok()?;
+    let note = (octave + 2) * 12 + pitch as i16;
+    u8::try_from(note).ok().filter(|note| *note <= 127)
+}
+
+/// Frequency ratio of a coarse and fine frequency in ratio mode, without
+/// detune. A coarse frequency of 0 is a ratio of 0.5.
+pub(crate) fn ratio(coarse: u8, fine: u8) -> f64 {
+    let coarse = if coarse == 0 { 0.5 } else { coarse as f64 };
+    coarse * (100.0 + fine as f64) / 100.0
+}
+
+impl Operator {
+    /// MIDI note of the keyboard level scaling break point. The break
+    /// point is stored from 0 to 99 for A-1 to C8 on the DX7, which are MIDI
+    /// notes 21 to 120. Stored values above 99 are treated as 99.
+    pub fn break_point_midi_note(&self) -> u8 {
+        self.scaling_break_point.min(BREAK_POINT_MAX) + BREAK_POINT_LOWEST_NOTE
+    }
+
+    /// Name of the keyboard level scaling break point as shown on the DX7,
+    /// where middle C is C3.
+    ///
+    /// ```
+    /// use synthahol_dx7::Operator;
+    /// assert_eq!("C3", Operator::default().break_point_name());
+    /// ```
+    pub fn break_point_name(&self) -> String {
+        note_name(self.break_point_midi_note())
+    }
+
+    /// Change to the output level from keyboard level scaling when playing
+    /// a MIDI note, negative when the level is reduced.
+    ///
+    /// Notes below the break point use the left depth and curve and notes
+    /// above it use the right ones. Linear curves change the level by up to
+    /// about 32 per octave at a depth of 99, and exponential curves change
+    /// it slowly near the break point and quickly far from it. The offset is
+    /// limited to -99 to 99, which is enough to silence any operator.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Operator, ScalingCurve};
+    /// let operator = Operator {
+    ///     scaling_right_depth: 99,
+    ///     scaling_right_curve: ScalingCurve::NegativeLinear,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(0, operator.scaling_offset_at(60));
+    /// assert_eq!(-31, operator.scaling_offset_at(72));
+    /// ```
+    pub fn scaling_offset_at(&self, midi_note: u8) -> i8 {
+        let break_point = self.break_point_midi_note();
+        let offset = if midi_note >= break_point {
+            self.scaling_right_curve
+                .offset(midi_note - break_point, self.scaling_right_depth)
+        } else {
+            self.scaling_left_curve
+                .offset(break_point - midi_note, self.scaling_left_depth)
+        };
+        offset.clamp(-99, 99) as i8
+    }
+
+    /// Output level after keyboard level scaling when playing a MIDI note,
+    /// from 0 to 99.
+    pub fn scaled_output_level(&self, midi_note: u8) -> u8 {
+        let level = self.output_level as i16 + self.scaling_offset_at(midi_note) as i16;
+        level.clamp(0, 99) as u8
+    }
+
+    /// Output level when playing a note with a MIDI velocity from 0 to 127,
+    /// from 0 to 99.
+    ///
+    /// A velocity sensitivity of 0 ignores the velocity. Higher
+    /// sensitivities lower the level of soft notes, by up to 104 at a
+    /// velocity of 0 and a sensitivity of 7, and raise the level of the
+    /// hardest notes by up to 7. This follows the
+    /// [Music Synthesizer for Android](https://github.com/google/music-synthesizer-for-android)
+    /// emulation of the DX7, whose levels match output levels above 20.
+    ///
+    /// ```
+    /// use synthahol_dx7::Operator;
+    /// let operator = Operator {
+    ///     output_level: 90,
+    ///     velocity_sensitivity: 7,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(97, operator.level_at_velocity(127));
+    /// assert_eq!(58, operator.level_at_velocity(32));
+    /// ```
+    pub fn level_at_velocity(&self, velocity: u8) -> u8 {
+        let response = VELOCITY_RESPONSE[velocity.min(127) as usize >> 1] as i32 - 239;
+        let sensitivity = self.velocity_sensitivity.min(7) as i32;
+
+        // In 32nds of a level
+        let offset = ((sensitivity * response + 7) >> 3) << 4;
+        let level = self.output_level.min(99) as i32 * 32 + offset;
+        (level.clamp(0, 99 * 32) / 32) as u8
+    }
+
+    /// How much rate scaling speeds up the envelope when playing a MIDI
+    /// note, as an increase of the rates.
+    ///
+    /// Following the
+    /// [Music Synthesizer for Android](https://github.com/google/music-synthesizer-for-android)
+    /// emulation of the DX7, rates increase every three semitones from
+    /// MIDI note 23 up to note 114, by up to 42 at a rate scaling of 7.
+    pub fn rate_scaling_offset(&self, midi_note: u8) -> u8 {
+        let steps = (midi_note / 3).saturating_sub(7).min(31);
+
+        // In the units of the internal rate, which goes from 0 to 63
+        let offset = (self.rate_scaling.min(7) * steps) >> 3;
+        ((offset as u16 * 64 + 20) / 41) as u8
+    }
+
+    /// Rates of the envelope after rate scaling when playing a MIDI note,
+    /// from 0 to 99.
+    ///
+    /// ```
+    /// use synthahol_dx7::Operator;
+    /// let mut operator = Operator::default();
+    /// operator.envelope.rates = [50, 60, 70, 80];
+    /// operator.rate_scaling = 7;
+    /// assert_eq!([50, 60, 70, 80], operator.scaled_envelope_rates(21));
+    /// assert_eq!([92, 99, 99, 99], operator.scaled_envelope_rates(127));
+    /// ```
+    pub fn scaled_envelope_rates(&self, midi_note: u8) -> [u8; Envelope::SEGMENT_COUNT] {
+        let offset = self.rate_scaling_offset(midi_note);
+        self.envelope
+            .rates
+            .map(|rate| rate.saturating_add(offset).min(Envelope::MAX_VALUE))
+    }
+
+    /// Set the keyboard level scaling break point to a MIDI note from 21 to
+    /// 120, A-1 to C8 on the DX7. Other notes are rejected and the break
+    /// point is unchanged.
+    pub fn set_break_point_from_midi(&mut self, note: u8) -> Result<(), BreakPointError> {
+        let range = BREAK_POINT_LOWEST_NOTE..=BREAK_POINT_LOWEST_NOTE + BREAK_POINT_MAX;
+        if !range.contains(&note) {
+            return Err(BreakPointError::NoteOutOfRange(note));
+        }
+        self.scaling_break_point = note - BREAK_POINT_LOWEST_NOTE;
+        Ok(())
+    }
+
+    /// Set the keyboard level scaling break point to a note name like `C3`
+    /// or `A#-1`, where middle C is C3. Notes outside A-1 to C8 are
+    /// rejected and the break point is unchanged.
+    ///
+    /// ```
+    /// use synthahol_dx7::Operator;
+    /// let mut operator = Operator::default();
+    /// operator.set_break_point_from_name("A#2").unwrap();
+    /// assert_eq!(58, operator.break_point_midi_note());
+    /// assert!(operator.set_break_point_from_name("C9").is_err());
+    /// ```
+    pub fn set_break_point_from_name(&mut self, name: &str) -> Result<(), BreakPointError> {
+        let note =
+            note_from_name(name).ok_or_else(|| BreakPointError::InvalidName(name.to_owned()))?;
+        self.set_break_point_from_midi(note)
+    }
+
+    /// Ratio of the operator frequency to the frequency of the note, or
+    /// `None` for operators in fixed mode.
+    ///
+    /// The ratio is the coarse frequency, where 0 is 0.5, increased by the
+    /// fine frequency in hundredths and adjusted by the detune.
+    ///
+    /// ```
+    /// use synthahol_dx7::Operator;
+    /// let operator = Operator {
+    ///     frequency_course: 3,
+    ///     frequency_fine: 14,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(Some(3.42), operator.frequency_ratio());
+    /// ```
+    pub fn frequency_ratio(&self) -> Option<f64> {
+        match self.mode {
+            OperatorMode::Ratio => {
+                let ratio = ratio(self.frequency_course, self.frequency_fine);
+                Some(ratio * self.detune.ratio())
+            }
+            OperatorMode::Fixed => None,
+        }
+    }
+
+    /// Frequency of an operator in fixed mode in Hz, or `None` for
+    /// operators in ratio mode.
+    ///
+    /// The coarse frequency selects 1, 10, 100 or 1000 Hz, repeating every
+    /// four steps, and the fine frequency multiplies it by up to 9.772 on a
+    /// logarithmic scale. The frequency is adjusted by the detune.
+    ///
+    /// The DX7 calculates frequencies in a logarithmic format with limited
+    /// precision and shows them rounded to four digits, such as 9.772 Hz.
+    /// Frequencies from the hardware are within about 0.1% of these.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Operator, OperatorMode};
+    /// let operator = Operator {
+    ///     mode: OperatorMode::Fixed,
+    ///     frequency_course: 2,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(Some(100.0), operator.fixed_frequency_hz());
+    /// ```
+    pub fn fixed_frequency_hz(&self) -> Option<f64> {
+        match self.mode {
+            OperatorMode::Ratio => None,
+            OperatorMode::Fixed => {
+                let exponent =
+                    (self.frequency_course % 4) as f64 + self.frequency_fine as f64 / 100.0;
+                Some(10f64.powf(exponent) * self.detune.ratio())
+            }
+        }
+    }
+
+    /// Clamp all parameters to valid ranges, recording the ones that were
+    /// out of range.
+    pub(crate) fn normalize_with(&self, n: &mut Normalizer) -> Self {
+        Self {
+            envelope: self
+                .envelope
+                .normalize_with(n, &envelope::OPERATOR_ENVELOPE_NAMES),
+            scaling_break_point: n.clamp("break point", self.scaling_break_point, 99),
+            scaling_left_depth: n.clamp("left depth", self.scaling_left_depth, 99),
+            scaling_right_depth: n.clamp("right depth", self.scaling_right_depth, 99),
+            scaling_left_curve: self.scaling_left_curve,
+            scaling_right_curve: self.scaling_right_curve,
+            detune: self.detune,
+            rate_scaling: n.clamp("rate scaling", self.rate_scaling, 7),
+            velocity_sensitivity: n.clamp("velocity sensitivity", self.velocity_sensitivity, 7),
+            modulation_sensitivity: n.clamp(
+                "modulation sensitivity",
+                self.modulation_sensitivity,
+                3,
+            ),
+            output_level: n.clamp("output level", self.output_level, 99),
+            frequency_course: n.clamp("frequency coarse", self.frequency_course, 31),
+            frequency_fine: n.clamp("frequency fine", self.frequency_fine, 99),
+            mode: self.mode,
+        }
+    }
+
+    /// Frequency of the operator in Hz when playing a MIDI note, using
+    /// equal temperament tuned to A440.
+    ///
+    /// Operators in ratio mode follow the note, shifted by the preset
+    /// transpose. The transpose is stored from 0 to 48, where 24 does not
+    /// shift the note, and is clamped to that range. Operators in fixed mode
+    /// ignore the note and the transpose. Notes above 127 are treated as 127.
+    ///
+    /// ```
+    /// use synthahol_dx7::Operator;
+    /// let frequency = Operator::default().frequency_for_note(69, 24);
+    /// assert!((frequency - 440.0).abs() < 1e-9);
+    /// ```
+    pub fn frequency_for_note(&self, midi_note: u8, transpose: u8) -> f64 {
+        if let Some(frequency) = self.fixed_frequency_hz() {
+            return frequency;
+        }
+        let center = Preset::TRANSPOSE_CENTER;
+        let note = midi_note.min(127) as i16 + transpose.min(2 * center) as i16 - center as i16;
+        let fundamental = 440.0 * 2f64.powf((note - A4_NOTE) as f64 / 12.0);
+        fundamental * self.frequency_ratio().unwrap_or(1.0)
+    }
+}
+
+/// One line summary of the operator with the frequency shown like on the
+/// DX7, such as `Ratio 1.00` or `Fixed 100.0 Hz`.
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let frequency = match self.mode {
+            OperatorMode::Ratio => {
+                format!("{:.2}", ratio(self.frequency_course, self.frequency_fine))
+            }
+            OperatorMode::Fixed => {
+                let range = self.frequency_course % 4;
+                let frequency = 10f64.powf(range as f64 + self.frequency_fine as f64 / 100.0);
+                let decimals = 3 - range as usize;
+                format!("{frequency:.decimals$} Hz")
+            }
+        };
+        write!(
+            f,
+            "{:<5} {frequency:<8} level {:>2} detune {:>2} scaling {:<4} {} {:>2} {} {:>2} EG {}",
+            self.mode.to_string(),
+            self.output_level,
+            self.detune.to_string(),
+            self.break_point_name(),
+            self.scaling_left_curve,
+            self.scaling_left_depth,
+            self.scaling_right_curve,
+            self.scaling_right_depth,
+            self.envelope
+        )
+    }
+}
+
+impl ScalingCurve {
+    /// Level offset of the curve for a distance in semitones from the
+    /// break point.
+    fn offset(&self, semitones: u8, depth: u8) -> i32 {
+        use ScalingCurve::*;
+
+        // The DX7 scales in groups of three semitones
+        let group = (semitones as usize + 1) / 3;
+        let depth = depth as i32;
+        let offset = match self {
+            NegativeLinear | PositiveLinear => (group as i32 * depth * 329) >> 12,
+            NegativeExponential | PositiveExponential => {
+                let scaling = EXPONENTIAL_SCALING[group.min(EXPONENTIAL_SCALING.len() - 1)];
+                (scaling as i32 * depth * 329) >> 15
+            }
+        };
+        match self {
+            NegativeLinear | NegativeExponential => -offset,
+            PositiveLinear | PositiveExponential => offset,
+        }
+    }
+}
+
+impl Default for Operator {
+    fn default() -> Self {
+        // The last envelope generator has a different default level according to
+        // the DX7 II manual.
+        let mut envelope = Envelope::default();
+        envelope.levels[envelope.levels.len() - 1] = 0;
+
+        Operator {
+            envelope,
+            scaling_break_point: 39,
+            scaling_left_depth: 0,
+            scaling_right_depth: 0,
+            scaling_left_curve: ScalingCurve::NegativeLinear,
+            scaling_right_curve: ScalingCurve::NegativeLinear,
+            detune: Detune::default(),
+            rate_scaling: 0,
+            velocity_sensitivity: 0,
+            modulation_sensitivity: 0,
+            output_level: 0,
+            mode: OperatorMode::Ratio,
+            frequency_course: 1,
+            frequency_fine: 0,
+        }
+    }
+}
+
+/// Why an operator can't be built.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamError {
+    /// A parameter is outside of its valid range.
+    OutOfRange(OutOfRange),
+
+    /// The fixed frequency in Hz is not between 1 and 9772.
+    FixedFrequency(f64),
+
+    /// The envelope has a rate or level out of range.
+    Envelope(EnvelopeError),
+}
+
+impl Display for ParamError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use ParamError::*;
+        match self {
+            OutOfRange(out_of_range) => out_of_range.fmt(f),
+            FixedFrequency(frequency) => {
+                write!(
+                    f,
+                    "Fixed frequency {frequency} Hz is not between 1 and 9772 Hz"
+                )
+            }
+            Envelope(error) => write!(f, "Envelope: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+/// Builds an operator, checking each parameter as it is set. The first
+/// parameter out of range is reported when the operator is built.
+///
+/// A simple FM pair with a modulator at twice the frequency of the carrier,
+/// using the first algorithm where operator 2 modulates operator 1:
+///
+/// ```
+/// use synthahol_dx7::{OperatorBuilder, Preset};
+/// let carrier = OperatorBuilder::carrier().build().unwrap();
+/// let modulator = OperatorBuilder::modulator(75).ratio(2, 0).build().unwrap();
+///
+/// let mut preset = Preset::default();
+/// preset.operators[0] = carrier;
+/// preset.operators[1] = modulator;
+/// assert_eq!(Some(2.0), preset.operators[1].frequency_ratio());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OperatorBuilder {
+    operator: Operator,
+    error: Option<ParamError>,
+}
+
+impl OperatorBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from an operator at full output level.
+    pub fn carrier() -> Self {
+        Self::new().output_level(99)
+    }
+
+    /// Start from an operator with an output level from 0 to 99, which sets
+    /// the modulation depth.
+    pub fn modulator(output_level: u8) -> Self {
+        Self::new().output_level(output_level)
+    }
+
+    /// Record the first parameter that is out of range.
+    fn check(&mut self, parameter: &'static str, value: i16, min: i16, max: i16) -> bool {
+        let in_range = (min..=max).contains(&value);
+        if !in_range && self.error.is_none() {
+            self.error = Some(ParamError::OutOfRange(OutOfRange {
+                operator: None,
+                parameter,
+                value,
+                min,
+                max,
+            }));
+        }
+        in_range
+    }
+
+    pub fn output_level(mut self, output_level: u8) -> Self {
+        if self.check("output level", output_level as i16, 0, 99) {
+            self.operator.output_level = output_level;
+        }
+        self
+    }
+
+    /// Use ratio mode with a coarse frequency from 0 to 31 and a fine
+    /// frequency from 0 to 99.
+    pub fn ratio(mut self, coarse: u8, fine: u8) -> Self {
+        let coarse_in_range = self.check("frequency coarse", coarse as i16, 0, 31);
+        if self.check("frequency fine", fine as i16, 0, 99) && coarse_in_range {
+            self.operator.mode = OperatorMode::Ratio;
+            self.operator.frequency_course = coarse;
+            self.operator.frequency_fine = fine;
+        }
+        self
+    }
+
+    /// Use fixed mode with the closest frequency to one from 1 to 9772 Hz.
+    pub fn fixed_hz(mut self, frequency: f64) -> Self {
+        // Each step of the fine frequency is a hundredth of a decade
+        let steps = (frequency.log10() * 100.0).round();
+        if (0.0..400.0).contains(&steps) {
+            let steps = steps as u16;
+            self.operator.mode = OperatorMode::Fixed;
+            self.operator.frequency_course = (steps / 100) as u8;
+            self.operator.frequency_fine = (steps % 100) as u8;
+        } else if self.error.is_none() {
+            self.error = Some(ParamError::FixedFrequency(frequency));
+        }
+        self
+    }
+
+    /// Detune from -7 to 7.
+    pub fn detune(mut self, detune: i8) -> Self {
+        if self.check(
+            "detune",
+            detune as i16,
+            Detune::MIN as i16,
+            Detune::MAX as i16,
+        ) {
+            self.operator.detune = Detune::new(detune);
+        }
+        self
+    }
+
+    pub fn envelope(mut self, envelope: Envelope) -> Self {
+        match Envelope::new(envelope.rates, envelope.levels) {
+            Ok(envelope) => self.operator.envelope = envelope,
+            Err(error) => {
+                self.error.get_or_insert(ParamError::Envelope(error));
+            }
+        }
+        self
+    }
+
+    /// Keyboard level scaling with a break point and depths from 0 to 99.
+    pub fn scaling(
+        mut self,
+        break_point: u8,
+        left_depth: u8,
+        right_depth: u8,
+        left_curve: ScalingCurve,
+        right_curve: ScalingCurve,
+    ) -> Self {
+        let break_point_in_range = self.check("break point", break_point as i16, 0, 99);
+        let left_in_range = self.check("left depth", left_depth as i16, 0, 99);
+        let right_in_range = self.check("right depth", right_depth as i16, 0, 99);
+        if break_point_in_range && left_in_range && right_in_range {
+            self.operator.scaling_break_point = break_point;
+            self.operator.scaling_left_depth = left_depth;
+            self.operator.scaling_right_depth = right_depth;
+            self.operator.scaling_left_curve = left_curve;
+            self.operator.scaling_right_curve = right_curve;
+        }
+        self
+    }
+
+    /// Rate scaling from 0 to 7.
+    pub fn rate_scaling(mut self, rate_scaling: u8) -> Self {
+        if self.check("rate scaling", rate_scaling as i16, 0, 7) {
+            self.operator.rate_scaling = rate_scaling;
+        }
+        self
+    }
+
+    /// Velocity sensitivity from 0 to 7.
+    pub fn velocity_sensitivity(mut self, velocity_sensitivity: u8) -> Self {
+        if self.check("velocity sensitivity", velocity_sensitivity as i16, 0, 7) {
+            self.operator.velocity_sensitivity = velocity_sensitivity;
+        }
+        self
+    }
+
+    /// Amplitude modulation sensitivity from 0 to 3.
+    pub fn modulation_sensitivity(mut self, modulation_sensitivity: u8) -> Self {
+        if self.check(
+            "modulation sensitivity",
+            modulation_sensitivity as i16,
+            0,
+            3,
+        ) {
+            self.operator.modulation_sensitivity = modulation_sensitivity;
+        }
+        self
+    }
+
+    pub fn build(&self) -> Result<Operator, ParamError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.operator),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaling_curve() {
+        for value in 0..4 {
+            let curve = ScalingCurve::try_from(value).unwrap();
+            assert_eq!(value, u8::from(curve));
+        }
+        assert!(ScalingCurve::try_from(4).is_err());
+        let names: Vec<_> = (0..4)
+            .map(|value| ScalingCurve::try_from(value).unwrap().to_string())
+            .collect();
+        assert_eq!(vec!["-LIN", "-EXP", "+EXP", "+LIN"], names);
+    }
+
+    #[test]
+    fn break_point() {
+        let mut operator = Operator::default();
+        assert_eq!(39, operator.scaling_break_point);
+        assert_eq!(60, operator.break_point_midi_note());
+        assert_eq!("C3", operator.break_point_name());
+
+        operator.scaling_break_point = 0;
+        assert_eq!(21, operator.break_point_midi_note());
+        assert_eq!("A-1", operator.break_point_name());
+        operator.scaling_break_point = 99;
+        assert_eq!(120, operator.break_point_midi_note());
+        assert_eq!("C8", operator.break_point_name());
+        operator.scaling_break_point = 1;
+        assert_eq!("A#-1", operator.break_point_name());
+
+        for stored in 0..=99 {
+            let mut operator = Operator {
+                scaling_break_point: stored,
+                ..Default::default()
+            };
+            let note = operator.break_point_midi_note();
+            let name = operator.break_point_name();
+            operator.set_break_point_from_midi(note).unwrap();
+            assert_eq!(stored, operator.scaling_break_point);
+            operator.scaling_break_point = 50;
+            operator.set_break_point_from_name(&name).unwrap();
+            assert_eq!(stored, operator.scaling_break_point);
+        }
+
+        let mut operator = Operator::default();
+        operator.set_break_point_from_name("a#2").unwrap();
+        assert_eq!(58, operator.break_point_midi_note());
+        assert_eq!(
+            Err(BreakPointError::NoteOutOfRange(20)),
+            operator.set_break_point_from_midi(20)
+        );
+        assert_eq!(
+            Err(BreakPointError::NoteOutOfRange(121)),
+            operator.set_break_point_from_midi(121)
+        );
+        assert_eq!(
+            Err(BreakPointError::NoteOutOfRange(20)),
+            operator.set_break_point_from_name("G#-1")
+        );
+        assert_eq!(
+            Err(BreakPointError::NoteOutOfRange(121)),
+            operator.set_break_point_from_name("C#8")
+        );
+        for name in ["", "C", "H3", "C#", "3", "Cb3", "C-3", "C99"] {
+            assert_eq!(
+                Err(BreakPointError::InvalidName(name.to_owned())),
+                operator.set_break_point_from_name(name),
+                "{name}"
+            );
+        }
+        assert_eq!(58, operator.break_point_midi_note());
+    }
+
+    #[test]
+    fn scaling_offset_at() {
+        let operator = Operator {
+            output_level: 80,
+            scaling_left_depth: 50,
+            scaling_right_depth: 50,
+            scaling_left_curve: ScalingCurve::NegativeExponential,
+            scaling_right_curve: ScalingCurve::PositiveLinear,
+            ..Default::default()
+        };
+
+        // No change at the break point
+        assert_eq!(0, operator.scaling_offset_at(60));
+        assert_eq!(80, operator.scaled_output_level(60));
+
+        // Rises linearly above the break point
+        let octave = operator.scaling_offset_at(72);
+        assert!(octave > 0);
+        assert_eq!(2 * octave, operator.scaling_offset_at(84));
+        assert_eq!(3 * octave, operator.scaling_offset_at(96));
+        assert_eq!(99, operator.scaled_output_level(96));
+
+        // Falls faster and faster below the break point
+        let falls: Vec<_> = [48, 36, 24]
+            .iter()
+            .map(|note| operator.scaling_offset_at(*note))
+            .collect();
+        assert!(falls.iter().all(|offset| *offset < 0));
+        assert!(falls[1] - falls[2] > falls[0] - falls[1]);
+        assert_eq!((80 + falls[2]) as u8, operator.scaled_output_level(24));
+
+        // Always within the range of levels
+        let operator = Operator {
+            output_level: 10,
+            scaling_break_point: 99,
+            scaling_left_depth: 99,
+            scaling_left_curve: ScalingCurve::NegativeLinear,
+            ..Default::default()
+        };
+        assert_eq!(-99, operator.scaling_offset_at(0));
+        assert_eq!(0, operator.scaled_output_level(0));
+
+        let operator = Operator {
+            output_level: 70,
+            scaling_left_curve: ScalingCurve::PositiveExponential,
+            scaling_right_curve: ScalingCurve::NegativeLinear,
+            ..Default::default()
+        };
+        for note in 0..=127 {
+            assert_eq!(0, operator.scaling_offset_at(note));
+            assert_eq!(70, operator.scaled_output_level(note));
+        }
+    }
+
+    #[test]
+    fn builder() {
+        assert_eq!(Ok(Operator::default()), OperatorBuilder::new().build());
+        let carrier = OperatorBuilder::carrier().build().unwrap();
+        assert_eq!(99, carrier.output_level);
+
+        let operator = OperatorBuilder::modulator(60)
+            .ratio(0, 50)
+            .detune(-3)
+            .scaling(
+                27,
+                10,
+                20,
+                ScalingCurve::PositiveLinear,
+                ScalingCurve::NegativeExponential,
+            )
+            .rate_scaling(3)
+            .velocity_sensitivity(2)
+            .modulation_sensitivity(1)
+            .envelope(Envelope::new([90, 80, 70, 60], [99, 80, 0, 0]).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(60, operator.output_level);
+        assert_eq!(OperatorMode::Ratio, operator.mode);
+        assert_eq!(
+            (0, 50),
+            (operator.frequency_course, operator.frequency_fine)
+        );
+        assert_eq!(-3, operator.detune.value());
+        assert_eq!("C2", operator.break_point_name());
+        assert_eq!(10, operator.scaling_left_depth);
+        assert_eq!(20, operator.scaling_right_depth);
+        assert_eq!(ScalingCurve::PositiveLinear, operator.scaling_left_curve);
+        assert_eq!(
+            ScalingCurve::NegativeExponential,
+            operator.scaling_right_curve
+        );
+        assert_eq!(3, operator.rate_scaling);
+        assert_eq!(2, operator.velocity_sensitivity);
+        assert_eq!(1, operator.modulation_sensitivity);
+        assert_eq!(0, operator.envelope.sustain_level());
+
+        for (frequency, coarse, fine) in
+            [(1.0, 0, 0), (100.0, 2, 0), (440.0, 2, 64), (9772.0, 3, 99)]
+        {
+            let operator = OperatorBuilder::new().fixed_hz(frequency).build().unwrap();
+            assert_eq!(OperatorMode::Fixed, operator.mode);
+            assert_eq!(
+                (coarse, fine),
+                (operator.frequency_course, operator.frequency_fine)
+            );
+        }
+    }
+
+    #[test]
+    fn builder_errors() {
+        let parameter = |builder: OperatorBuilder| match builder.build() {
+            Err(ParamError::OutOfRange(out_of_range)) => {
+                (out_of_range.parameter, out_of_range.value)
+            }
+            result => panic!("Unexpected {result:?}"),
+        };
+        let builder = OperatorBuilder::new();
+        assert_eq!(("output level", 100), parameter(builder.output_level(100)));
+        assert_eq!(
+            ("output level", 100),
+            parameter(OperatorBuilder::modulator(100))
+        );
+        assert_eq!(("frequency coarse", 32), parameter(builder.ratio(32, 0)));
+        assert_eq!(("frequency fine", 100), parameter(builder.ratio(1, 100)));
+        assert_eq!(("detune", -8), parameter(builder.detune(-8)));
+        assert_eq!(("detune", 8), parameter(builder.detune(8)));
+        let curve = ScalingCurve::NegativeLinear;
+        assert_eq!(
+            ("break point", 100),
+            parameter(builder.scaling(100, 0, 0, curve, curve))
+        );
+        assert_eq!(
+            ("left depth", 100),
+            parameter(builder.scaling(0, 100, 0, curve, curve))
+        );
+        assert_eq!(
+            ("right depth", 100),
+            parameter(builder.scaling(0, 0, 100, curve, curve))
+        );
+        assert_eq!(("rate scaling", 8), parameter(builder.rate_scaling(8)));
+        assert_eq!(
+            ("velocity sensitivity", 70),
+            parameter(builder.velocity_sensitivity(70))
+        );
+        assert_eq!(
+            ("modulation sensitivity", 4),
+            parameter(builder.modulation_sensitivity(4))
+        );
+
+        // The first error is reported
+        assert_eq!(
+            ("rate scaling", 9),
+            parameter(builder.rate_scaling(9).output_level(100).detune(1))
+        );
+
+        for frequency in [0.5, 9900.0, f64::NAN, f64::INFINITY, -1.0] {
+            let error = builder.fixed_hz(frequency).build().unwrap_err();
+            assert!(
+                matches!(error, ParamError::FixedFrequency(_)),
+                "{frequency}"
+            );
+        }
+
+        let envelope = Envelope {
+            rates: [99, 99, 120, 99],
+            levels: [99; 4],
+        };
+        assert_eq!(
+            Err(ParamError::Envelope(EnvelopeError::RateOutOfRange {
+                segment: 2,
+                rate: 120
+            })),
+            builder.envelope(envelope).build()
+        );
+    }
+
+    #[test]
+    fn level_at_velocity() {
+        let operator = Operator {
+            output_level: 80,
+            ..Default::default()
+        };
+        for velocity in 0..=127 {
+            assert_eq!(80, operator.level_at_velocity(velocity));
+        }
+
+        for velocity_sensitivity in 1..=7 {
+            let operator = Operator {
+                velocity_sensitivity,
+                ..operator
+            };
+            let levels: Vec<_> = (0..=127)
+                .map(|velocity| operator.level_at_velocity(velocity))
+                .collect();
+            assert!(levels.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert!(levels[127] >= 80 && levels[127] <= 87);
+            assert!(levels[0] < 80);
+        }
+
+        let operator = Operator {
+            output_level: 99,
+            velocity_sensitivity: 7,
+            ..Default::default()
+        };
+        assert_eq!(99, operator.level_at_velocity(127));
+        assert_eq!(0, operator.level_at_velocity(0));
+        assert_eq!(99, operator.level_at_velocity(200));
+    }
+
+    #[test]
+    fn rate_scaling() {
+        let mut operator = Operator::default();
+        operator.envelope.rates = [10, 40, 70, 99];
+        for note in 0..=127 {
+            assert_eq!(0, operator.rate_scaling_offset(note));
+            assert_eq!(
+                operator.envelope.rates,
+                operator.scaled_envelope_rates(note)
+            );
+        }
+
+        operator.rate_scaling = 7;
+        assert_eq!(0, operator.rate_scaling_offset(23));
+        assert_eq!(42, operator.rate_scaling_offset(114));
+        assert_eq!(42, operator.rate_scaling_offset(127));
+        assert_eq!([52, 82, 99, 99], operator.scaled_envelope_rates(127));
+
+        // Rates rise with the note and with the rate scaling
+        let mut previous = 0;
+        for note in 0..=127 {
+            let offset = operator.rate_scaling_offset(note);
+            assert!(offset >= previous);
+            previous = offset;
+            assert!(operator
+                .scaled_envelope_rates(note)
+                .iter()
+                .all(|rate| *rate <= 99));
+        }
+        let offsets: Vec<_> = (0..=7)
+            .map(|rate_scaling| {
+                Operator {
+                    rate_scaling,
+                    ..Default::default()
+                }
+                .rate_scaling_offset(96)
+            })
+            .collect();
+        assert!(offsets.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(0, offsets[0]);
+    }
+
+    #[test]
+    fn detune() {
+        for stored in 0..=14 {
+            let detune = Detune::from_stored(stored);
+            assert_eq!(stored, detune.stored());
+            assert_eq!(stored as i8 - 7, detune.value());
+            assert_eq!(detune, Detune::new(detune.value()));
+        }
+        assert_eq!(Detune::new(7), Detune::from_stored(15));
+        assert_eq!(Detune::new(-7), Detune::new(-100));
+        assert_eq!(Detune::new(7), Detune::new(100));
+
+        assert_eq!("0", Detune::default().to_string());
+        assert_eq!("+3", Detune::new(3).to_string());
+        assert_eq!("-5", Detune::new(-5).to_string());
+
+        assert_eq!(0.0, Detune::default().cents());
+        assert_eq!(7.0, Detune::new(7).cents());
+        assert_eq!(-7.0, Detune::new(-7).cents());
+    }
+
+    #[test]
+    fn frequency_ratio() {
+        let ratio = |coarse, fine| {
+            Operator {
+                frequency_course: coarse,
+                frequency_fine: fine,
+                ..Default::default()
+            }
+            .frequency_ratio()
+            .unwrap()
+        };
+        assert_eq!(0.5, ratio(0, 0));
+        assert_eq!(0.75, ratio(0, 50));
+        assert_eq!(1.0, ratio(1, 0));
+        assert_eq!(1.01, ratio(1, 1));
+        assert_eq!(1.99, ratio(1, 99));
+        assert_eq!(3.42, ratio(3, 14));
+        assert_eq!(31.0, ratio(31, 0));
+        assert_eq!(Some(1.0), Operator::default().frequency_ratio());
+
+        let detuned = |detune| {
+            Operator {
+                detune: Detune::new(detune),
+                ..Default::default()
+            }
+            .frequency_ratio()
+            .unwrap()
+        };
+        let cent = 2f64.powf(1.0 / 1200.0);
+        assert!((detuned(7) - cent.powi(7)).abs() < 1e-12);
+        assert!((detuned(-7) - cent.powi(-7)).abs() < 1e-12);
+        assert!(detuned(-7) < detuned(0) && detuned(0) < detuned(7));
+
+        let operator = Operator {
+            mode: OperatorMode::Fixed,
+            ..Default::default()
+        };
+        assert_eq!(None, operator.frequency_ratio());
+    }
+
+    #[test]
+    fn fixed_frequency_hz() {
+        let frequency = |coarse, fine| {
+            Operator {
+                mode: OperatorMode::Fixed,
+                frequency_course: coarse,
+                frequency_fine: fine,
+                ..Default::default()
+            }
+            .fixed_frequency_hz()
+            .unwrap()
+        };
+        let assert_near = |expected: f64, actual: f64| {
+            assert!(
+                ((actual - expected) / expected).abs() < 0.001,
+                "{actual} is not close to {expected}"
+            );
+        };
+        assert_near(1.0, frequency(0, 0));
+        assert_near(10.0, frequency(1, 0));
+        assert_near(100.0, frequency(2, 0));
+        assert_near(1000.0, frequency(3, 0));
+        assert_near(9.772, frequency(0, 99));
+        assert_near(9772.0, frequency(3, 99));
+        assert_near(1.023, frequency(0, 1));
+        assert_near(3.162, frequency(0, 50));
+
+        // Coarse frequencies repeat every four steps
+        assert_eq!(frequency(1, 20), frequency(5, 20));
+        assert_eq!(frequency(3, 0), frequency(31, 0));
+
+        assert_eq!(None, Operator::default().fixed_frequency_hz());
+    }
+
+    #[test]
+    fn frequency_for_note() {
+        // Middle C with the first operator of INIT VOICE
+        let preset = Preset::default();
+        let operator = preset.operators[0];
+        let frequency = operator.frequency_for_note(60, preset.transpose);
+        assert!((frequency - 261.626).abs() < 0.001);
+
+        // Transposed up an octave and with a ratio of 0.5
+        let operator = Operator {
+            frequency_course: 0,
+            ..operator
+        };
+        assert!((operator.frequency_for_note(60, 36) - 261.626).abs() < 0.001);
+        assert_eq!(
+            operator.frequency_for_note(60, 48),
+            operator.frequency_for_note(60, 100)
+        );
+        assert_eq!(
+            operator.frequency_for_note(127, 24),
+            operator.frequency_for_note(200, 24)
+        );
+
+        let operator = Operator {
+            mode: OperatorMode::Fixed,
+            frequency_course: 2,
+            ..Default::default()
+        };
+        for note in [0, 60, 127] {
+            assert_eq!(100.0, operator.frequency_for_note(note, 24));
+            assert_eq!(100.0, operator.frequency_for_note(note, 0));
+        }
+    }
+}
@@ -0,0 +1,848 @@
+use lazy_static::lazy_static;
+
+use crate::envelope::{OPERATOR_ENVELOPE_NAMES, PITCH_ENVELOPE_NAMES};
+use crate::operator;
+use crate::{
+    AlgorithmId, Envelope, OperatorId, OperatorMode, OutOfRange, ParamError, Preset, PresetName,
+    ScalingCurve, Waveform,
+};
+
+/// Number of parameters stored for each operator in a single voice dump.
+const OPERATOR_PARAMETER_COUNT: usize = 21;
+
+/// Number of the first parameter after the operators.
+const GLOBAL_PARAMETER_START: usize = Preset::OPERATOR_COUNT * OPERATOR_PARAMETER_COUNT;
+
+const NAME_CHARACTER_NAMES: [&str; PresetName::MAX_LENGTH] = [
+    "name character 1",
+    "name character 2",
+    "name character 3",
+    "name character 4",
+    "name character 5",
+    "name character 6",
+    "name character 7",
+    "name character 8",
+    "name character 9",
+    "name character 10",
+];
+
+/// A parameter of a voice, to read and change every parameter of a preset
+/// the same way with [`Preset::get`] and [`Preset::set`].
+///
+/// Operator parameters carry the [`OperatorId`] of the operator, counting
+/// from 0 for operator 1. Envelope segments and name characters are also
+/// counted from 0.
+///
+/// Every parameter has a value from 0 to 127, the value stored in a single
+/// voice dump. Operator modes are 0 for ratio and 1 for fixed, switches are
+/// 0 for off and 1 for on, and detune is from 0 to 14 with 7 for no detune.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Parameter {
+    EgRate(OperatorId, usize),
+    EgLevel(OperatorId, usize),
+    BreakPoint(OperatorId),
+    LeftDepth(OperatorId),
+    RightDepth(OperatorId),
+    LeftCurve(OperatorId),
+    RightCurve(OperatorId),
+    RateScaling(OperatorId),
+    ModulationSensitivity(OperatorId),
+    VelocitySensitivity(OperatorId),
+    OutputLevel(OperatorId),
+    Mode(OperatorId),
+    FrequencyCoarse(OperatorId),
+    FrequencyFine(OperatorId),
+    Detune(OperatorId),
+    PitchEgRate(usize),
+    PitchEgLevel(usize),
+    Algorithm,
+    Feedback,
+    OscillatorKeySync,
+    LfoSpeed,
+    LfoDelay,
+    LfoPitchModDepth,
+    LfoAmplitudeModDepth,
+    LfoKeySync,
+    LfoWaveform,
+    LfoPitchModSensitivity,
+    Transpose,
+    NameCharacter(usize),
+}
+
+impl Parameter {
+    /// Number of parameters in a voice.
+    pub const COUNT: usize = GLOBAL_PARAMETER_START + 19 + PresetName::MAX_LENGTH;
+
+    /// Every parameter in the order of a single voice dump, which starts
+    /// with operator 6.
+    pub const ALL: [Parameter; Parameter::COUNT] = Parameter::all();
+
+    const fn all() -> [Parameter; Parameter::COUNT] {
+        let mut all = [Parameter::Algorithm; Parameter::COUNT];
+        let mut index = 0;
+        while index < GLOBAL_PARAMETER_START {
+            // Stored last-operator-first
+            let operator = (Preset::OPERATOR_COUNT - 1 - index / OPERATOR_PARAMETER_COUNT) as u8;
+            all[index] = match index % OPERATOR_PARAMETER_COUNT {
+                segment @ 0..=3 => Parameter::EgRate(operator, segment),
+                offset @ 4..=7 => Parameter::EgLevel(operator, offset - 4),
+                8 => Parameter::BreakPoint(operator),
+                9 => Parameter::LeftDepth(operator),
+                10 => Parameter::RightDepth(operator),
+                11 => Parameter::LeftCurve(operator),
+                12 => Parameter::RightCurve(operator),
+                13 => Parameter::RateScaling(operator),
+                14 => Parameter::ModulationSensitivity(operator),
+                15 => Parameter::VelocitySensitivity(operator),
+                16 => Parameter::OutputLevel(operator),
+                17 => Parameter::Mode(operator),
+                18 => Parameter::FrequencyCoarse(operator),
+                19 => Parameter::FrequencyFine(operator),
+                _ => Parameter::Detune(operator),
+            };
+            index += 1;
+        }
+        let globals = [
+            Parameter::Algorithm,
+            Parameter::Feedback,
+            Parameter::OscillatorKeySync,
+            Parameter::LfoSpeed,
+            Parameter::LfoDelay,
+            Parameter::LfoPitchModDepth,
+            Parameter::LfoAmplitudeModDepth,
+            Parameter::LfoKeySync,
+            Parameter::LfoWaveform,
+            Parameter::LfoPitchModSensitivity,
+            Parameter::Transpose,
+        ];
+        while index < Parameter::COUNT {
+            let offset = index - GLOBAL_PARAMETER_START;
+            all[index] = match offset {
+                segment @ 0..=3 => Parameter::PitchEgRate(segment),
+                offset @ 4..=7 => Parameter::PitchEgLevel(offset - 4),
+                offset @ 8..=18 => globals[offset - 8],
+                offset => Parameter::NameCharacter(offset - 19),
+            };
+            index += 1;
+        }
+        all
+    }
+
+    /// The operator the parameter belongs to, or `None` for parameters of
+    /// the whole preset.
+    pub fn operator_id(&self) -> Option<OperatorId> {
+        use Parameter::*;
+        match *self {
+            EgRate(operator, _)
+            | EgLevel(operator, _)
+            | BreakPoint(operator)
+            | LeftDepth(operator)
+            | RightDepth(operator)
+            | LeftCurve(operator)
+            | RightCurve(operator)
+            | RateScaling(operator)
+            | ModulationSensitivity(operator)
+            | VelocitySensitivity(operator)
+            | OutputLevel(operator)
+            | Mode(operator)
+            | FrequencyCoarse(operator)
+            | FrequencyFine(operator)
+            | Detune(operator) => Some(operator),
+            _ => None,
+        }
+    }
+
+    /// Number of the parameter in parameter change messages, which is also
+    /// its position in a single voice dump and in [`Parameter::ALL`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operator of the parameter doesn't exist.
+    ///
+    /// ```
+    /// use synthahol_dx7::Parameter;
+    /// assert_eq!(64, Parameter::EgRate(2, 1).number());
+    /// assert_eq!(134, Parameter::Algorithm.number());
+    /// ```
+    pub fn number(&self) -> u8 {
+        use Parameter::*;
+        // Stored last-operator-first
+        let operator = |operator_id: OperatorId, offset: usize| {
+            (Preset::OPERATOR_COUNT - 1 - operator_id as usize) * OPERATOR_PARAMETER_COUNT + offset
+        };
+        let global = |offset: usize| GLOBAL_PARAMETER_START + offset;
+        let number = match *self {
+            EgRate(id, segment) => operator(id, segment),
+            EgLevel(id, segment) => operator(id, 4 + segment),
+            BreakPoint(id) => operator(id, 8),
+            LeftDepth(id) => operator(id, 9),
+            RightDepth(id) => operator(id, 10),
+            LeftCurve(id) => operator(id, 11),
+            RightCurve(id) => operator(id, 12),
+            RateScaling(id) => operator(id, 13),
+            ModulationSensitivity(id) => operator(id, 14),
+            VelocitySensitivity(id) => operator(id, 15),
+            OutputLevel(id) => operator(id, 16),
+            Mode(id) => operator(id, 17),
+            FrequencyCoarse(id) => operator(id, 18),
+            FrequencyFine(id) => operator(id, 19),
+            Detune(id) => operator(id, 20),
+            PitchEgRate(segment) => global(segment),
+            PitchEgLevel(segment) => global(4 + segment),
+            Algorithm => global(8),
+            Feedback => global(9),
+            OscillatorKeySync => global(10),
+            LfoSpeed => global(11),
+            LfoDelay => global(12),
+            LfoPitchModDepth => global(13),
+            LfoAmplitudeModDepth => global(14),
+            LfoKeySync => global(15),
+            LfoWaveform => global(16),
+            LfoPitchModSensitivity => global(17),
+            Transpose => global(18),
+            NameCharacter(index) => global(19 + index),
+        };
+        number as u8
+    }
+
+    /// The parameter with a number from parameter change messages, or `None`
+    /// if the number isn't a voice parameter.
+    pub fn from_number(number: u8) -> Option<Parameter> {
+        Parameter::ALL.get(number as usize).copied()
+    }
+
+    /// The name, range, default and value names of the parameter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operator, envelope segment or name character of the
+    /// parameter doesn't exist.
+    ///
+    /// ```
+    /// use synthahol_dx7::Parameter;
+    /// let metadata = Parameter::LeftCurve(2).metadata();
+    /// assert_eq!("OP3 KBD LEV SCL LFT CURVE", metadata.name);
+    /// assert_eq!(3, metadata.max);
+    /// assert_eq!("+EXP", metadata.format_value(2));
+    /// ```
+    pub fn metadata(&self) -> &'static ParamMeta {
+        let metadata = &METADATA[self.number() as usize];
+        assert_eq!(*self, metadata.parameter, "Parameter doesn't exist");
+        metadata
+    }
+
+    /// How the parameter is named, its range and how its values are shown.
+    /// Every other description of a parameter comes from this table.
+    fn definition(&self) -> Definition {
+        use Parameter::*;
+        let number = |name, label, max| Definition::new(name, label, 0, max, Values::Number);
+        match *self {
+            EgRate(_, segment) => number(
+                OPERATOR_ENVELOPE_NAMES.rates[segment],
+                ["EG RATE 1", "EG RATE 2", "EG RATE 3", "EG RATE 4"][segment],
+                99,
+            ),
+            EgLevel(_, segment) => number(
+                OPERATOR_ENVELOPE_NAMES.levels[segment],
+                ["EG LEVEL 1", "EG LEVEL 2", "EG LEVEL 3", "EG LEVEL 4"][segment],
+                99,
+            ),
+            BreakPoint(_) => {
+                Definition::new("break point", "KBD LEV SCL BRK PT", 0, 99, Values::Note)
+            }
+            LeftDepth(_) => number("left depth", "KBD LEV SCL LFT DEPTH", 99),
+            RightDepth(_) => number("right depth", "KBD LEV SCL RHT DEPTH", 99),
+            LeftCurve(_) => Definition::new(
+                "left curve",
+                "KBD LEV SCL LFT CURVE",
+                0,
+                3,
+                Values::Labels(&CURVES),
+            ),
+            RightCurve(_) => Definition::new(
+                "right curve",
+                "KBD LEV SCL RHT CURVE",
+                0,
+                3,
+                Values::Labels(&CURVES),
+            ),
+            RateScaling(_) => number("rate scaling", "KBD RATE SCALING", 7),
+            ModulationSensitivity(_) => number("modulation sensitivity", "AMP MOD SENSITIVITY", 3),
+            VelocitySensitivity(_) => number("velocity sensitivity", "KEY VEL SENSITIVITY", 7),
+            OutputLevel(_) => number("output level", "OPERATOR OUTPUT LEVEL", 99),
+            Mode(_) => Definition::new("mode", "OSC MODE", 0, 1, Values::Labels(&MODES)),
+            FrequencyCoarse(_) => number("frequency coarse", "OSC FREQ COARSE", 31),
+            FrequencyFine(_) => number("frequency fine", "OSC FREQ FINE", 99),
+            Detune(_) => Definition::new("detune", "OSC DETUNE", 0, 14, Values::Detune),
+            PitchEgRate(segment) => number(
+                PITCH_ENVELOPE_NAMES.rates[segment],
+                [
+                    "PITCH EG RATE 1",
+                    "PITCH EG RATE 2",
+                    "PITCH EG RATE 3",
+                    "PITCH EG RATE 4",
+                ][segment],
+                99,
+            ),
+            PitchEgLevel(segment) => number(
+                PITCH_ENVELOPE_NAMES.levels[segment],
+                [
+                    "PITCH EG LEVEL 1",
+                    "PITCH EG LEVEL 2",
+                    "PITCH EG LEVEL 3",
+                    "PITCH EG LEVEL 4",
+                ][segment],
+                99,
+            ),
+            Algorithm => Definition::new("algorithm", "ALGORITHM #", 0, 31, Values::Algorithm),
+            Feedback => number("feedback level", "FEEDBACK", 7),
+            OscillatorKeySync => Definition::new(
+                "oscillator key sync",
+                "OSCILLATOR SYNC",
+                0,
+                1,
+                Values::Labels(&SWITCH),
+            ),
+            LfoSpeed => number("LFO speed", "LFO SPEED", 99),
+            LfoDelay => number("LFO delay", "LFO DELAY", 99),
+            LfoPitchModDepth => number("LFO pitch mod depth", "LFO PITCH MOD DEPTH", 99),
+            LfoAmplitudeModDepth => number("LFO amplitude mod depth", "LFO AMP MOD DEPTH", 99),
+            LfoKeySync => {
+                Definition::new("LFO key sync", "LFO SYNC", 0, 1, Values::Labels(&SWITCH))
+            }
+            LfoWaveform => Definition::new(
+                "LFO waveform",
+                "LFO WAVEFORM",
+                0,
+                5,
+                Values::Labels(&WAVEFORMS),
+            ),
+            LfoPitchModSensitivity => {
+                number("LFO pitch mod sensitivity", "PITCH MOD SENSITIVITY", 7)
+            }
+            Transpose => Definition::new("transpose", "TRANSPOSE", 0, 48, Values::Transpose),
+            // Character codes of PresetName::CHARSET
+            NameCharacter(index) => Definition::new(
+                NAME_CHARACTER_NAMES[index],
+                [
+                    "VOICE NAME CHAR 1",
+                    "VOICE NAME CHAR 2",
+                    "VOICE NAME CHAR 3",
+                    "VOICE NAME CHAR 4",
+                    "VOICE NAME CHAR 5",
+                    "VOICE NAME CHAR 6",
+                    "VOICE NAME CHAR 7",
+                    "VOICE NAME CHAR 8",
+                    "VOICE NAME CHAR 9",
+                    "VOICE NAME CHAR 10",
+                ][index],
+                0x20,
+                0x7F,
+                Values::Character,
+            ),
+        }
+    }
+
+    /// Returns `true` if the value is in the range of the parameter.
+    pub(crate) fn contains(&self, value: u8) -> bool {
+        let definition = self.definition();
+        (definition.min..=definition.max).contains(&value)
+    }
+
+    pub(crate) fn out_of_range(&self, value: u8) -> OutOfRange {
+        let definition = self.definition();
+        OutOfRange {
+            operator: self.operator_id().map(|operator| operator as usize),
+            parameter: definition.name,
+            value: value as i16,
+            min: definition.min as i16,
+            max: definition.max as i16,
+        }
+    }
+}
+
+const CURVES: [&str; 4] = ["-LIN", "-EXP", "+EXP", "+LIN"];
+const MODES: [&str; 2] = ["Ratio", "Fixed"];
+const SWITCH: [&str; 2] = ["Off", "On"];
+const WAVEFORMS: [&str; 6] = [
+    "Triangle",
+    "Saw down",
+    "Saw up",
+    "Square",
+    "Sine",
+    "Sample and hold",
+];
+
+/// How the values of a parameter are shown.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Values {
+    Number,
+    Labels(&'static [&'static str]),
+    Note,
+    Detune,
+    Algorithm,
+    Transpose,
+    Character,
+}
+
+/// An entry in the table of parameters.
+struct Definition {
+    /// Name used by [`OutOfRange`] and when normalizing.
+    name: &'static str,
+
+    /// Name used by the DX7 documentation, without the operator.
+    label: &'static str,
+
+    min: u8,
+    max: u8,
+    values: Values,
+}
+
+impl Definition {
+    fn new(name: &'static str, label: &'static str, min: u8, max: u8, values: Values) -> Self {
+        Self {
+            name,
+            label,
+            min,
+            max,
+            values,
+        }
+    }
+}
+
+/// Description of a [`Parameter`] for editors.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParamMeta {
+    pub parameter: Parameter,
+
+    /// Name from the DX7 documentation, such as `"OP3 EG RATE 2"`.
+    pub name: String,
+
+    /// Number in parameter change messages.
+    pub number: u8,
+
+    pub min: u8,
+    pub max: u8,
+
+    /// Value in the INIT VOICE.
+    pub default: u8,
+
+    /// Names of the values of enumerated parameters, starting with the
+    /// value `min`. Empty for other parameters.
+    pub labels: &'static [&'static str],
+
+    values: Values,
+}
+
+impl ParamMeta {
+    fn new(parameter: Parameter, init: &Preset) -> Self {
+        let definition = parameter.definition();
+        let name = match parameter.operator_id() {
+            // Operators are numbered from one like on the DX7
+            Some(operator) => format!("OP{} {}", operator + 1, definition.label),
+            None => definition.label.to_owned(),
+        };
+        let labels = match definition.values {
+            Values::Labels(labels) => labels,
+            _ => &[],
+        };
+        Self {
+            parameter,
+            name,
+            number: parameter.number(),
+            min: definition.min,
+            max: definition.max,
+            default: init.get(parameter),
+            labels,
+            values: definition.values,
+        }
+    }
+
+    /// Show a value the way the DX7 does. Enumerated parameters show their
+    /// label, break points and transpose show a note, detune is signed,
+    /// algorithms are numbered from 1 and name characters are shown as
+    /// characters. Values outside of the range are shown as numbers.
+    ///
+    /// ```
+    /// use synthahol_dx7::Parameter;
+    /// assert_eq!("Sine", Parameter::LfoWaveform.metadata().format_value(4));
+    /// assert_eq!("-2", Parameter::Detune(0).metadata().format_value(5));
+    /// assert_eq!("C2", Parameter::Transpose.metadata().format_value(12));
+    /// ```
+    pub fn format_value(&self, value: u8) -> String {
+        if !(self.min..=self.max).contains(&value) {
+            return value.to_string();
+        }
+        match self.values {
+            Values::Number => value.to_string(),
+            Values::Labels(labels) => labels[(value - self.min) as usize].to_owned(),
+            Values::Note => operator::note_name(value + operator::BREAK_POINT_LOWEST_NOTE),
+            Values::Detune => crate::Detune::from_stored(value).to_string(),
+            Values::Algorithm => AlgorithmId::from_stored(value).display().to_string(),
+            Values::Transpose => {
+                operator::note_name(Preset::MIDDLE_C + value - Preset::TRANSPOSE_CENTER)
+            }
+            Values::Character => crate::lcd_char(value).unwrap_or(' ').to_string(),
+        }
+    }
+
+    /// Read a value shown by [`ParamMeta::format_value`], ignoring case.
+    /// Spaces in labels can also be written as hyphens. The value is not
+    /// checked against the range so callers can report values that are out
+    /// of range.
+    pub(crate) fn parse_value(&self, text: &str) -> Option<i16> {
+        let note = || operator::note_from_name(text).map(i16::from);
+        match self.values {
+            Values::Number => text.parse().ok(),
+            Values::Labels(labels) => labels
+                .iter()
+                .position(|label| {
+                    label.eq_ignore_ascii_case(text)
+                        || label.replace(' ', "-").eq_ignore_ascii_case(text)
+                })
+                .map(|index| index as i16 + self.min as i16),
+            Values::Note => Some(note()? - operator::BREAK_POINT_LOWEST_NOTE as i16),
+            Values::Detune => Some(text.parse::<i16>().ok()? - crate::Detune::MIN as i16),
+            Values::Algorithm => Some(text.parse::<i16>().ok()? - 1),
+            Values::Transpose => {
+                Some(note()? - Preset::MIDDLE_C as i16 + Preset::TRANSPOSE_CENTER as i16)
+            }
+            Values::Character => {
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(character), None) => crate::lcd_code(character).map(i16::from),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+lazy_static! {
+    static ref METADATA: Vec<ParamMeta> = {
+        let init = Preset::init();
+        Parameter::ALL
+            .iter()
+            .map(|&parameter| ParamMeta::new(parameter, &init))
+            .collect()
+    };
+}
+
+fn envelope_value(envelope: &Envelope, segment: usize, rate: bool) -> u8 {
+    if rate {
+        envelope.rates[segment]
+    } else {
+        envelope.levels[segment]
+    }
+}
+
+impl Preset {
+    /// The value of a parameter as stored in a single voice dump.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operator, envelope segment or name character of the
+    /// parameter doesn't exist.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Parameter, Preset};
+    /// let preset = Preset::default();
+    /// assert_eq!(99, preset.get(Parameter::OutputLevel(0)));
+    /// assert_eq!(b'I', preset.get(Parameter::NameCharacter(0)));
+    /// ```
+    pub fn get(&self, parameter: Parameter) -> u8 {
+        use Parameter::*;
+        let operator = |operator_id: OperatorId| &self.operators[operator_id as usize];
+        match parameter {
+            EgRate(id, segment) => envelope_value(&operator(id).envelope, segment, true),
+            EgLevel(id, segment) => envelope_value(&operator(id).envelope, segment, false),
+            BreakPoint(id) => operator(id).scaling_break_point,
+            LeftDepth(id) => operator(id).scaling_left_depth,
+            RightDepth(id) => operator(id).scaling_right_depth,
+            LeftCurve(id) => operator(id).scaling_left_curve.into(),
+            RightCurve(id) => operator(id).scaling_right_curve.into(),
+            RateScaling(id) => operator(id).rate_scaling,
+            ModulationSensitivity(id) => operator(id).modulation_sensitivity,
+            VelocitySensitivity(id) => operator(id).velocity_sensitivity,
+            OutputLevel(id) => operator(id).output_level,
+            Mode(id) => match operator(id).mode {
+                OperatorMode::Ratio => 0,
+                OperatorMode::Fixed => 1,
+            },
+            FrequencyCoarse(id) => operator(id).frequency_course,
+            FrequencyFine(id) => operator(id).frequency_fine,
+            Detune(id) => operator(id).detune.stored(),
+            PitchEgRate(segment) => envelope_value(&self.pitch_envelope, segment, true),
+            PitchEgLevel(segment) => envelope_value(&self.pitch_envelope, segment, false),
+            Algorithm => self.algorithm_id.stored(),
+            Feedback => self.feedback_level,
+            OscillatorKeySync => self.oscillator_key_sync as u8,
+            LfoSpeed => self.lfo_speed,
+            LfoDelay => self.lfo_delay,
+            LfoPitchModDepth => self.lfo_pitch_mod_depth,
+            LfoAmplitudeModDepth => self.lfo_amplitude_mod_depth,
+            LfoKeySync => self.lfo_key_sync as u8,
+            LfoWaveform => self.lfo_waveform as u8,
+            LfoPitchModSensitivity => self.lfo_pitch_mod_sensitivity,
+            Transpose => self.transpose,
+            NameCharacter(index) => self.name.to_padded_bytes()[index],
+        }
+    }
+
+    /// Change a parameter to a value as stored in a single voice dump.
+    /// Values outside of the range of the parameter are rejected and the
+    /// preset is unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operator, envelope segment or name character of the
+    /// parameter doesn't exist.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Parameter, Preset};
+    /// let mut preset = Preset::default();
+    /// preset.set(Parameter::Feedback, 7).unwrap();
+    /// assert_eq!(7, preset.feedback_level);
+    /// assert!(preset.set(Parameter::Feedback, 8).is_err());
+    /// ```
+    pub fn set(&mut self, parameter: Parameter, value: u8) -> Result<(), ParamError> {
+        if !parameter.contains(value) {
+            return Err(ParamError::OutOfRange(parameter.out_of_range(value)));
+        }
+        self.set_unchecked(parameter, value);
+        Ok(())
+    }
+
+    /// Change a parameter without checking its range, so presets can hold
+    /// the same values as presets read without normalizing. Values out of
+    /// range for curves, modes, detune and waveforms, which can't be held,
+    /// are replaced.
+    pub(crate) fn set_unchecked(&mut self, parameter: Parameter, value: u8) {
+        use Parameter::*;
+        let curve = |value| ScalingCurve::try_from(value).unwrap_or(ScalingCurve::NegativeLinear);
+        match parameter {
+            EgRate(id, segment) => self.operators[id as usize].envelope.rates[segment] = value,
+            EgLevel(id, segment) => self.operators[id as usize].envelope.levels[segment] = value,
+            BreakPoint(id) => self.operators[id as usize].scaling_break_point = value,
+            LeftDepth(id) => self.operators[id as usize].scaling_left_depth = value,
+            RightDepth(id) => self.operators[id as usize].scaling_right_depth = value,
+            LeftCurve(id) => self.operators[id as usize].scaling_left_curve = curve(value),
+            RightCurve(id) => self.operators[id as usize].scaling_right_curve = curve(value),
+            RateScaling(id) => self.operators[id as usize].rate_scaling = value,
+            ModulationSensitivity(id) => self.operators[id as usize].modulation_sensitivity = value,
+            VelocitySensitivity(id) => self.operators[id as usize].velocity_sensitivity = value,
+            OutputLevel(id) => self.operators[id as usize].output_level = value,
+            Mode(id) => {
+                self.operators[id as usize].mode = if value == 0 {
+                    OperatorMode::Ratio
+                } else {
+                    OperatorMode::Fixed
+                }
+            }
+            FrequencyCoarse(id) => self.operators[id as usize].frequency_course = value,
+            FrequencyFine(id) => self.operators[id as usize].frequency_fine = value,
+            Detune(id) => self.operators[id as usize].detune = crate::Detune::from_stored(value),
+            PitchEgRate(segment) => self.pitch_envelope.rates[segment] = value,
+            PitchEgLevel(segment) => self.pitch_envelope.levels[segment] = value,
+            Algorithm => self.algorithm_id = AlgorithmId::from_stored(value),
+            Feedback => self.feedback_level = value,
+            OscillatorKeySync => self.oscillator_key_sync = value != 0,
+            LfoSpeed => self.lfo_speed = value,
+            LfoDelay => self.lfo_delay = value,
+            LfoPitchModDepth => self.lfo_pitch_mod_depth = value,
+            LfoAmplitudeModDepth => self.lfo_amplitude_mod_depth = value,
+            LfoKeySync => self.lfo_key_sync = value != 0,
+            LfoWaveform => {
+                self.lfo_waveform = Waveform::try_from(value).unwrap_or(Waveform::Triangle)
+            }
+            LfoPitchModSensitivity => self.lfo_pitch_mod_sensitivity = value,
+            Transpose => self.transpose = value,
+            NameCharacter(index) => {
+                let mut name = self.name.to_padded_bytes();
+                name[index] = value;
+                self.name = PresetName::from_lossy(&name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    #[test]
+    fn all() {
+        assert_eq!(155, Parameter::ALL.len());
+        assert_eq!(Parameter::EgRate(5, 0), Parameter::ALL[0]);
+        assert_eq!(Parameter::OutputLevel(5), Parameter::ALL[16]);
+        assert_eq!(Parameter::Detune(0), Parameter::ALL[125]);
+        assert_eq!(Parameter::PitchEgLevel(3), Parameter::ALL[133]);
+        assert_eq!(Parameter::Algorithm, Parameter::ALL[134]);
+        assert_eq!(Parameter::Transpose, Parameter::ALL[144]);
+        assert_eq!(Parameter::NameCharacter(9), Parameter::ALL[154]);
+
+        let unique: std::collections::HashSet<_> = Parameter::ALL.iter().collect();
+        assert_eq!(Parameter::COUNT, unique.len());
+    }
+
+    #[test]
+    fn numbers() {
+        for (number, parameter) in Parameter::ALL.iter().enumerate() {
+            assert_eq!(number as u8, parameter.number());
+            assert_eq!(Some(*parameter), Parameter::from_number(number as u8));
+        }
+        assert_eq!(None, Parameter::from_number(155));
+    }
+
+    #[test]
+    fn metadata() {
+        // Numbers and names from the DX7 single voice dump documentation
+        let expected = [
+            (Parameter::EgRate(5, 0), 0, "OP6 EG RATE 1"),
+            (Parameter::EgLevel(5, 3), 7, "OP6 EG LEVEL 4"),
+            (Parameter::BreakPoint(5), 8, "OP6 KBD LEV SCL BRK PT"),
+            (Parameter::Detune(5), 20, "OP6 OSC DETUNE"),
+            (Parameter::RightCurve(4), 33, "OP5 KBD LEV SCL RHT CURVE"),
+            (Parameter::EgRate(2, 1), 64, "OP3 EG RATE 2"),
+            (
+                Parameter::ModulationSensitivity(1),
+                98,
+                "OP2 AMP MOD SENSITIVITY",
+            ),
+            (Parameter::OutputLevel(0), 121, "OP1 OPERATOR OUTPUT LEVEL"),
+            (Parameter::Mode(0), 122, "OP1 OSC MODE"),
+            (Parameter::PitchEgRate(0), 126, "PITCH EG RATE 1"),
+            (Parameter::Algorithm, 134, "ALGORITHM #"),
+            (Parameter::OscillatorKeySync, 136, "OSCILLATOR SYNC"),
+            (Parameter::LfoWaveform, 142, "LFO WAVEFORM"),
+            (Parameter::Transpose, 144, "TRANSPOSE"),
+            (Parameter::NameCharacter(9), 154, "VOICE NAME CHAR 10"),
+        ];
+        for (parameter, number, name) in expected {
+            let metadata = parameter.metadata();
+            assert_eq!(parameter, metadata.parameter);
+            assert_eq!(number, metadata.number);
+            assert_eq!(name, metadata.name);
+        }
+
+        let init = Preset::init();
+        for parameter in Parameter::ALL {
+            let metadata = parameter.metadata();
+            assert!(metadata.min <= metadata.default, "{}", metadata.name);
+            assert!(metadata.default <= metadata.max, "{}", metadata.name);
+            assert_eq!(init.get(parameter), metadata.default);
+            assert!(
+                metadata.labels.is_empty()
+                    || metadata.labels.len() == (metadata.max - metadata.min + 1) as usize
+            );
+        }
+        assert_eq!(99, Parameter::OutputLevel(0).metadata().default);
+        assert_eq!(0, Parameter::OutputLevel(1).metadata().default);
+        assert_eq!(24, Parameter::Transpose.metadata().default);
+        assert_eq!(31, Parameter::Algorithm.metadata().max);
+        assert_eq!(7, Parameter::LfoPitchModSensitivity.metadata().max);
+    }
+
+    #[test]
+    fn format_value() {
+        let format = |parameter: Parameter, value| parameter.metadata().format_value(value);
+        for curve in 0..4 {
+            let expected = ScalingCurve::try_from(curve).unwrap().to_string();
+            assert_eq!(expected, format(Parameter::LeftCurve(0), curve));
+        }
+        for waveform in 0..6 {
+            let expected = Waveform::try_from(waveform).unwrap().to_string();
+            assert_eq!(expected, format(Parameter::LfoWaveform, waveform));
+        }
+        assert_eq!(
+            OperatorMode::Fixed.to_string(),
+            format(Parameter::Mode(3), 1)
+        );
+        assert_eq!("On", format(Parameter::LfoKeySync, 1));
+        assert_eq!("A-1", format(Parameter::BreakPoint(0), 0));
+        assert_eq!("C3", format(Parameter::BreakPoint(0), 39));
+        assert_eq!("+7", format(Parameter::Detune(0), 14));
+        assert_eq!("0", format(Parameter::Detune(0), 7));
+        assert_eq!("32", format(Parameter::Algorithm, 31));
+        assert_eq!("C3", format(Parameter::Transpose, 24));
+        assert_eq!("C5", format(Parameter::Transpose, 48));
+        assert_eq!("A", format(Parameter::NameCharacter(0), b'A'));
+        assert_eq!("57", format(Parameter::OutputLevel(0), 57));
+
+        // Out of range values are shown as numbers
+        assert_eq!("9", format(Parameter::LfoWaveform, 9));
+        assert_eq!("0", format(Parameter::NameCharacter(0), 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn metadata_of_missing_segment() {
+        Parameter::EgRate(0, 4).metadata();
+    }
+
+    #[test]
+    fn get_matches_voice_dump() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        for preset in presets {
+            let mut dump = Vec::new();
+            Voice::write(&preset, &mut dump).unwrap();
+            let values: Vec<u8> = Parameter::ALL
+                .iter()
+                .map(|&parameter| preset.get(parameter))
+                .collect();
+            assert_eq!(&dump[6..161], values, "{}", preset.name);
+        }
+    }
+
+    #[test]
+    fn set() {
+        for parameter in Parameter::ALL {
+            let mut preset = Preset::default();
+            preset.get(parameter);
+            let metadata = parameter.metadata();
+            let (min, max) = (metadata.min, metadata.max);
+            preset.set(parameter, max).unwrap();
+            assert_eq!(max, preset.get(parameter), "{parameter:?}");
+
+            let unchanged = preset.clone();
+            let error = preset.set(parameter, max + 1).unwrap_err();
+            let ParamError::OutOfRange(out_of_range) = error else {
+                panic!("{error:?}");
+            };
+            assert_eq!(parameter.out_of_range(max + 1), out_of_range);
+            assert_eq!(
+                parameter.operator_id().map(|operator| operator as usize),
+                out_of_range.operator
+            );
+            assert_eq!(unchanged, preset);
+            if min > 0 {
+                assert!(preset.set(parameter, min - 1).is_err());
+            }
+        }
+
+        let mut preset = Preset::default();
+        let error = preset.set(Parameter::OutputLevel(2), 100).unwrap_err();
+        assert_eq!(
+            "Operator 3 output level is 100, not between 0 and 99",
+            error.to_string()
+        );
+        let error = preset.set(Parameter::LfoWaveform, 6).unwrap_err();
+        assert_eq!("LFO waveform is 6, not between 0 and 5", error.to_string());
+
+        preset.set(Parameter::Detune(1), 0).unwrap();
+        assert_eq!(-7, preset.operators[1].detune.value());
+        preset.set(Parameter::Mode(1), 1).unwrap();
+        assert_eq!(OperatorMode::Fixed, preset.operators[1].mode);
+        preset.set(Parameter::LeftCurve(1), 3).unwrap();
+        assert_eq!(
+            ScalingCurve::PositiveLinear,
+            preset.operators[1].scaling_left_curve
+        );
+        preset.set(Parameter::NameCharacter(4), b'-').unwrap();
+        assert_eq!("INIT-VOICE", preset.name.to_string());
+        preset.set(Parameter::NameCharacter(9), b' ').unwrap();
+        assert_eq!("INIT-VOIC", preset.name.to_string());
+        assert!(preset.set(Parameter::NameCharacter(0), 0x1F).is_err());
+    }
+}
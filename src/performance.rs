@@ -0,0 +1,196 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::read::{packed_chunks, read_exact, read_footer, read_header};
+use crate::*;
+
+/// Header of a DX7II performance bank, also known as PMEM. The low nibble
+/// of the third byte is the MIDI channel.
+const PERFORMANCE_SYSEX_HEADER: [u8; Format::HEADER_LEN] = [0xF0, 0x43, 0x00, 0x7E, 0x0C, 0x6A];
+
+/// Identifies the type of data at the start of the body of messages with
+/// the universal bulk dump format number.
+pub(crate) const PERFORMANCE_IDENTIFIER: [u8; 10] = *b"LM  8952PM";
+
+/// Number of bytes for each performance.
+const PACKED_PERFORMANCE_LEN: usize = 51;
+
+/// Number of performances in a bank.
+const PERFORMANCE_COUNT: usize = 32;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum PlayMode {
+    #[default]
+    Single,
+
+    /// Both voices sound for every key.
+    Dual,
+
+    /// Voice A sounds below the split point and voice B above it.
+    Split,
+}
+
+impl Display for PlayMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use PlayMode::*;
+        let txt = match self {
+            Single => "Single",
+            Dual => "Dual",
+            Split => "Split",
+        };
+        f.write_str(txt)
+    }
+}
+
+/// A DX7II performance, also known as PCED, that combines one or two
+/// voices.
+///
+/// The layout follows the DX7II data list. Only the parameters describing
+/// how the voices are combined are decoded.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Performance {
+    pub name: String,
+    pub play_mode: PlayMode,
+
+    /// Voice numbers of voice A and B, from 0 to 63. Voices 0 to 31 are in
+    /// internal memory and 32 to 63 are on the cartridge.
+    pub voice_numbers: [u8; 2],
+
+    /// Detune between the voices in dual mode, from 0 to 7.
+    pub dual_detune: u8,
+
+    /// Key number of the split point, from 0 to 99.
+    pub split_point: u8,
+
+    /// Note shift of voice A and B, from 0 to 48 where 24 is no shift.
+    pub note_shifts: [u8; 2],
+
+    /// Balance between voice A and B, from 0 to 100 where 50 is even.
+    pub balance: u8,
+
+    /// Total volume from 0 to 99.
+    pub volume: u8,
+}
+
+impl Performance {
+    /// Unpack a performance from the 51 byte layout used by performance
+    /// banks.
+    pub fn from_packed(packed: &[u8; PACKED_PERFORMANCE_LEN]) -> Result<Self, Error> {
+        let play_mode = match packed[0] {
+            0 => PlayMode::Single,
+            1 => PlayMode::Dual,
+            2 => PlayMode::Split,
+            mode => return Err(Error::InvalidPlayMode(mode)),
+        };
+        Ok(Performance {
+            name: lossy_ascii(&packed[31..51]),
+            play_mode,
+            voice_numbers: [packed[1] & 0x3F, packed[2] & 0x3F],
+            dual_detune: packed[6] & 0b111,
+            split_point: packed[7],
+            note_shifts: [packed[13], packed[14]],
+            balance: packed[15],
+            volume: packed[16],
+        })
+    }
+}
+
+/// Banks of DX7II performances, also known as PMEM.
+///
+/// No performance bank dumped from a DX7II was available, so reading has
+/// only been checked against one generated from the DX7II data list.
+pub struct PerformanceBank;
+
+impl PerformanceBank {
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Vec<Performance>, Error> {
+        let input = File::open(&path)?;
+        let mut reader = BufReader::new(input);
+        Self::load(&mut reader)
+    }
+
+    pub fn load<R: Read>(reader: &mut R) -> Result<Vec<Performance>, Error> {
+        // Header, ignoring the MIDI channel
+        read_header(reader, &PERFORMANCE_SYSEX_HEADER)?;
+
+        // Body, starting with the identifier of the type of data
+        let mut body =
+            [0; PERFORMANCE_IDENTIFIER.len() + PERFORMANCE_COUNT * PACKED_PERFORMANCE_LEN];
+        read_exact(reader, &mut body)?;
+        let (identifier, packed_performances) = body.split_at(PERFORMANCE_IDENTIFIER.len());
+        if identifier != PERFORMANCE_IDENTIFIER {
+            return Err(Error::IncorrectIdentifier {
+                found: identifier.to_vec(),
+            });
+        }
+
+        // Body checksum and the end of SysEx marker
+        read_footer(reader, &body)?;
+
+        packed_chunks(packed_performances)
+            .enumerate()
+            .map(|(index, packed_performance)| {
+                Performance::from_packed(packed_performance).map_err(|err| Error::At {
+                    offset: (PERFORMANCE_SYSEX_HEADER.len()
+                        + PERFORMANCE_IDENTIFIER.len()
+                        + index * PACKED_PERFORMANCE_LEN) as u64,
+                    source: Box::new(err),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+
+    use super::*;
+
+    #[test]
+    fn performances() {
+        let performances =
+            PerformanceBank::load_file(test_data_path(&["dx7ii_performances.syx"])).unwrap();
+        assert_eq!(32, performances.len());
+
+        let performance = &performances[11];
+        assert_eq!("SPLIT BASS/BRASS", performance.name);
+        assert_eq!(PlayMode::Split, performance.play_mode);
+        assert_eq!([6, 46], performance.voice_numbers);
+        assert_eq!(60, performance.split_point);
+
+        let performance = &performances[0];
+        assert_eq!("DUAL STRINGS", performance.name);
+        assert_eq!(PlayMode::Dual, performance.play_mode);
+        assert_eq!([3, 4], performance.voice_numbers);
+        assert_eq!(5, performance.dual_detune);
+        assert_eq!([24, 36], performance.note_shifts);
+        assert_eq!(50, performance.balance);
+        assert_eq!(99, performance.volume);
+
+        assert_eq!(PlayMode::Single, performances[31].play_mode);
+    }
+
+    #[test]
+    fn malformed() {
+        let path = test_data_path(&["dx7ii_performances.syx"]);
+        let contents = std::fs::read(path).unwrap();
+
+        let mut data = contents.clone();
+        data[contents.len() - 2] ^= 1;
+        let error = PerformanceBank::load(&mut data.as_slice()).unwrap_err();
+        assert!(matches!(error, Error::ChecksumMismatch { .. }));
+
+        let mut data = contents.clone();
+        data[6 + 8] = b'V';
+        let error = PerformanceBank::load(&mut data.as_slice()).unwrap_err();
+        assert_eq!(
+            "Incorrect data identifier \"LM  8952VM\"",
+            error.to_string()
+        );
+
+        let error = PerformanceBank::load_file(test_data_path(&["rom1a.syx"])).unwrap_err();
+        assert!(matches!(error, Error::IncorrectHeader { .. }));
+    }
+}
@@ -1,161 +1,737 @@
+use std::fmt::{Display, Formatter};
 use std::fs::File;
-use std::io::{BufReader, Error, ErrorKind, Read};
+use std::io::{BufReader, ErrorKind, Read};
 use std::path::Path;
 
 use crate::*;
 
-/// Compute a masked 2's complement checksum.
-fn checksum(data: &[u8]) -> u8 {
-    data.iter().fold(0u8, |sum, c| sum.wrapping_sub(*c)) & 0x7F
+/// Fill the buffer, reporting how many more bytes were needed if the input
+/// ends first.
+pub(crate) fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), Error> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => {
+                return Err(Error::UnexpectedEof {
+                    needed: buf.len() - filled,
+                })
+            }
+            Ok(len) => filled += len,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Read a SysEx header, returning the MIDI channel in the low nibble of the
+/// third byte. The channel is ignored when comparing to the expected header.
+/// The byte count in the last two bytes is checked separately so the error
+/// can report the count that was declared.
+pub(crate) fn read_header<R: Read>(
+    reader: &mut R,
+    expected: &[u8; Format::HEADER_LEN],
+) -> Result<u8, Error> {
+    let mut header = [0; Format::HEADER_LEN];
+    read_exact(reader, &mut header)?;
+    let channel = header[2] & 0x0F;
+    if header[2] & 0xF0 != expected[2] || header[..2] != expected[..2] || header[3] != expected[3] {
+        return Err(Error::IncorrectHeader { found: header });
+    }
+    let declared = byte_count(&header);
+    if declared != byte_count(expected) {
+        return Err(Error::IncorrectByteCount {
+            expected: byte_count(expected),
+            declared,
+        });
+    }
+    Ok(channel)
+}
+
+/// The number of bytes in the body of a message, which is stored as 14 bits
+/// in the last two bytes of the header.
+pub(crate) fn byte_count(header: &[u8; Format::HEADER_LEN]) -> usize {
+    ((header[4] & 0x7F) as usize) << 7 | (header[5] & 0x7F) as usize
+}
+
+/// Read the checksum and End of SysEx marker that follow the body of a
+/// message. Both bytes are always consumed.
+pub(crate) fn read_footer<R: Read>(reader: &mut R, body: &[u8]) -> Result<(), Error> {
+    let mut footer = [0; 2];
+    read_exact(reader, &mut footer)?;
+    let [expected, end] = footer;
+    if end != 0xF7 {
+        return Err(Error::MissingEndOfSysex);
+    }
+    let computed = checksum(body);
+    if computed != expected {
+        return Err(Error::ChecksumMismatch { expected, computed });
+    }
+    Ok(())
+}
+
+/// Split `data` into arrays of `N` bytes. Any bytes left over at the end
+/// are ignored.
+pub(crate) fn packed_chunks<const N: usize>(data: &[u8]) -> impl Iterator<Item = &[u8; N]> {
+    data.chunks_exact(N)
+        .filter_map(|chunk| chunk.try_into().ok())
+}
+
+/// Tracks how many bytes have been read so errors can report where they
+/// occurred.
+pub(crate) struct CountingReader<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+    /// Wrap a reader whose first byte is at `position` in the input.
+    pub(crate) fn new(inner: R, position: u64) -> Self {
+        Self { inner, position }
+    }
+
+    /// Offset of the next byte to be read.
+    pub(crate) fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Attach an offset to an error. Errors from the input itself, such as
+    /// it ending early, are located at the current position instead.
+    pub(crate) fn locate(&self, err: Error, offset: u64) -> Error {
+        let offset = match err {
+            Error::UnexpectedEof { .. } | Error::Io(_) => self.position,
+            _ => offset,
+        };
+        Error::At {
+            offset,
+            source: Box::new(err),
+        }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+/// A bank and information about the message it was read from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BankDump {
+    /// MIDI channel of the device that sent the bank, from 0 to 15.
+    pub channel: u8,
+
+    pub presets: Vec<Preset>,
+
+    /// Problems that were tolerated while reading.
+    pub warnings: Vec<Warning>,
+}
+
+/// Options for reading banks.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReadOptions {
+    /// Reject banks where the checksum does not match the data. When `false`
+    /// a mismatch is reported as a warning instead.
+    pub verify_checksum: bool,
+
+    /// Reject presets with parameters outside of their valid ranges instead
+    /// of clamping them.
+    pub strict: bool,
+
+    /// Reject input that continues after the End of SysEx marker. When
+    /// `false` the reader is left immediately after the marker.
+    pub require_eof: bool,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            verify_checksum: true,
+            strict: false,
+            require_eof: false,
+        }
+    }
+}
+
+/// A problem that did not prevent a bank from being read.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Warning {
+    ChecksumMismatch {
+        expected: u8,
+        computed: u8,
+    },
+
+    /// A parameter of a voice was out of range and clamped.
+    OutOfRange {
+        index: usize,
+        out_of_range: OutOfRange,
+    },
 }
 
-/// Banks are collection of presets
-pub struct Bank;
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::ChecksumMismatch { expected, computed } => write!(
+                f,
+                "Computed checksum {computed} does not match expected checksum {expected}"
+            ),
+            Warning::OutOfRange {
+                index,
+                out_of_range,
+            } => write!(
+                f,
+                "Voice {index}: {out_of_range}, clamped to {}",
+                out_of_range.clamped()
+            ),
+        }
+    }
+}
 
 impl Bank {
+    /// Read a bank from a file. The bank is named after the file without
+    /// its extension and the path is kept as the source.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use synthahol_dx7::{Bank, Error};
+    ///
+    /// match Bank::load_file("tests/rom1a.syx") {
+    ///     Ok(bank) => println!("Read {} presets", bank.len()),
+    ///     Err(err) if matches!(err.root_cause(), Error::ChecksumMismatch { .. }) => {
+    ///         println!("The bank is corrupt: {err}")
+    ///     }
+    ///     Err(err) => println!("Unable to read the bank: {err}"),
+    /// }
+    /// ```
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Bank, Error> {
+        let path = path.as_ref();
+        let input = File::open(path)?;
+        let mut reader = BufReader::new(input);
+        Ok(Self::load(&mut reader, None)?.named_after(path))
+    }
+
+    /// Read a bank with an optional name.
+    pub fn load<R: Read>(reader: &mut R, name: Option<String>) -> Result<Bank, Error> {
+        let dump = Self::read_dump(reader)?;
+        Ok(Bank {
+            name,
+            source: None,
+            presets: dump.presets,
+        })
+    }
+
+    /// Read the presets of a bank from a file.
+    #[deprecated(note = "use `Bank::load_file`, which keeps the name of the bank")]
     pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Vec<Preset>, Error> {
-        let input = File::open(&path)?;
+        Self::load_file(path).map(|bank| bank.presets)
+    }
+
+    /// Read the presets of a bank.
+    #[deprecated(note = "use `Bank::load`, which keeps the name of the bank")]
+    pub fn read<R: Read>(reader: &mut R, name: Option<String>) -> Result<Vec<Preset>, Error> {
+        Self::load(reader, name).map(|bank| bank.presets)
+    }
+
+    /// Read a bank along with the MIDI channel it was sent on.
+    pub fn read_dump<R: Read>(reader: &mut R) -> Result<BankDump, Error> {
+        Self::read_with(reader, ReadOptions::default())
+    }
+
+    /// Read a bank from the start of a slice, returning the bank and the
+    /// number of bytes the bank used. Any data after the bank is ignored so
+    /// messages that follow can be read from the rest of the slice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use synthahol_dx7::Bank;
+    ///
+    /// let data = std::fs::read("tests/rom1a_twice.syx").unwrap();
+    /// let mut remaining = data.as_slice();
+    /// while !remaining.is_empty() {
+    ///     let (bank, consumed) = Bank::from_bytes(remaining).unwrap();
+    ///     println!("Read {} presets", bank.len());
+    ///     remaining = &remaining[consumed..];
+    /// }
+    /// ```
+    pub fn from_bytes(data: &[u8]) -> Result<(Bank, usize), Error> {
+        let mut reader = CountingReader::new(data, 0);
+        let dump = Self::read_bank(&mut reader, ReadOptions::default())?;
+        Ok((Bank::new(dump.presets), reader.position() as usize))
+    }
+
+    /// Read a bank, tolerating the problems allowed by the options.
+    pub fn read_with<R: Read>(reader: &mut R, options: ReadOptions) -> Result<BankDump, Error> {
+        Self::read_bank(&mut CountingReader::new(reader, 0), options)
+    }
+
+    pub(crate) fn read_bank<R: Read>(
+        reader: &mut CountingReader<R>,
+        options: ReadOptions,
+    ) -> Result<BankDump, Error> {
+        let mut warnings = Vec::new();
+        let (channel, body, body_offset) = read_bank_body(reader, options, &mut warnings)?;
+        let presets = unpack_presets(&body, body_offset, options, &mut warnings)?;
+        Ok(BankDump {
+            channel,
+            presets,
+            warnings,
+        })
+    }
+
+    /// Read a bank, unpacking each preset as the iterator is advanced. The
+    /// checksum covers the whole body, so the body is read and the checksum
+    /// and End of SysEx marker are verified before the iterator is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use synthahol_dx7::Bank;
+    ///
+    /// let mut file = File::open("tests/rom1a.syx").unwrap();
+    /// let first = Bank::presets(&mut file).unwrap().next().unwrap().unwrap();
+    /// assert_eq!("BRASS   1", first.name.to_string());
+    /// ```
+    pub fn presets<R: Read>(reader: &mut R) -> Result<PresetIter, Error> {
+        let mut reader = CountingReader::new(reader, 0);
+        let (_, body, body_offset) =
+            read_bank_body(&mut reader, ReadOptions::default(), &mut Vec::new())?;
+        Ok(PresetIter {
+            body: body.to_vec(),
+            body_offset,
+            index: 0,
+        })
+    }
+
+    /// Recover as many presets as possible from a bank that may be truncated
+    /// or damaged. Every complete voice that can be decoded is returned
+    /// along with the errors for the parts that could not be read. An error
+    /// is only returned when the header cannot be read.
+    pub fn read_partial<R: Read>(reader: &mut R) -> Result<(Vec<Preset>, Vec<Error>), Error> {
+        let mut reader = CountingReader::new(reader, 0);
+        let mut errors = Vec::new();
+        read_header(&mut reader, &SYSEX_HEADER).map_err(|err| reader.locate(err, 0))?;
+
+        let body_offset = reader.position();
+        let mut body = Vec::with_capacity(Format::BANK_BODY_LEN);
+        (&mut reader)
+            .take(Format::BANK_BODY_LEN as u64)
+            .read_to_end(&mut body)?;
+        if body.len() < Format::BANK_BODY_LEN {
+            let err = Error::UnexpectedEof {
+                needed: Format::BANK_BODY_LEN - body.len(),
+            };
+            errors.push(reader.locate(err, 0));
+        } else {
+            let checksum_offset = reader.position();
+            if let Err(err) = read_footer(&mut reader, &body) {
+                let offset = match err {
+                    Error::MissingEndOfSysex => checksum_offset + 1,
+                    _ => checksum_offset,
+                };
+                errors.push(reader.locate(err, offset));
+            }
+        }
+
+        // Incomplete voices at the end are covered by the truncation error
+        let options = ReadOptions::default();
+        let mut presets = Vec::with_capacity(body.len() / Format::PACKED_VOICE_LEN);
+        for (index, packed_preset) in packed_chunks(&body).enumerate() {
+            match unpack_voice(packed_preset, index, body_offset, options, &mut Vec::new()) {
+                Ok(preset) => presets.push(preset),
+                Err(err) => errors.push(err),
+            }
+        }
+        Ok((presets, errors))
+    }
+
+    /// Read every bank in a file. The banks share the file as their source
+    /// but are not named.
+    pub fn read_all_file<P: AsRef<Path>>(path: P) -> Result<Vec<Bank>, Error> {
+        let path = path.as_ref();
+        let input = File::open(path)?;
         let mut reader = BufReader::new(input);
-        Self::read(
-            &mut reader,
-            Some(path.as_ref().to_string_lossy().to_string()),
-        )
-    }
-
-    pub fn read<R: Read>(reader: &mut R, _name: Option<String>) -> Result<Vec<Preset>, Error> {
-        // Header
-        let mut header = [0; SYSEX_HEADER.len()];
-        reader.read_exact(&mut header)?;
-        if header != SYSEX_HEADER {
-            return Err(Error::new(ErrorKind::InvalidData, "Incorrect header"));
-        }
-
-        // Body
-        let mut body = [0; 4096]; // Length is hard coded in the header
-        reader.read_exact(&mut body)?;
-
-        // Body checksum
-        let mut byte_buf = [0; 1];
-        reader.read_exact(&mut byte_buf)?;
-        let expected_checksum = byte_buf[0];
-        let computed_checksum = checksum(&body);
-        if computed_checksum != expected_checksum {
-            return Err(Error::new(ErrorKind::InvalidData, format!("Computed checksum {computed_checksum} does not match expected checksum {expected_checksum}")));
-        }
-
-        // Verify the end of SysEx marker
-        reader.read_exact(&mut byte_buf)?;
-        if byte_buf[0] != 0xF7 {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "Missing End of SysEx marker",
-            ));
-        }
-
-        // Presets
-        let mut presets = Vec::with_capacity(32);
-        for packed_preset in body.chunks(128) {
-            // Going directly to a String is unsafe because the name bytes may
-            // be garbage.
-            let name = PresetName::from_lossy(&packed_preset[118..127]);
-
-            // Operators
-            let mut operators = [Operator::default(); Preset::OPERATOR_COUNT];
-            for operator_index in 0..operators.len() {
-                let packed_operator =
-                    &packed_preset[(operator_index * 17)..(operator_index + 1) * 17];
-
-                // Envelope generators
-                let rates = &packed_operator[0..4];
-                let levels = &packed_operator[rates.len()..(rates.len() + 4)];
-                let envelope =
-                    Envelope::try_from_rates_and_levels(rates, levels).expect("envelope");
-
-                let scaling_break_point = packed_operator[8];
-                let scaling_left_depth = packed_operator[9];
-                let scaling_right_depth = packed_operator[10];
-                let scaling_left_curve = packed_operator[11] & 0b0011;
-                let scaling_right_curve = (packed_operator[11] & 0b1100) >> 2;
-
-                // -7 to 7 stored as 0-14 in the preset
-                let detune = 0_i8 - ((packed_operator[12] & 0b1111000) >> 3) as i8;
-
-                let rate_scaling = packed_operator[12] & 0b0000111; // 0-7
-                let velocity_sensitivity = (packed_operator[13] & 0b0011100) >> 2; // 0-7
-                let modulation_sensitivity = packed_operator[13] & 0b0000011; // 0-3
-                let output_level = packed_operator[14]; // 0-99
-
-                let mode = if packed_operator[15] & 0b0000001 == 0 {
-                    OperatorMode::Fixed
+        let source = path.to_string_lossy().to_string();
+        Ok(Self::read_all(&mut reader)?
+            .into_iter()
+            .map(|bank| Bank {
+                source: Some(source.clone()),
+                ..bank
+            })
+            .collect())
+    }
+
+    /// Read every bank from a series of SysEx messages until the end of the
+    /// input. Messages that are not banks are skipped.
+    pub fn read_all<R: Read>(reader: &mut R) -> Result<Vec<Bank>, Error> {
+        let mut reader = CountingReader::new(reader, 0);
+        let mut banks = Vec::new();
+        while let Some((offset, message)) = read_message(&mut reader)? {
+            // Banks are identified by the format number so that a bank with
+            // an incorrect byte count is reported instead of skipped.
+            if Format::detect(&message) == Some(FormatKind::VoiceBank32) {
+                let mut message_reader = CountingReader::new(message.as_slice(), offset);
+                let dump = Self::read_bank(&mut message_reader, ReadOptions::default())?;
+                banks.push(Bank::new(dump.presets));
+            } else {
+                log::warn!(
+                    "Skipping {} byte SysEx message that is not a bank at offset {offset:#X}",
+                    message.len()
+                );
+            }
+        }
+        Ok(banks)
+    }
+
+    pub fn read_raw_file<P: AsRef<Path>>(path: P) -> Result<Bank, Error> {
+        let path = path.as_ref();
+        let input = File::open(path)?;
+        let mut reader = BufReader::new(input);
+        Ok(Self::read_raw(&mut reader)?.named_after(path))
+    }
+
+    /// Read a bank that is only the 4096 bytes of packed presets without the
+    /// SysEx header, checksum or end marker. These are often found with a
+    /// `.bin` or `.dx7` extension.
+    pub fn read_raw<R: Read>(reader: &mut R) -> Result<Bank, Error> {
+        // Read one more byte than a full SysEx message to detect the length
+        let mut body = Vec::with_capacity(Format::BANK_BODY_LEN);
+        reader
+            .take(Format::BANK_MESSAGE_LEN as u64 + 1)
+            .read_to_end(&mut body)?;
+        if body.len() != Format::BANK_BODY_LEN {
+            return Err(
+                if (Format::BANK_BODY_LEN + 1..=Format::BANK_MESSAGE_LEN).contains(&body.len())
+                    && body.starts_with(&[0xF0, 0x43])
+                {
+                    Error::UnexpectedSysex
                 } else {
-                    OperatorMode::Ratio
-                };
+                    Error::IncorrectLength {
+                        expected: Format::BANK_BODY_LEN,
+                        found: body.len(),
+                    }
+                },
+            );
+        }
+        let presets = unpack_presets(&body, 0, ReadOptions::default(), &mut Vec::new())?;
+        Ok(Bank::new(presets))
+    }
+
+    /// Name a bank after the file it was read from.
+    fn named_after(self, path: &Path) -> Bank {
+        Bank {
+            name: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string()),
+            source: Some(path.to_string_lossy().to_string()),
+            ..self
+        }
+    }
+}
+
+/// Read the next SysEx message, including the start and end markers, and
+/// the offset it starts at. Any bytes before the start of the message are
+/// skipped. Returns `None` at the end of the input.
+pub(crate) fn read_message<R: Read>(
+    reader: &mut CountingReader<R>,
+) -> Result<Option<(u64, Vec<u8>)>, Error> {
+    loop {
+        match read_byte(reader)? {
+            Some(0xF0) => break,
+            Some(_) => continue,
+            None => return Ok(None),
+        }
+    }
 
-                let frequency_course = (packed_operator[15] & 0b0111110) >> 1; // 0-31
-                let frequency_fine = packed_operator[16]; // 0-99
-
-                operators[operator_index] = Operator {
-                    envelope,
-                    scaling_break_point,
-                    scaling_left_depth,
-                    scaling_right_depth,
-                    scaling_left_curve,
-                    scaling_right_curve,
-                    detune,
-                    rate_scaling,
-                    velocity_sensitivity,
-                    modulation_sensitivity,
-                    output_level,
-                    mode,
-                    frequency_course,
-                    frequency_fine,
+    let offset = reader.position() - 1;
+    let mut message = vec![0xF0];
+    loop {
+        match read_byte(reader)? {
+            Some(byte) => {
+                message.push(byte);
+                if byte == 0xF7 {
+                    return Ok(Some((offset, message)));
+                }
+                if message.len() >= Format::MAX_MESSAGE_LEN {
+                    let limit = Format::MAX_MESSAGE_LEN;
+                    return Err(reader.locate(Error::MessageTooLong { limit }, offset));
                 }
             }
-            operators.reverse(); // Stored last-operator-first in the file
-
-            // Pitch envelope generators
-            let pitch_env_rates_base = 102;
-            let rates = &packed_preset[pitch_env_rates_base..(pitch_env_rates_base + 4)];
-            let pitch_env_levels_base = pitch_env_rates_base + rates.len();
-            let levels =
-                &packed_preset[pitch_env_levels_base..(pitch_env_levels_base + rates.len())];
-            let pitch_envelope =
-                Envelope::try_from_rates_and_levels(rates, levels).expect("pitch envelope");
-
-            let algorithm = packed_preset[110] as AlgorithmId;
-            let oscillator_key_sync = (packed_preset[111] & 0b0001000) >> 4 == 1;
-            let feedback_level = packed_preset[111] & 0b0000111;
-            let lfo_speed = packed_preset[112];
-            let lfo_delay = packed_preset[113];
-            let lfo_pitch_mod_depth = packed_preset[114];
-            let lfo_amplitude_mod_depth = packed_preset[115];
-            let lfo_pitch_mod_sensitivity = (packed_preset[116] & 0b1110000) >> 4;
-            let lfo_waveform = Waveform::try_from((packed_preset[116] & 0b0001110) >> 1)
-                .map_err(|msg| Error::new(ErrorKind::InvalidData, msg))?;
-            let lfo_key_sync = packed_preset[116] & 0b0000001 == 1;
-            let transpose = packed_preset[117];
-
-            let preset = Preset {
-                name,
-                operators,
-                pitch_envelope,
-                algorithm_id: algorithm,
-                oscillator_key_sync,
-                feedback_level,
-                lfo_speed,
-                lfo_delay,
-                lfo_pitch_mod_depth,
-                lfo_amplitude_mod_depth,
-                lfo_pitch_mod_sensitivity,
-                lfo_waveform,
-                lfo_key_sync,
-                transpose,
-            }
-            .normalize();
-            presets.push(preset);
+            None => return Err(reader.locate(Error::MissingEndOfSysex, reader.position())),
+        }
+    }
+}
+
+/// The format number of a Yamaha bulk dump message, or `None` if the message
+/// is not a bulk dump.
+pub(crate) fn format_number(message: &[u8]) -> Option<u8> {
+    match message {
+        [0xF0, 0x43, status, format, ..] if status & 0xF0 == 0 => Some(*format),
+        _ => None,
+    }
+}
+
+/// Read a single byte, returning `None` at the end of the input.
+fn read_byte<R: Read>(reader: &mut R) -> Result<Option<u8>, Error> {
+    let mut byte_buf = [0; 1];
+    match read_exact(reader, &mut byte_buf) {
+        Ok(()) => Ok(Some(byte_buf[0])),
+        Err(Error::UnexpectedEof { .. }) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Read the header, body and footer of a bank, returning the MIDI channel,
+/// the body and the offset of the body in the input.
+pub(crate) fn read_bank_body<R: Read>(
+    reader: &mut CountingReader<R>,
+    options: ReadOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<(u8, [u8; Format::BANK_BODY_LEN], u64), Error> {
+    let start = reader.position();
+    let channel = read_header(reader, &SYSEX_HEADER).map_err(|err| {
+        let offset = match err {
+            Error::IncorrectByteCount { .. } => start + 4,
+            _ => start,
+        };
+        reader.locate(err, offset)
+    })?;
+
+    // Body, which the header was checked to declare the length of
+    let body_offset = reader.position();
+    let mut body = [0; Format::BANK_BODY_LEN];
+    read_exact(reader, &mut body).map_err(|err| reader.locate(err, body_offset))?;
+
+    // Body checksum and the end of SysEx marker
+    let checksum_offset = reader.position();
+    match read_footer(reader, &body) {
+        Err(Error::ChecksumMismatch { expected, computed }) if !options.verify_checksum => {
+            warnings.push(Warning::ChecksumMismatch { expected, computed });
+        }
+        Err(err @ Error::MissingEndOfSysex) => {
+            return Err(reader.locate(err, checksum_offset + 1));
         }
-        Ok(presets)
+        result => result.map_err(|err| reader.locate(err, checksum_offset))?,
     }
+
+    if options.require_eof {
+        let end = reader.position();
+        if read_byte(reader)?.is_some() {
+            return Err(reader.locate(Error::TrailingData, end));
+        }
+    }
+
+    Ok((channel, body, body_offset))
+}
+
+/// Unpack the presets in the body of a bank that starts at `body_offset` in
+/// the input. Parameters that are clamped are added to the warnings.
+fn unpack_presets(
+    body: &[u8],
+    body_offset: u64,
+    options: ReadOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<Preset>, Error> {
+    let remainder = body.len() % Format::PACKED_VOICE_LEN;
+    if remainder != 0 {
+        return Err(Error::UnexpectedEof {
+            needed: Format::PACKED_VOICE_LEN - remainder,
+        });
+    }
+
+    packed_chunks(body)
+        .enumerate()
+        .map(|(index, packed_preset)| {
+            unpack_voice(packed_preset, index, body_offset, options, warnings)
+        })
+        .collect()
+}
+
+/// Unpack and normalize the preset at `index` in the body of a bank.
+pub(crate) fn unpack_voice(
+    packed_preset: &[u8; Format::PACKED_VOICE_LEN],
+    index: usize,
+    body_offset: u64,
+    options: ReadOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Preset, Error> {
+    let voice_error = |field_offset: usize, err| {
+        // The name is decoded on its own as it may still be readable
+        let name = PresetName::from_lossy(&packed_preset[118..128]);
+        Error::Voice {
+            index,
+            name: (!name.to_string().is_empty()).then_some(name),
+            offset: body_offset + (index * Format::PACKED_VOICE_LEN + field_offset) as u64,
+            source: Box::new(err),
+        }
+    };
+    let preset = unpack_preset(packed_preset)
+        .map_err(|(field_offset, err)| voice_error(field_offset, err))?;
+
+    let mut out_of_range = Vec::new();
+    let preset = preset.normalize_with(&mut out_of_range);
+    if let (true, Some(first)) = (options.strict, out_of_range.first()) {
+        return Err(voice_error(0, Error::OutOfRange(*first)));
+    }
+    warnings.extend(
+        out_of_range
+            .into_iter()
+            .map(|out_of_range| Warning::OutOfRange {
+                index,
+                out_of_range,
+            }),
+    );
+    Ok(preset)
+}
+
+/// Presets of a bank that are unpacked as the iterator is advanced. Created
+/// by [`Bank::presets`].
+#[derive(Clone, Debug)]
+pub struct PresetIter {
+    body: Vec<u8>,
+    body_offset: u64,
+    index: usize,
+}
+
+impl Iterator for PresetIter {
+    type Item = Result<Preset, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.index;
+        let packed_preset = packed_chunks(&self.body).nth(index)?;
+        self.index += 1;
+        Some(unpack_voice(
+            packed_preset,
+            index,
+            self.body_offset,
+            ReadOptions::default(),
+            &mut Vec::new(),
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = Bank::PRESET_COUNT - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for PresetIter {}
+
+impl Preset {
+    /// Unpack a preset from the 128 byte voice layout used by banks. The
+    /// preset is normalized.
+    pub fn from_packed(packed_preset: &[u8; Format::PACKED_VOICE_LEN]) -> Result<Preset, Error> {
+        unpack_preset(packed_preset)
+            .map(|preset| preset.normalize())
+            .map_err(|(_, err)| err)
+    }
+}
+
+/// Unpack a preset from the 128 byte voice layout without normalizing it,
+/// returning the offset of the byte that could not be decoded on failure.
+fn unpack_preset(packed_preset: &[u8; Format::PACKED_VOICE_LEN]) -> Result<Preset, (usize, Error)> {
+    // Going directly to a String is unsafe because the name bytes may
+    // be garbage.
+    let name = PresetName::from_lossy(&packed_preset[118..128]);
+
+    // Operators
+    let mut operators = [Operator::default(); Preset::OPERATOR_COUNT];
+    for operator_index in 0..operators.len() {
+        let packed_operator = &packed_preset[(operator_index * 17)..(operator_index + 1) * 17];
+
+        // Envelope generators
+        let rates = &packed_operator[0..4];
+        let levels = &packed_operator[rates.len()..(rates.len() + 4)];
+        let envelope = Envelope::try_from_rates_and_levels(rates, levels)
+            .map_err(|_| (operator_index * 17, Error::InvalidEnvelope))?;
+
+        let scaling_break_point = packed_operator[8];
+        let scaling_left_depth = packed_operator[9];
+        let scaling_right_depth = packed_operator[10];
+        let scaling_curve = |curve| {
+            ScalingCurve::try_from(curve)
+                .map_err(|_| (operator_index * 17 + 11, Error::InvalidScalingCurve(curve)))
+        };
+        let scaling_left_curve = scaling_curve(packed_operator[11] & 0b0011)?;
+        let scaling_right_curve = scaling_curve((packed_operator[11] & 0b1100) >> 2)?;
+
+        let detune = Detune::from_stored((packed_operator[12] & 0b1111000) >> 3);
+
+        let rate_scaling = packed_operator[12] & 0b0000111; // 0-7
+        let velocity_sensitivity = (packed_operator[13] & 0b0011100) >> 2; // 0-7
+        let modulation_sensitivity = packed_operator[13] & 0b0000011; // 0-3
+        let output_level = packed_operator[14]; // 0-99
+
+        let mode = if packed_operator[15] & 0b0000001 == 0 {
+            OperatorMode::Ratio
+        } else {
+            OperatorMode::Fixed
+        };
+
+        let frequency_course = (packed_operator[15] & 0b0111110) >> 1; // 0-31
+        let frequency_fine = packed_operator[16]; // 0-99
+
+        operators[operator_index] = Operator {
+            envelope,
+            scaling_break_point,
+            scaling_left_depth,
+            scaling_right_depth,
+            scaling_left_curve,
+            scaling_right_curve,
+            detune,
+            rate_scaling,
+            velocity_sensitivity,
+            modulation_sensitivity,
+            output_level,
+            mode,
+            frequency_course,
+            frequency_fine,
+        }
+    }
+    operators.reverse(); // Stored last-operator-first in the file
+
+    // Pitch envelope generators
+    let pitch_env_rates_base = 102;
+    let rates = &packed_preset[pitch_env_rates_base..(pitch_env_rates_base + 4)];
+    let pitch_env_levels_base = pitch_env_rates_base + rates.len();
+    let levels = &packed_preset[pitch_env_levels_base..(pitch_env_levels_base + rates.len())];
+    let pitch_envelope = Envelope::try_from_rates_and_levels(rates, levels)
+        .map_err(|_| (pitch_env_rates_base, Error::InvalidEnvelope))?;
+
+    let algorithm = AlgorithmId::from_stored(packed_preset[110]);
+    let oscillator_key_sync = (packed_preset[111] & 0b0001000) >> 3 == 1;
+    let feedback_level = packed_preset[111] & 0b0000111;
+    let lfo_speed = packed_preset[112];
+    let lfo_delay = packed_preset[113];
+    let lfo_pitch_mod_depth = packed_preset[114];
+    let lfo_amplitude_mod_depth = packed_preset[115];
+    let lfo_pitch_mod_sensitivity = (packed_preset[116] & 0b1110000) >> 4;
+    let lfo_waveform = (packed_preset[116] & 0b0001110) >> 1;
+    let lfo_waveform = Waveform::try_from(lfo_waveform)
+        .map_err(|_| (116, Error::InvalidWaveform(lfo_waveform)))?;
+    let lfo_key_sync = packed_preset[116] & 0b0000001 == 1;
+    let transpose = packed_preset[117];
+
+    Ok(Preset {
+        name,
+        operators,
+        pitch_envelope,
+        algorithm_id: algorithm,
+        oscillator_key_sync,
+        feedback_level,
+        lfo_speed,
+        lfo_delay,
+        lfo_pitch_mod_depth,
+        lfo_amplitude_mod_depth,
+        lfo_pitch_mod_sensitivity,
+        lfo_waveform,
+        lfo_key_sync,
+        transpose,
+    })
 }
 
 #[cfg(test)]
@@ -165,28 +741,588 @@ mod tests {
     use super::*;
 
     #[test]
-    fn checksums() {
-        assert_eq!(0, checksum(&[]));
-        assert_eq!(0, checksum(&[0,]));
-        assert_eq!(86, checksum(&[42,]));
-        assert_eq!(113, checksum(&[1, 2, 3, 4, 5,]));
-        assert_eq!(94, checksum(&[100, 20, 30, 40, 100,]));
+    fn packed_round_trip() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let contents = std::fs::read(path).unwrap();
+        let body = &contents[SYSEX_HEADER.len()..(SYSEX_HEADER.len() + 4096)];
+        for (index, packed_preset) in body.chunks_exact(128).enumerate() {
+            let packed_preset: &[u8; 128] = packed_preset.try_into().unwrap();
+            let preset = Preset::from_packed(packed_preset).unwrap();
+            assert_eq!(
+                packed_preset,
+                &preset.to_packed(),
+                "Preset index {index} does not round trip"
+            );
+        }
+    }
+
+    #[test]
+    fn packed_bit_fields() {
+        let packed = Preset::default().to_packed();
+
+        // Byte 111 is feedback and oscillator key sync
+        for (byte, feedback_level, oscillator_key_sync) in [
+            (0b0000000, 0, false),
+            (0b0000111, 7, false),
+            (0b0001000, 0, true),
+            (0b0001101, 5, true),
+            (0b1110010, 2, false),
+        ] {
+            let mut packed = packed;
+            packed[111] = byte;
+            let preset = Preset::from_packed(&packed).unwrap();
+            assert_eq!(feedback_level, preset.feedback_level, "Byte {byte:#09b}");
+            assert_eq!(
+                oscillator_key_sync, preset.oscillator_key_sync,
+                "Byte {byte:#09b}"
+            );
+        }
+
+        // Byte 116 is LFO key sync, waveform and pitch mod sensitivity
+        for (byte, lfo_key_sync, lfo_waveform, lfo_pitch_mod_sensitivity) in [
+            (0b0000000, false, Waveform::Triangle, 0),
+            (0b0000001, true, Waveform::Triangle, 0),
+            (0b0000010, false, Waveform::SawDown, 0),
+            (0b0001010, false, Waveform::SampleAndHold, 0),
+            (0b0001000, false, Waveform::Sine, 0),
+            (0b0110111, true, Waveform::Square, 3),
+            (0b1110000, false, Waveform::Triangle, 7),
+            (0b1011001, true, Waveform::Sine, 5),
+        ] {
+            let mut packed = packed;
+            packed[116] = byte;
+            let preset = Preset::from_packed(&packed).unwrap();
+            assert_eq!(lfo_key_sync, preset.lfo_key_sync, "Byte {byte:#09b}");
+            assert_eq!(lfo_waveform, preset.lfo_waveform, "Byte {byte:#09b}");
+            assert_eq!(
+                lfo_pitch_mod_sensitivity, preset.lfo_pitch_mod_sensitivity,
+                "Byte {byte:#09b}"
+            );
+        }
+    }
+
+    #[test]
+    fn channels() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let contents = std::fs::read(path).unwrap();
+        for channel in [0, 4, 15] {
+            let mut contents = contents.clone();
+            contents[2] = channel;
+            let dump = Bank::read_dump(&mut contents.as_slice()).unwrap();
+            assert_eq!(channel, dump.channel);
+            assert_eq!(32, dump.presets.len());
+        }
+
+        let mut contents = contents;
+        contents[2] = 0x10;
+        let error = Bank::read_dump(&mut contents.as_slice()).unwrap_err();
+        assert!(matches!(
+            error.root_cause(),
+            Error::IncorrectHeader {
+                found: [0xF0, 0x43, 0x10, 0x09, 0x20, 0x00]
+            }
+        ));
+    }
+
+    #[test]
+    fn byte_count() {
+        assert_eq!(4096, super::byte_count(&SYSEX_HEADER));
+        assert_eq!(
+            155,
+            super::byte_count(&[0xF0, 0x43, 0x00, 0x00, 0x01, 0x1B])
+        );
+
+        let path = test_data_path(&["rom1a.syx"]);
+        let contents = std::fs::read(path).unwrap();
+
+        // A single voice count in a bank header
+        let mut data = contents.clone();
+        data[4..6].copy_from_slice(&[0x01, 0x1B]);
+        let error = Bank::load(&mut data.as_slice(), None).unwrap_err();
+        assert_eq!(
+            "At offset 0x4: Expected a 4096 byte body, header declares 155",
+            error.to_string()
+        );
+        assert!(matches!(
+            error.root_cause(),
+            Error::IncorrectByteCount {
+                expected: 4096,
+                declared: 155
+            }
+        ));
+
+        let mut data = contents;
+        data[5] = 0x01;
+        let error = Bank::read_all(&mut data.as_slice()).unwrap_err();
+        assert!(matches!(
+            error.root_cause(),
+            Error::IncorrectByteCount {
+                expected: 4096,
+                declared: 4097
+            }
+        ));
+    }
+
+    #[test]
+    fn raw() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let contents = std::fs::read(&path).unwrap();
+        let body = &contents[SYSEX_HEADER.len()..(SYSEX_HEADER.len() + 4096)];
+        let bank = Bank::read_raw(&mut &body[..]).unwrap();
+        assert_eq!(Bank::load_file(&path).unwrap().presets, bank.presets);
+        assert_eq!(None, bank.name);
+
+        let error = Bank::read_raw_file(&path).unwrap_err();
+        assert!(matches!(error, Error::UnexpectedSysex));
+        assert!(error.to_string().contains("Bank::read"));
+
+        let error = Bank::read_raw(&mut &body[1..]).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::IncorrectLength {
+                expected: 4096,
+                found: 4095
+            }
+        ));
+    }
+
+    #[test]
+    fn multiple_banks() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let presets = Bank::load_file(&path).unwrap().presets;
+
+        let twice = test_data_path(&["rom1a_twice.syx"]);
+        let banks = Bank::read_all_file(&twice).unwrap();
+        assert_eq!(2, banks.len());
+        for bank in banks {
+            assert_eq!(presets, bank.presets);
+            assert_eq!(None, bank.name);
+            assert_eq!(Some(twice.to_string_lossy().to_string()), bank.source);
+        }
+
+        // Other messages are skipped
+        let contents = std::fs::read(&path).unwrap();
+        let mut data = contents.clone();
+        data.extend_from_slice(&[0xF0, 0x43, 0x00, 0x01, 0x00, 0x01, 0x00, 0x7F, 0xF7]);
+        data.extend_from_slice(&contents);
+        let banks = Bank::read_all(&mut data.as_slice()).unwrap();
+        assert_eq!(vec![Bank::new(presets.clone()), Bank::new(presets)], banks);
+
+        assert!(Bank::read_all(&mut [].as_slice()).unwrap().is_empty());
+
+        let error = Bank::read_all(&mut &contents[..100]).unwrap_err();
+        assert!(matches!(error.root_cause(), Error::MissingEndOfSysex));
+
+        // A message without an end marker is not buffered indefinitely
+        let mut data = contents.clone();
+        data[4103] = 0;
+        data.resize(Format::MAX_MESSAGE_LEN * 2, 0);
+        let error = Bank::read_all(&mut data.as_slice()).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::At { offset: 0, source } if matches!(*source, Error::MessageTooLong { limit: 16391 })
+        ));
+
+        // Errors in later banks are located in the whole input
+        let mut data = [contents.clone(), contents.clone()].concat();
+        data[4104 + 6 + 3 * 128 + 116] |= 0b0001110;
+        data[4104 + 4102] = checksum(&data[4104 + 6..4104 + 4102]);
+        let error = Bank::read_all(&mut data.as_slice()).unwrap_err();
+        assert_eq!(
+            "Voice 3 ('STRINGS 1') at offset 0x1202: Invalid LFO waveform 7",
+            error.to_string()
+        );
+
+        let mut data = [contents.clone(), contents].concat();
+        data[4104 + 4102] ^= 1;
+        let error = Bank::read_all(&mut data.as_slice()).unwrap_err();
+        assert!(matches!(error, Error::At { offset: 0x200E, .. }));
+    }
+
+    #[test]
+    fn from_bytes() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let presets = Bank::load_file(&path).unwrap().presets;
+
+        let data = std::fs::read(test_data_path(&["rom1a_twice.syx"])).unwrap();
+        let (first, consumed) = Bank::from_bytes(&data).unwrap();
+        assert_eq!(presets, first.presets);
+        assert_eq!(4104, consumed);
+        let (second, consumed) = Bank::from_bytes(&data[consumed..]).unwrap();
+        assert_eq!(presets, second.presets);
+        assert_eq!(4104, consumed);
+
+        let error = Bank::from_bytes(&data[..4000]).unwrap_err();
+        assert!(matches!(
+            error.root_cause(),
+            Error::UnexpectedEof { needed: 102 }
+        ));
+    }
+
+    #[test]
+    fn lazy_presets() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let presets = Bank::load_file(&path).unwrap().presets;
+        let mut contents = std::fs::read(&path).unwrap();
+
+        let iter = Bank::presets(&mut contents.as_slice()).unwrap();
+        assert_eq!(32, iter.len());
+        let lazy: Vec<_> = iter.map(Result::unwrap).collect();
+        assert_eq!(presets, lazy);
+
+        // Only the voices that are unpacked can fail
+        contents[6 + 3 * 128 + 116] |= 0b0001110;
+        contents[4102] = checksum(&contents[6..4102]);
+        let mut iter = Bank::presets(&mut contents.as_slice()).unwrap();
+        let first: Vec<_> = iter.by_ref().take(3).map(Result::unwrap).collect();
+        assert_eq!(presets[..3], first);
+        assert!(matches!(
+            iter.next(),
+            Some(Err(Error::Voice { index: 3, .. }))
+        ));
+        assert_eq!(Some(&presets[4]), iter.next().unwrap().as_ref().ok());
+        assert_eq!(27, iter.len());
+
+        // The checksum is verified before any preset is returned
+        contents[4102] ^= 1;
+        let error = Bank::presets(&mut contents.as_slice()).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::At { offset: 4102, source } if matches!(*source, Error::ChecksumMismatch { .. })
+        ));
+        contents[4102] ^= 1;
+        contents[4103] = 0;
+        let error = Bank::presets(&mut contents.as_slice()).unwrap_err();
+        assert!(matches!(error.root_cause(), Error::MissingEndOfSysex));
+    }
+
+    #[test]
+    fn trailing_data() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let contents = std::fs::read(&path).unwrap();
+        let presets = Bank::load_file(&path).unwrap().presets;
+        let options = ReadOptions {
+            require_eof: true,
+            ..Default::default()
+        };
+
+        let trailers: [&[u8]; 3] = [&[0], &[0; 512], b"Downloaded from a patch site"];
+        for trailer in trailers {
+            let data = [contents.as_slice(), trailer].concat();
+
+            // The reader is left after the end marker
+            let mut reader = data.as_slice();
+            assert_eq!(presets, Bank::load(&mut reader, None).unwrap().presets);
+            assert_eq!(trailer, reader);
+
+            let error = Bank::read_with(&mut data.as_slice(), options).unwrap_err();
+            assert!(matches!(error, Error::At { offset: 4104, .. }));
+            assert!(matches!(error.root_cause(), Error::TrailingData));
+        }
+
+        let dump = Bank::read_with(&mut contents.as_slice(), options).unwrap();
+        assert_eq!(presets, dump.presets);
+    }
+
+    #[test]
+    fn partial() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let presets = Bank::load_file(&path).unwrap().presets;
+        let mut contents = std::fs::read(&path).unwrap();
+
+        let (recovered, errors) = Bank::read_partial(&mut contents.as_slice()).unwrap();
+        assert_eq!(presets, recovered);
+        assert!(errors.is_empty());
+
+        // Two and a half voices
+        let len = 6 + 128 * 5 / 2;
+        let (recovered, errors) = Bank::read_partial(&mut &contents[..len]).unwrap();
+        assert_eq!(presets[..2], recovered);
+        assert_eq!(1, errors.len());
+        assert_eq!(
+            "At offset 0x146: Unexpected end of input, 3776 more bytes needed",
+            errors[0].to_string()
+        );
+
+        // Damaged voices and checksum are skipped
+        contents[6 + 3 * 128 + 116] |= 0b0001110;
+        let (recovered, errors) = Bank::read_partial(&mut contents.as_slice()).unwrap();
+        assert_eq!(31, recovered.len());
+        assert_eq!(presets[4], recovered[3]);
+        assert!(matches!(
+            errors[0].root_cause(),
+            Error::ChecksumMismatch { .. }
+        ));
+        assert!(matches!(errors[1], Error::Voice { index: 3, .. }));
+
+        let error = Bank::read_partial(&mut &contents[..3]).unwrap_err();
+        assert!(matches!(error.root_cause(), Error::UnexpectedEof { .. }));
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let mut contents = std::fs::read(&path).unwrap();
+        contents[4102] = (contents[4102] + 1) & 0x7F;
+
+        let error = Bank::read_dump(&mut contents.as_slice()).unwrap_err();
+        assert!(matches!(
+            error.root_cause(),
+            Error::ChecksumMismatch {
+                expected: 52,
+                computed: 51
+            }
+        ));
+        assert_eq!(
+            "At offset 0x1006: Computed checksum 51 does not match expected checksum 52",
+            error.to_string()
+        );
+
+        let options = ReadOptions {
+            verify_checksum: false,
+            ..Default::default()
+        };
+        let dump = Bank::read_with(&mut contents.as_slice(), options).unwrap();
+        assert_eq!(Bank::load_file(&path).unwrap().presets, dump.presets);
+        assert_eq!(
+            vec![Warning::ChecksumMismatch {
+                expected: 52,
+                computed: 51
+            }],
+            dump.warnings
+        );
+    }
+
+    #[test]
+    fn strict() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let mut contents = std::fs::read(&path).unwrap();
+        let options = ReadOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let dump = Bank::read_with(&mut contents.as_slice(), options).unwrap();
+        assert_eq!(Bank::load_file(&path).unwrap().presets, dump.presets);
+
+        // Transpose of the sixth voice
+        contents[6 + 5 * 128 + 117] = 100;
+        contents[4102] = checksum(&contents[6..4102]);
+
+        let presets = Bank::load(&mut contents.as_slice(), None).unwrap().presets;
+        assert_eq!(48, presets[5].transpose);
+
+        let error = Bank::read_with(&mut contents.as_slice(), options).unwrap_err();
+        let Error::OutOfRange(out_of_range) = error.root_cause() else {
+            panic!("Expected a value out of range, not {error:?}");
+        };
+        assert_eq!(None, out_of_range.operator);
+        assert_eq!("transpose", out_of_range.parameter);
+        assert_eq!(100, out_of_range.value);
+        assert!(matches!(error, Error::Voice { index: 5, .. }));
+        assert!(error
+            .to_string()
+            .ends_with("Transpose is 100, not between 0 and 48"));
+
+        // Operator parameters
+        contents[6 + 5 * 128 + 117] = 24;
+        contents[6 + 5 * 128 + 17 * 3 + 14] = 123;
+        contents[4102] = checksum(&contents[6..4102]);
+        let error = Bank::read_with(&mut contents.as_slice(), options).unwrap_err();
+        assert!(error
+            .to_string()
+            .ends_with("Operator 3 output level is 123, not between 0 and 99"));
+    }
+
+    #[test]
+    fn out_of_range_warnings() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let mut contents = std::fs::read(&path).unwrap();
+        let dump = Bank::read_dump(&mut contents.as_slice()).unwrap();
+        assert!(dump.warnings.is_empty());
+
+        contents[6 + 2 * 128 + 117] = 100; // Transpose
+        contents[6 + 7 * 128 + 17 * 5 + 14] = 123; // Operator 1 output level
+        contents[6 + 7 * 128 + 102] = 127; // Pitch EG rate 1
+        contents[4102] = checksum(&contents[6..4102]);
+        let dump = Bank::read_dump(&mut contents.as_slice()).unwrap();
+        assert_eq!(
+            vec![
+                Warning::OutOfRange {
+                    index: 2,
+                    out_of_range: OutOfRange {
+                        operator: None,
+                        parameter: "transpose",
+                        value: 100,
+                        min: 0,
+                        max: 48,
+                    },
+                },
+                Warning::OutOfRange {
+                    index: 7,
+                    out_of_range: OutOfRange {
+                        operator: Some(0),
+                        parameter: "output level",
+                        value: 123,
+                        min: 0,
+                        max: 99,
+                    },
+                },
+                Warning::OutOfRange {
+                    index: 7,
+                    out_of_range: OutOfRange {
+                        operator: None,
+                        parameter: "pitch EG rate 1",
+                        value: 127,
+                        min: 0,
+                        max: 99,
+                    },
+                },
+            ],
+            dump.warnings
+        );
+        assert_eq!(
+            "Voice 2: Transpose is 100, not between 0 and 48, clamped to 48",
+            dump.warnings[0].to_string()
+        );
+        assert_eq!(48, dump.presets[2].transpose);
+        assert_eq!(99, dump.presets[7].operators[0].output_level);
+    }
+
+    #[test]
+    fn malformed() {
+        let path = test_data_path(&["rom1a.syx"]);
+        let contents = std::fs::read(path).unwrap();
+
+        // Invalid LFO waveform in the fourth voice
+        let mut body = contents[6..4102].to_vec();
+        body[3 * 128 + 116] |= 0b0001110;
+        let error = Bank::read_raw(&mut body.as_slice()).unwrap_err();
+        assert_eq!(
+            "Voice 3 ('STRINGS 1') at offset 0x1F4: Invalid LFO waveform 7",
+            error.to_string()
+        );
+        let Error::Voice {
+            index,
+            name,
+            offset,
+            source,
+        } = error
+        else {
+            panic!("Expected a voice error, not {error:?}");
+        };
+        assert_eq!(3, index);
+        assert_eq!(Some(PresetName::from_lossy(b"STRINGS 1")), name);
+        assert_eq!(3 * 128 + 116, offset);
+        assert!(matches!(*source, Error::InvalidWaveform(7)));
+
+        // Voices without a name
+        body[3 * 128 + 118..3 * 128 + 128].fill(b' ');
+        let error = Bank::read_raw(&mut body.as_slice()).unwrap_err();
+        assert_eq!(
+            "Voice 3 at offset 0x1F4: Invalid LFO waveform 7",
+            error.to_string()
+        );
+
+        // Truncated
+        for (len, expected_needed) in [(0, 6), (3, 3), (6, 4096), (100, 4002), (4102, 2)] {
+            let error = Bank::load(&mut &contents[..len], None).unwrap_err();
+            let Error::UnexpectedEof { needed } = *error.root_cause() else {
+                panic!("Expected an unexpected end of input, not {error:?}");
+            };
+            assert_eq!(expected_needed, needed, "Length {len}");
+            let Error::At { offset, .. } = error else {
+                panic!("Expected an offset, not {error:?}");
+            };
+            assert_eq!(len as u64, offset, "Length {len}");
+        }
+
+        // Missing end marker
+        let mut data = contents.clone();
+        data[4103] = 0;
+        let error = Bank::load(&mut data.as_slice(), None).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::At { offset: 4103, source } if matches!(*source, Error::MissingEndOfSysex)
+        ));
+
+        // Garbage with a valid checksum must not panic
+        let mut seed = 0x1234_5678_u32;
+        for _ in 0..100 {
+            let mut data = SYSEX_HEADER.to_vec();
+            let body = (0..4096).map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed >> 24) as u8 & 0x7F
+            });
+            data.extend(body);
+            data.push(checksum(&data[6..]));
+            data.push(0xF7);
+            let _ = Bank::load(&mut data.as_slice(), None);
+        }
+    }
+
+    #[test]
+    fn operator_mode() {
+        // Bit 0 of byte 15 of each packed operator is 0 for ratio and 1 for a
+        // fixed frequency. Few factory voices use fixed frequencies.
+        let path = test_data_path(&["rom1a.syx"]);
+        let contents = std::fs::read(path).unwrap();
+        let body = &contents[6..6 + 4096];
+        let presets: Vec<Preset> = body
+            .chunks_exact(128)
+            .map(|packed_preset| Preset::from_packed(packed_preset.try_into().unwrap()).unwrap())
+            .collect();
+        let fixed: Vec<usize> = presets
+            .iter()
+            .enumerate()
+            .filter(|(_, preset)| {
+                preset
+                    .operators
+                    .iter()
+                    .any(|operator| operator.mode == OperatorMode::Fixed)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        // TUB BELLS, STEEL DRUM, REFS WHISL and TRAIN
+        assert_eq!(vec![25, 26, 28, 30], fixed);
+
+        // Every operator of the referee whistle is fixed
+        assert!(presets[28]
+            .operators
+            .iter()
+            .all(|operator| operator.mode == OperatorMode::Fixed));
     }
 
     #[test]
     fn factory_bank() {
-        let presets = Bank::read_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"]))
+            .unwrap()
+            .presets;
         assert_eq!(presets.len(), 32);
 
         let preset = presets.first().unwrap();
         assert_eq!("BRASS   1", preset.name.to_string());
-        assert_eq!(21, preset.algorithm_id);
+        assert_eq!(21, preset.algorithm_id.stored());
+        assert_eq!("Algorithm 22", preset.algorithm_id.to_string());
         assert_eq!(Waveform::Sine, preset.lfo_waveform);
+        assert!(preset.oscillator_key_sync);
+        assert_eq!(7, preset.feedback_level);
 
-        let op1 = preset.operators[0];
-        assert_eq!(0, op1.detune);
+        let detunes = preset.operators.map(|operator| operator.detune.value());
+        assert_eq!([7, 7, -2, 0, 1, 0], detunes);
+        assert_eq!("+7", preset.operators[0].detune.to_string());
 
+        let op2 = preset.operators[1];
+        assert_eq!(ScalingCurve::PositiveLinear, op2.scaling_left_curve);
+        assert_eq!(ScalingCurve::NegativeExponential, op2.scaling_right_curve);
         let op6 = preset.operators[5];
-        assert_eq!(0, op6.detune);
+        assert_eq!("-EXP", op6.scaling_left_curve.to_string());
+        assert_eq!("-EXP", op6.scaling_right_curve.to_string());
+        assert_eq!(14, preset.operators[0].detune.stored());
+
+        // Names that use all ten characters
+        assert_eq!("SYN-LEAD 1", presets[13].name.to_string());
+        assert_eq!("HARPSICH 1", presets[18].name.to_string());
+        assert_eq!("ORCH-CHIME", presets[24].name.to_string());
+        assert_eq!("STEEL DRUM", presets[26].name.to_string());
     }
 }
@@ -0,0 +1,243 @@
+//! Serialization with [serde](https://serde.rs), enabled by the `serde`
+//! feature.
+//!
+//! Fields use the names of the struct fields and enums are strings such as
+//! `"saw_down"`. Names are plain strings, detune is from -7 to 7 and the
+//! algorithm is the number stored in presets, from 0 to 31.
+//!
+//! Values out of range fail to deserialize instead of being clamped, so a
+//! preset is deserialized exactly as it was serialized or not at all.
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::normalize::Normalizer;
+use crate::{
+    AlgorithmId, Detune, Envelope, EnvelopeError, Operator, OperatorMode, OutOfRange, Preset,
+    PresetName, ScalingCurve, Waveform,
+};
+
+impl Serialize for PresetName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for PresetName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Detune {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i8(self.value())
+    }
+}
+
+impl<'de> Deserialize<'de> for Detune {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = i8::deserialize(deserializer)?;
+        if (Detune::MIN..=Detune::MAX).contains(&value) {
+            Ok(Detune::new(value))
+        } else {
+            Err(D::Error::custom(OutOfRange {
+                operator: None,
+                parameter: "detune",
+                value: value as i16,
+                min: Detune::MIN as i16,
+                max: Detune::MAX as i16,
+            }))
+        }
+    }
+}
+
+/// The first parameter that normalizing would clamp.
+fn check<T>(value: T, normalize: impl FnOnce(&T, &mut Vec<OutOfRange>)) -> Result<T, OutOfRange> {
+    let mut out_of_range = Vec::new();
+    normalize(&value, &mut out_of_range);
+    match out_of_range.first() {
+        Some(out_of_range) => Err(*out_of_range),
+        None => Ok(value),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct EnvelopeFields {
+    rates: [u8; Envelope::SEGMENT_COUNT],
+    levels: [u8; Envelope::SEGMENT_COUNT],
+}
+
+impl TryFrom<EnvelopeFields> for Envelope {
+    type Error = EnvelopeError;
+
+    fn try_from(fields: EnvelopeFields) -> Result<Self, Self::Error> {
+        Envelope::new(fields.rates, fields.levels)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct OperatorFields {
+    envelope: Envelope,
+    scaling_break_point: u8,
+    scaling_left_depth: u8,
+    scaling_right_depth: u8,
+    scaling_left_curve: ScalingCurve,
+    scaling_right_curve: ScalingCurve,
+    detune: Detune,
+    rate_scaling: u8,
+    velocity_sensitivity: u8,
+    modulation_sensitivity: u8,
+    output_level: u8,
+    mode: OperatorMode,
+    frequency_course: u8,
+    frequency_fine: u8,
+}
+
+impl TryFrom<OperatorFields> for Operator {
+    type Error = OutOfRange;
+
+    fn try_from(fields: OperatorFields) -> Result<Self, Self::Error> {
+        let operator = Operator {
+            envelope: fields.envelope,
+            scaling_break_point: fields.scaling_break_point,
+            scaling_left_depth: fields.scaling_left_depth,
+            scaling_right_depth: fields.scaling_right_depth,
+            scaling_left_curve: fields.scaling_left_curve,
+            scaling_right_curve: fields.scaling_right_curve,
+            detune: fields.detune,
+            rate_scaling: fields.rate_scaling,
+            velocity_sensitivity: fields.velocity_sensitivity,
+            modulation_sensitivity: fields.modulation_sensitivity,
+            output_level: fields.output_level,
+            mode: fields.mode,
+            frequency_course: fields.frequency_course,
+            frequency_fine: fields.frequency_fine,
+        };
+        check(operator, |operator, out_of_range| {
+            operator.normalize_with(&mut Normalizer::new(out_of_range));
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PresetFields {
+    name: PresetName,
+    operators: [Operator; Preset::OPERATOR_COUNT],
+    pitch_envelope: Envelope,
+    algorithm_id: AlgorithmId,
+    oscillator_key_sync: bool,
+    feedback_level: u8,
+    lfo_speed: u8,
+    lfo_delay: u8,
+    lfo_pitch_mod_depth: u8,
+    lfo_pitch_mod_sensitivity: u8,
+    lfo_amplitude_mod_depth: u8,
+    lfo_waveform: Waveform,
+    lfo_key_sync: bool,
+    transpose: u8,
+}
+
+impl TryFrom<PresetFields> for Preset {
+    type Error = OutOfRange;
+
+    fn try_from(fields: PresetFields) -> Result<Self, Self::Error> {
+        let preset = Preset {
+            name: fields.name,
+            operators: fields.operators,
+            pitch_envelope: fields.pitch_envelope,
+            algorithm_id: fields.algorithm_id,
+            oscillator_key_sync: fields.oscillator_key_sync,
+            feedback_level: fields.feedback_level,
+            lfo_speed: fields.lfo_speed,
+            lfo_delay: fields.lfo_delay,
+            lfo_pitch_mod_depth: fields.lfo_pitch_mod_depth,
+            lfo_pitch_mod_sensitivity: fields.lfo_pitch_mod_sensitivity,
+            lfo_amplitude_mod_depth: fields.lfo_amplitude_mod_depth,
+            lfo_waveform: fields.lfo_waveform,
+            lfo_key_sync: fields.lfo_key_sync,
+            transpose: fields.transpose,
+        };
+        check(preset, |preset, out_of_range| {
+            preset.normalize_with(out_of_range);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    #[test]
+    fn round_trip() {
+        let preset = Preset::default();
+        let json = serde_json::to_string(&preset).unwrap();
+        assert_eq!(preset, serde_json::from_str(&json).unwrap());
+
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        for preset in presets {
+            let json = serde_json::to_string_pretty(&preset).unwrap();
+            assert_eq!(preset, serde_json::from_str(&json).unwrap());
+        }
+    }
+
+    #[test]
+    fn format() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let json = serde_json::to_value(&presets[0]).unwrap();
+        assert_eq!("BRASS   1", json["name"]);
+        assert_eq!(21, json["algorithm_id"]);
+        assert_eq!("sine", json["lfo_waveform"]);
+        assert_eq!(true, json["oscillator_key_sync"]);
+
+        let operator = &json["operators"][1];
+        assert_eq!("ratio", operator["mode"]);
+        assert_eq!("positive_linear", operator["scaling_left_curve"]);
+        assert_eq!(7, operator["detune"]);
+        assert_eq!(
+            serde_json::json!({"rates": [84, 95, 95, 60], "levels": [50, 50, 50, 50]}),
+            json["pitch_envelope"]
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let json = serde_json::to_value(Preset::default()).unwrap();
+        let error = |change: &dyn Fn(&mut serde_json::Value)| {
+            let mut json = json.clone();
+            change(&mut json);
+            serde_json::from_value::<Preset>(json)
+                .unwrap_err()
+                .to_string()
+        };
+
+        let message = error(&|json| json["lfo_waveform"] = "wobble".into());
+        assert!(message.starts_with("unknown variant `wobble`"), "{message}");
+
+        let message = error(&|json| json["feedback_level"] = 8.into());
+        assert_eq!("Feedback level is 8, not between 0 and 7", message);
+
+        let message = error(&|json| json["operators"][2]["output_level"] = 100.into());
+        assert_eq!("Output level is 100, not between 0 and 99", message);
+
+        let message = error(&|json| json["operators"][0]["detune"] = (-8).into());
+        assert_eq!("Detune is -8, not between -7 and 7", message);
+
+        let message = error(&|json| json["pitch_envelope"]["rates"][1] = 100.into());
+        assert!(message.contains("100"), "{message}");
+
+        let message = error(&|json| json["name"] = "MUCH TOO LONG".into());
+        assert_eq!("Name is 13 characters, more than 10", message);
+
+        let message = error(&|json| json["name"] = "C:\\".into());
+        assert_eq!(
+            "Character '\\\\' at index 2 can't be used in a name",
+            message
+        );
+
+        let message = error(&|json| json["operators"] = serde_json::json!([]));
+        assert!(message.contains("length 0"), "{message}");
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{Bank, Preset};
+
+/// Why a slot in a bank can't be changed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SlotError {
+    /// The slot is not less than [`Bank::PRESET_COUNT`].
+    OutOfRange { slot: usize },
+
+    /// The bank is full so inserting would push the last preset out.
+    Full,
+}
+
+impl Display for SlotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use SlotError::*;
+        match self {
+            OutOfRange { slot } => write!(
+                f,
+                "Slot {slot} is out of range, a bank has {} slots",
+                Bank::PRESET_COUNT
+            ),
+            Full => write!(f, "Bank is full"),
+        }
+    }
+}
+
+impl std::error::Error for SlotError {}
+
+/// What [`Bank::insert_at`] does when the bank is already full.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Overflow {
+    /// Remove the preset in the last slot and return it.
+    #[default]
+    DropLast,
+
+    /// Leave the bank unchanged and return [`SlotError::Full`].
+    Error,
+}
+
+/// Slot editing. Slots are numbered from 0 and every method checks the slot
+/// against [`Bank::PRESET_COUNT`], not the number of presets. A slot after
+/// the last preset of a bank that isn't full is empty, and is treated as
+/// holding the default preset the same way [`Bank::write`] fills it. Such a
+/// bank grows with default presets up to any slot that is changed.
+impl Bank {
+    /// Put a preset in a slot, returning the preset that was there.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Bank, Preset};
+    /// let mut bank = Bank::load_file("tests/rom1a.syx").unwrap();
+    /// let old = bank.replace(31, Preset::default()).unwrap();
+    /// assert_eq!("TAKE OFF", old.name.to_string());
+    /// assert!(bank.replace(32, old).is_err());
+    /// ```
+    pub fn replace(&mut self, slot: usize, preset: Preset) -> Result<Preset, SlotError> {
+        self.fill_to(slot)?;
+        Ok(std::mem::replace(&mut self.presets[slot], preset))
+    }
+
+    /// Exchange the presets in two slots.
+    pub fn swap(&mut self, a: usize, b: usize) -> Result<(), SlotError> {
+        Self::check_slot(a)?;
+        self.fill_to(b)?;
+        self.fill_to(a)?;
+        self.presets.swap(a, b);
+        Ok(())
+    }
+
+    /// Put a preset in a slot, moving the presets from that slot onwards
+    /// down one slot. If the bank is full the preset in the last slot is
+    /// removed and returned, or an error is returned, depending on
+    /// `overflow`.
+    pub fn insert_at(
+        &mut self,
+        slot: usize,
+        preset: Preset,
+        overflow: Overflow,
+    ) -> Result<Option<Preset>, SlotError> {
+        Self::check_slot(slot)?;
+        let dropped = if self.presets.len() >= Self::PRESET_COUNT {
+            match overflow {
+                Overflow::DropLast => self.presets.pop(),
+                Overflow::Error => return Err(SlotError::Full),
+            }
+        } else {
+            None
+        };
+        if slot > 0 {
+            self.fill_to(slot - 1)?;
+        }
+        self.presets.insert(slot, preset);
+        Ok(dropped)
+    }
+
+    /// Put the INIT VOICE in a slot, returning the preset that was there.
+    pub fn clear(&mut self, slot: usize) -> Result<Preset, SlotError> {
+        self.replace(slot, Preset::init())
+    }
+
+    fn check_slot(slot: usize) -> Result<(), SlotError> {
+        if slot < Self::PRESET_COUNT {
+            Ok(())
+        } else {
+            Err(SlotError::OutOfRange { slot })
+        }
+    }
+
+    /// Add default presets until there is a preset in the slot.
+    fn fill_to(&mut self, slot: usize) -> Result<(), SlotError> {
+        Self::check_slot(slot)?;
+        if self.presets.len() <= slot {
+            self.presets.resize(slot + 1, Preset::default());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    fn rom1a() -> Bank {
+        Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap()
+    }
+
+    #[test]
+    fn replace() {
+        let mut bank = rom1a();
+        let original = bank.clone();
+        let old = bank.replace(0, original[31].clone()).unwrap();
+        assert_eq!(original[0], old);
+        assert_eq!(original[31], bank[0]);
+        let old = bank.replace(31, old).unwrap();
+        assert_eq!(original[31], old);
+        assert_eq!(original[0], bank[31]);
+        assert_eq!(Bank::PRESET_COUNT, bank.len());
+
+        assert_eq!(
+            Err(SlotError::OutOfRange { slot: 32 }),
+            bank.replace(32, Preset::default())
+        );
+        assert_eq!(Bank::PRESET_COUNT, bank.len());
+        assert_eq!(
+            "Slot 32 is out of range, a bank has 32 slots",
+            SlotError::OutOfRange { slot: 32 }.to_string()
+        );
+    }
+
+    #[test]
+    fn swap() {
+        let mut bank = rom1a();
+        let original = bank.clone();
+        bank.swap(0, 31).unwrap();
+        assert_eq!(original[31], bank[0]);
+        assert_eq!(original[0], bank[31]);
+        bank.swap(5, 5).unwrap();
+        assert_eq!(original[5], bank[5]);
+        assert!(bank.swap(0, 32).is_err());
+        assert!(bank.swap(32, 0).is_err());
+        assert_eq!(original[31], bank[0]);
+    }
+
+    #[test]
+    fn insert_at() {
+        let mut bank = rom1a();
+        let original = bank.clone();
+        let mut inserted = Preset::default();
+        inserted.set_name("NEW").unwrap();
+
+        let dropped = bank.insert_at(0, inserted.clone(), Overflow::DropLast);
+        assert_eq!(Ok(Some(original[31].clone())), dropped);
+        assert_eq!(Bank::PRESET_COUNT, bank.len());
+        assert_eq!(inserted, bank[0]);
+        assert_eq!(original[0], bank[1]);
+        assert_eq!(original[30], bank[31]);
+
+        let mut bank = original.clone();
+        bank.insert_at(31, inserted.clone(), Overflow::DropLast)
+            .unwrap();
+        assert_eq!(inserted, bank[31]);
+        assert_eq!(original[30], bank[30]);
+
+        let mut bank = original.clone();
+        assert_eq!(
+            Err(SlotError::Full),
+            bank.insert_at(10, inserted.clone(), Overflow::Error)
+        );
+        assert_eq!(original, bank);
+        assert!(bank
+            .insert_at(32, inserted.clone(), Overflow::DropLast)
+            .is_err());
+        assert_eq!(original, bank);
+    }
+
+    #[test]
+    fn short_bank() {
+        let mut bank = Bank::new(vec![rom1a()[0].clone()]);
+        let mut inserted = Preset::default();
+        inserted.set_name("NEW").unwrap();
+        assert_eq!(
+            Ok(None),
+            bank.insert_at(0, inserted.clone(), Overflow::Error)
+        );
+        assert_eq!(2, bank.len());
+        assert_eq!(inserted, bank[0]);
+
+        // Empty slots before the inserted preset hold the default preset
+        bank.insert_at(4, inserted.clone(), Overflow::Error)
+            .unwrap();
+        assert_eq!(5, bank.len());
+        assert_eq!(Preset::default(), bank[3]);
+        assert_eq!(inserted, bank[4]);
+
+        assert_eq!(Ok(Preset::default()), bank.replace(31, inserted.clone()));
+        assert_eq!(Bank::PRESET_COUNT, bank.len());
+        bank.swap(0, 1).unwrap();
+        assert_eq!("BRASS   1", bank[0].name.to_string());
+    }
+
+    #[test]
+    fn clear() {
+        let mut bank = rom1a();
+        let original = bank.clone();
+        assert_eq!(Ok(original[0].clone()), bank.clear(0));
+        assert_eq!(Ok(original[31].clone()), bank.clear(31));
+        assert_eq!(Preset::init(), bank[0]);
+        assert_eq!(Preset::init(), bank[31]);
+        assert_eq!("INIT VOICE", bank[31].name.to_string());
+        assert!(bank.clear(32).is_err());
+        assert_eq!(Bank::PRESET_COUNT, bank.len());
+    }
+}
@@ -0,0 +1,187 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+
+use crate::{Bank, Preset};
+
+/// A comparison of two presets for [`SortKey::Custom`].
+pub type PresetComparator<'a> = Box<dyn FnMut(&Preset, &Preset) -> Ordering + 'a>;
+
+/// How [`Bank::sort_by`] orders presets.
+pub enum SortKey<'a> {
+    /// By name, ignoring case and spaces, with numbers in order of their
+    /// value so `PIANO 2` comes before `PIANO 10`.
+    Name,
+
+    /// By algorithm number.
+    Algorithm,
+
+    /// By the category from [`Preset::classify`], in the order the
+    /// categories are declared in [`Category`](crate::Category).
+    Category,
+
+    /// By a comparison of two presets.
+    Custom(PresetComparator<'a>),
+}
+
+impl Bank {
+    /// Sort the presets. The sort is stable so presets that are equal keep
+    /// their order, which allows sorting by one key and then another.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Bank, SortKey};
+    /// let mut bank = Bank::load_file("tests/rom1a.syx").unwrap();
+    /// bank.sort_by(SortKey::Custom(Box::new(|a, b| b.feedback_level.cmp(&a.feedback_level))));
+    /// assert_eq!(7, bank[0].feedback_level);
+    /// ```
+    pub fn sort_by(&mut self, key: SortKey) {
+        match key {
+            SortKey::Name => self
+                .presets
+                .sort_by(|a, b| natural_cmp(a.name.as_str(), b.name.as_str())),
+            SortKey::Algorithm => self.presets.sort_by_key(|preset| preset.algorithm_id),
+            // Classifying is slow enough to do once for each preset
+            SortKey::Category => self.presets.sort_by_cached_key(|preset| preset.classify()),
+            SortKey::Custom(mut compare) => self.presets.sort_by(|a, b| compare(a, b)),
+        }
+    }
+
+    /// A copy of the bank with the presets sorted. See [`Bank::sort_by`].
+    pub fn sorted(&self, key: SortKey) -> Bank {
+        let mut sorted = self.clone();
+        sorted.sort_by(key);
+        sorted
+    }
+}
+
+/// Compare names ignoring case and spaces, comparing runs of digits by their
+/// value.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let chars = |name: &str| -> Vec<char> {
+        name.chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    };
+    let (a, b) = (chars(a), chars(b));
+    let mut a = a.into_iter().peekable();
+    let mut b = b.into_iter().peekable();
+    loop {
+        let ordering = match (a.peek(), b.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, y) = (digits(&mut a), digits(&mut b));
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                // Without leading zeros the longer number is larger
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.cmp(y);
+                a.next();
+                b.next();
+                ordering
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Take a run of digits.
+fn digits(chars: &mut Peekable<impl Iterator<Item = char>>) -> String {
+    let mut digits = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        digits.push(digit);
+    }
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    use super::natural_cmp;
+
+    #[test]
+    fn name() {
+        let bank = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let sorted = bank.sorted(SortKey::Name);
+        assert_eq!(bank.len(), sorted.len());
+        assert_eq!("BASS    1", sorted[0].name.to_string());
+        assert_eq!("BASS    2", sorted[1].name.to_string());
+        assert_eq!("VOICE   1", sorted[31].name.to_string());
+        assert_eq!(bank[14], sorted[0]);
+
+        let mut bank = Bank::new(Vec::new());
+        for name in ["PIANO 10", "piano 2", "PIANO   1", "PIANO"] {
+            let mut preset = Preset::default();
+            preset.set_name(name).unwrap();
+            bank.presets.push(preset);
+        }
+        bank.sort_by(SortKey::Name);
+        let names: Vec<String> = bank.iter().map(|preset| preset.name.to_string()).collect();
+        assert_eq!(vec!["PIANO", "PIANO   1", "piano 2", "PIANO 10"], names);
+    }
+
+    #[test]
+    fn natural() {
+        assert_eq!(Ordering::Less, natural_cmp("PIANO 2", "PIANO 10"));
+        assert_eq!(Ordering::Less, natural_cmp("A9Z", "A09Z1"));
+        assert_eq!(Ordering::Equal, natural_cmp("TUB BELLS", "tubbells"));
+        assert_eq!(Ordering::Greater, natural_cmp("B", "A100"));
+        assert_eq!(Ordering::Less, natural_cmp("", "A"));
+    }
+
+    #[test]
+    fn algorithm() {
+        let mut bank = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let original = bank.clone();
+        bank.sort_by(SortKey::Algorithm);
+        assert!(bank
+            .presets
+            .windows(2)
+            .all(|pair| pair[0].algorithm_id <= pair[1].algorithm_id));
+        assert_eq!(2, bank[0].algorithm_id.display());
+        assert_eq!(32, bank[31].algorithm_id.display());
+
+        // Presets with the same algorithm keep their order
+        let fives: Vec<&Preset> = bank
+            .iter()
+            .filter(|preset| preset.algorithm_id.display() == 5)
+            .collect();
+        let original_fives: Vec<&Preset> = original
+            .iter()
+            .filter(|preset| preset.algorithm_id.display() == 5)
+            .collect();
+        assert_eq!(original_fives, fives);
+    }
+
+    #[test]
+    fn category_and_custom() {
+        let bank = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let sorted = bank.sorted(SortKey::Category);
+        assert!(sorted
+            .presets
+            .windows(2)
+            .all(|pair| pair[0].classify() <= pair[1].classify()));
+        assert_eq!(Category::Bass, sorted[0].classify());
+
+        let mut calls = 0;
+        let by_level = bank.sorted(SortKey::Custom(Box::new(|a, b| {
+            calls += 1;
+            a.operators[0]
+                .output_level
+                .cmp(&b.operators[0].output_level)
+        })));
+        assert!(calls > 0);
+        assert!(by_level
+            .presets
+            .windows(2)
+            .all(|pair| pair[0].operators[0].output_level <= pair[1].operators[0].output_level));
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{Algorithms, Bank, OperatorMode, Preset, Waveform};
+
+/// The number of LFO waveforms.
+const WAVEFORM_COUNT: usize = 6;
+
+/// A summary of the presets in a bank, for exploring large collections.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BankStats {
+    pub preset_count: usize,
+
+    /// The number of presets using each algorithm. Algorithm 1 is first.
+    pub algorithms: [usize; Algorithms::COUNT],
+
+    /// The number of presets with at least one operator in fixed mode.
+    pub fixed_mode: usize,
+
+    /// The number of presets with each feedback level from 0 to 7.
+    pub feedback: [usize; 8],
+
+    /// The number of presets using each LFO waveform, in the order of the
+    /// values of [`Waveform`].
+    pub lfo_waveforms: [usize; WAVEFORM_COUNT],
+
+    /// The lowest and highest transpose in semitones, or `None` when there
+    /// are no presets.
+    pub transpose_range: Option<(i8, i8)>,
+
+    /// The number of presets where every carrier dies away while the note is
+    /// held. See [`Preset::is_percussive`].
+    pub percussive: usize,
+
+    /// The number of presets that aren't percussive.
+    pub sustaining: usize,
+
+    /// The average output level of each operator, in the order of
+    /// [`Preset::operators`]. All are 0 when there are no presets.
+    pub average_output_levels: [f64; Preset::OPERATOR_COUNT],
+}
+
+impl BankStats {
+    /// Summarize any number of presets. Presets are normalized first so
+    /// parameters out of range are counted as the values they are written
+    /// as.
+    pub fn from_presets(presets: &[Preset]) -> Self {
+        let mut stats = BankStats {
+            preset_count: presets.len(),
+            algorithms: [0; Algorithms::COUNT],
+            fixed_mode: 0,
+            feedback: [0; 8],
+            lfo_waveforms: [0; WAVEFORM_COUNT],
+            transpose_range: None,
+            percussive: 0,
+            sustaining: 0,
+            average_output_levels: [0.0; Preset::OPERATOR_COUNT],
+        };
+        let mut output_levels = [0_u64; Preset::OPERATOR_COUNT];
+        for preset in presets {
+            let preset = preset.normalize();
+            stats.algorithms[preset.algorithm_id.stored() as usize] += 1;
+            if preset
+                .operators
+                .iter()
+                .any(|operator| operator.mode == OperatorMode::Fixed)
+            {
+                stats.fixed_mode += 1;
+            }
+            stats.feedback[preset.feedback_level as usize] += 1;
+            stats.lfo_waveforms[preset.lfo_waveform as usize] += 1;
+
+            let transpose = preset.transpose_semitones();
+            stats.transpose_range = Some(match stats.transpose_range {
+                Some((lowest, highest)) => (lowest.min(transpose), highest.max(transpose)),
+                None => (transpose, transpose),
+            });
+
+            if preset.is_percussive() {
+                stats.percussive += 1;
+            } else {
+                stats.sustaining += 1;
+            }
+            for (total, operator) in output_levels.iter_mut().zip(&preset.operators) {
+                *total += operator.output_level as u64;
+            }
+        }
+        if !presets.is_empty() {
+            for (average, total) in stats.average_output_levels.iter_mut().zip(output_levels) {
+                *average = total as f64 / presets.len() as f64;
+            }
+        }
+        stats
+    }
+}
+
+/// Counts that aren't zero as `label: count` pairs.
+fn histogram(counts: &[usize], label: impl Fn(usize) -> String) -> String {
+    let used: Vec<String> = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(index, count)| format!("{}: {count}", label(index)))
+        .collect();
+    if used.is_empty() {
+        "none".to_owned()
+    } else {
+        used.join(", ")
+    }
+}
+
+impl Display for BankStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} presets, {} percussive and {} sustaining",
+            self.preset_count, self.percussive, self.sustaining
+        )?;
+        writeln!(
+            f,
+            "Algorithms: {}",
+            histogram(&self.algorithms, |index| (index + 1).to_string())
+        )?;
+        writeln!(f, "Operators in fixed mode: {} presets", self.fixed_mode)?;
+        writeln!(
+            f,
+            "Feedback: {}",
+            histogram(&self.feedback, |level| level.to_string())
+        )?;
+        writeln!(
+            f,
+            "LFO waveforms: {}",
+            histogram(&self.lfo_waveforms, |index| {
+                Waveform::try_from(index as u8)
+                    .map(|waveform| waveform.to_string())
+                    .unwrap_or_default()
+            })
+        )?;
+        match self.transpose_range {
+            Some((lowest, highest)) => {
+                writeln!(f, "Transpose: {lowest:+} to {highest:+} semitones")?
+            }
+            None => writeln!(f, "Transpose: none")?,
+        }
+        let levels: Vec<String> = self
+            .average_output_levels
+            .iter()
+            .enumerate()
+            .map(|(operator, level)| format!("{}: {level:.1}", operator + 1))
+            .collect();
+        write!(f, "Average output levels: {}", levels.join(", "))
+    }
+}
+
+impl Bank {
+    /// A summary of the presets in the bank. See [`BankStats::from_presets`]
+    /// to summarize presets that aren't in a bank.
+    ///
+    /// ```
+    /// use synthahol_dx7::Bank;
+    /// let bank = Bank::load_file("tests/rom1a.syx").unwrap();
+    /// let stats = bank.statistics();
+    /// assert_eq!(32, stats.preset_count);
+    /// println!("{stats}");
+    /// ```
+    pub fn statistics(&self) -> BankStats {
+        BankStats::from_presets(&self.presets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    #[test]
+    fn rom1a() {
+        let bank = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let stats = bank.statistics();
+        assert_eq!(32, stats.preset_count);
+        assert_eq!(5, stats.algorithms[4]);
+        assert_eq!(4, stats.algorithms[1]);
+        assert_eq!(1, stats.algorithms[31]);
+        assert_eq!(0, stats.algorithms[0]);
+        assert_eq!(4, stats.fixed_mode);
+        assert_eq!([3, 1, 1, 0, 1, 5, 3, 18], stats.feedback);
+        assert_eq!([16, 1, 1, 0, 13, 1], stats.lfo_waveforms);
+        assert_eq!(Some((-24, 12)), stats.transpose_range);
+        assert_eq!(16, stats.percussive);
+        assert_eq!(16, stats.sustaining);
+        assert_eq!(94.5, stats.average_output_levels[0]);
+        assert_eq!(32, stats.algorithms.iter().sum::<usize>());
+        assert_eq!(32, stats.feedback.iter().sum::<usize>());
+        assert_eq!(32, stats.lfo_waveforms.iter().sum::<usize>());
+        assert_eq!(32, stats.percussive + stats.sustaining);
+        assert_eq!(stats, BankStats::from_presets(&bank.presets));
+    }
+
+    #[test]
+    fn display() {
+        let bank = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let report = bank.statistics().to_string();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!("32 presets, 16 percussive and 16 sustaining", lines[0]);
+        assert!(lines[1].starts_with("Algorithms: 2: 4, 3: 2, 5: 5, 7: 2"));
+        assert_eq!("Operators in fixed mode: 4 presets", lines[2]);
+        assert_eq!(
+            "LFO waveforms: Triangle: 16, Saw down: 1, Saw up: 1, Sine: 13, Sample and hold: 1",
+            lines[4]
+        );
+        assert_eq!("Transpose: -24 to +12 semitones", lines[5]);
+        assert!(lines[6].starts_with("Average output levels: 1: 94.5, 2: 85.0"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize() {
+        let bank = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let json = serde_json::to_value(bank.statistics()).unwrap();
+        assert_eq!(5, json["algorithms"][4]);
+        assert_eq!(serde_json::json!([-24, 12]), json["transpose_range"]);
+        assert_eq!(16, json["percussive"]);
+    }
+
+    #[test]
+    fn empty() {
+        let stats = BankStats::from_presets(&[]);
+        assert_eq!(0, stats.preset_count);
+        assert_eq!(None, stats.transpose_range);
+        assert_eq!([0.0; 6], stats.average_output_levels);
+        assert!(stats.to_string().contains("Algorithms: none"));
+    }
+}
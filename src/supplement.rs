@@ -0,0 +1,384 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::fractional::FRACTIONAL_SCALING_IDENTIFIER;
+use crate::read::{
+    format_number, packed_chunks, read_exact, read_footer, read_header, read_message,
+    CountingReader,
+};
+use crate::*;
+
+/// Header of a DX7II additional voice bank, also known as AMEM. The low
+/// nibble of the third byte is the MIDI channel.
+pub(crate) const SUPPLEMENT_SYSEX_HEADER: [u8; Format::HEADER_LEN] =
+    [0xF0, 0x43, 0x00, 0x06, 0x08, 0x60];
+
+/// Number of bytes for each voice in an additional voice bank.
+const PACKED_SUPPLEMENT_LEN: usize = 35;
+
+/// Parameters added by the DX7II and DX7s to each voice, also known as
+/// ACED. They are sent in a separate message from the bank of presets they
+/// extend.
+///
+/// The layout follows the DX7II data list. The foot controller 2 and MIDI
+/// controller sensitivities of the DX7II FD are not decoded.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct AcedSupplement {
+    /// Keyboard scaling mode of each operator, in the same order as
+    /// [`Preset::operators`]. 0 is normal and 1 is fractional.
+    pub scaling_modes: [u8; 6],
+
+    /// Amplitude modulation sensitivity of each operator from 0 to 7, in
+    /// the same order as [`Preset::operators`]. This replaces the 0 to 3
+    /// sensitivity of the DX7.
+    pub modulation_sensitivities: [u8; 6],
+
+    /// 0 is 8 octaves, 1 is 2 octaves, 2 is 1 octave and 3 is half an
+    /// octave.
+    pub pitch_envelope_range: u8,
+
+    /// Restart the LFO for every key instead of only the first.
+    pub lfo_multi_trigger: bool,
+
+    /// Scale the pitch envelope by key velocity.
+    pub pitch_envelope_velocity: bool,
+
+    /// 0 is poly and 1 is mono. The DX7s adds 2 for unison poly and 3 for
+    /// unison mono.
+    pub poly_mono: u8,
+
+    pub pitch_bend_range: u8,
+    pub pitch_bend_step: u8,
+    pub pitch_bend_mode: u8,
+    pub random_pitch: u8,
+    pub portamento_mode: u8,
+    pub portamento_step: u8,
+    pub portamento_time: u8,
+    pub mod_wheel_pitch: u8,
+    pub mod_wheel_amplitude: u8,
+    pub foot_pitch: u8,
+    pub foot_amplitude: u8,
+    pub foot_envelope_bias: u8,
+    pub foot_volume: u8,
+    pub breath_pitch: u8,
+    pub breath_amplitude: u8,
+    pub breath_envelope_bias: u8,
+    pub breath_pitch_bias: u8,
+    pub aftertouch_pitch: u8,
+    pub aftertouch_amplitude: u8,
+    pub aftertouch_envelope_bias: u8,
+    pub aftertouch_pitch_bias: u8,
+    pub pitch_envelope_rate_scaling: u8,
+
+    /// Detune between the voices of unison modes, from 0 to 7.
+    pub unison_detune: u8,
+}
+
+impl AcedSupplement {
+    /// Unpack a supplement from the 35 byte layout used by additional voice
+    /// banks.
+    pub fn from_packed(packed: &[u8; PACKED_SUPPLEMENT_LEN]) -> Self {
+        // Operators are stored last-operator-first like in a bank
+        let mut scaling_modes = [0; Preset::OPERATOR_COUNT];
+        let mut modulation_sensitivities = [0; Preset::OPERATOR_COUNT];
+        for (stored_index, operator_index) in (0..Preset::OPERATOR_COUNT).rev().enumerate() {
+            scaling_modes[operator_index] = (packed[0] >> stored_index) & 0b1;
+            let sensitivities = packed[1 + stored_index / 2];
+            modulation_sensitivities[operator_index] =
+                (sensitivities >> (stored_index % 2 * 3)) & 0b111;
+        }
+
+        AcedSupplement {
+            scaling_modes,
+            modulation_sensitivities,
+            pitch_envelope_range: packed[4] & 0b0000011,
+            lfo_multi_trigger: packed[4] & 0b0000100 != 0,
+            pitch_envelope_velocity: packed[4] & 0b0001000 != 0,
+            poly_mono: (packed[4] & 0b1110000) >> 4,
+            pitch_bend_range: packed[5],
+            pitch_bend_step: packed[6],
+            pitch_bend_mode: packed[7] & 0b0000011,
+            random_pitch: (packed[7] & 0b0011100) >> 2,
+            portamento_mode: packed[8],
+            portamento_step: packed[9],
+            portamento_time: packed[10],
+            mod_wheel_pitch: packed[11],
+            mod_wheel_amplitude: packed[12],
+            foot_pitch: packed[13],
+            foot_amplitude: packed[14],
+            foot_envelope_bias: packed[15],
+            foot_volume: packed[16],
+            breath_pitch: packed[17],
+            breath_amplitude: packed[18],
+            breath_envelope_bias: packed[19],
+            breath_pitch_bias: packed[20],
+            aftertouch_pitch: packed[21],
+            aftertouch_amplitude: packed[22],
+            aftertouch_envelope_bias: packed[23],
+            aftertouch_pitch_bias: packed[24],
+            pitch_envelope_rate_scaling: packed[25] & 0b0000111,
+            unison_detune: packed[34] & 0b0000111,
+        }
+    }
+}
+
+/// Banks of DX7II additional voice parameters, also known as AMEM.
+///
+/// The layout follows the DX7II data list. It has only been tested with a
+/// generated bank, not one dumped from a DX7II.
+pub struct Supplement;
+
+impl Supplement {
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Vec<AcedSupplement>, Error> {
+        let input = File::open(&path)?;
+        let mut reader = BufReader::new(input);
+        Self::load(&mut reader)
+    }
+
+    /// Read the supplements for the 32 presets of a bank.
+    pub fn load<R: Read>(reader: &mut R) -> Result<Vec<AcedSupplement>, Error> {
+        // Header, ignoring the MIDI channel
+        read_header(reader, &SUPPLEMENT_SYSEX_HEADER)?;
+
+        // Body
+        let mut body = [0; Bank::PRESET_COUNT * PACKED_SUPPLEMENT_LEN];
+        read_exact(reader, &mut body)?;
+
+        // Body checksum and the end of SysEx marker
+        read_footer(reader, &body)?;
+
+        Ok(packed_chunks(&body)
+            .map(AcedSupplement::from_packed)
+            .collect())
+    }
+}
+
+/// A preset and the DX7II parameters that extend it, if they were found.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SupplementedPreset {
+    pub preset: Preset,
+    pub supplement: Option<AcedSupplement>,
+    pub fractional_scaling: Option<FractionalScaling>,
+}
+
+/// The messages that are combined into a bank of supplemented presets.
+#[derive(Default)]
+struct SupplementedBank {
+    presets: Option<Vec<Preset>>,
+    supplements: Option<Vec<AcedSupplement>>,
+    fractional_scaling: Option<Vec<FractionalScaling>>,
+}
+
+impl Bank {
+    pub fn read_all_with_supplements_file<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<Vec<Vec<SupplementedPreset>>, Error> {
+        let input = File::open(&path)?;
+        let mut reader = BufReader::new(input);
+        Self::read_all_with_supplements(&mut reader)
+    }
+
+    /// Read every bank from a series of SysEx messages along with the DX7II
+    /// additional voice banks and fractional scaling that extend them. These
+    /// are paired with the bank next to them, which may be before or after
+    /// them. Other messages are skipped.
+    pub fn read_all_with_supplements<R: Read>(
+        reader: &mut R,
+    ) -> Result<Vec<Vec<SupplementedPreset>>, Error> {
+        let mut reader = CountingReader::new(reader, 0);
+        let mut banks: Vec<SupplementedBank> = Vec::new();
+        let mut pending = SupplementedBank::default();
+        while let Some((offset, message)) = read_message(&mut reader)? {
+            let mut message_reader = CountingReader::new(message.as_slice(), offset);
+            match format_number(&message) {
+                Some(format) if format == SYSEX_HEADER[3] => {
+                    let dump = Self::read_bank(&mut message_reader, ReadOptions::default())?;
+                    pending.presets = Some(dump.presets);
+                    banks.push(std::mem::take(&mut pending));
+                }
+                Some(format) if format == SUPPLEMENT_SYSEX_HEADER[3] => {
+                    let supplements = Supplement::load(&mut message_reader)
+                        .map_err(|err| message_reader.locate(err, offset))?;
+                    match banks.last_mut() {
+                        Some(bank) if bank.supplements.is_none() && pending.is_empty() => {
+                            bank.supplements = Some(supplements)
+                        }
+                        _ => pending.supplements = Some(supplements),
+                    }
+                }
+                Some(_) if message.get(6..16) == Some(&FRACTIONAL_SCALING_IDENTIFIER) => {
+                    let fractional_scaling = FractionalScalingBank::load(&mut message_reader)
+                        .map_err(|err| message_reader.locate(err, offset))?;
+                    match banks.last_mut() {
+                        Some(bank) if bank.fractional_scaling.is_none() && pending.is_empty() => {
+                            bank.fractional_scaling = Some(fractional_scaling)
+                        }
+                        _ => pending.fractional_scaling = Some(fractional_scaling),
+                    }
+                }
+                _ => log::warn!(
+                    "Skipping {} byte SysEx message that is not a bank at offset {offset:#X}",
+                    message.len()
+                ),
+            }
+        }
+
+        Ok(banks
+            .into_iter()
+            .map(SupplementedBank::into_presets)
+            .collect())
+    }
+}
+
+impl SupplementedBank {
+    fn is_empty(&self) -> bool {
+        self.supplements.is_none() && self.fractional_scaling.is_none()
+    }
+
+    fn into_presets(self) -> Vec<SupplementedPreset> {
+        let mut supplements = self.supplements.into_iter().flatten();
+        let mut fractional_scaling = self.fractional_scaling.into_iter().flatten();
+        self.presets
+            .into_iter()
+            .flatten()
+            .map(|preset| SupplementedPreset {
+                preset,
+                supplement: supplements.next(),
+                fractional_scaling: fractional_scaling.next(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+
+    use super::*;
+
+    #[test]
+    fn supplement_fields() {
+        let mut packed = [0; PACKED_SUPPLEMENT_LEN];
+        packed[0] = 0b000001; // Operator 6 fractional scaling
+        packed[1] = 0b101_011; // Operator 5 and 6 sensitivities
+        packed[3] = 0b111_000; // Operator 1 and 2 sensitivities
+        packed[4] = 0b0101110;
+        packed[5] = 12;
+        packed[7] = 0b0010110;
+        packed[10] = 99;
+        packed[34] = 0b0001101;
+
+        let supplement = AcedSupplement::from_packed(&packed);
+        assert_eq!([0, 0, 0, 0, 0, 1], supplement.scaling_modes);
+        assert_eq!([7, 0, 0, 0, 5, 3], supplement.modulation_sensitivities);
+        assert_eq!(2, supplement.pitch_envelope_range);
+        assert!(supplement.lfo_multi_trigger);
+        assert!(supplement.pitch_envelope_velocity);
+        assert_eq!(2, supplement.poly_mono);
+        assert_eq!(12, supplement.pitch_bend_range);
+        assert_eq!(2, supplement.pitch_bend_mode);
+        assert_eq!(5, supplement.random_pitch);
+        assert_eq!(99, supplement.portamento_time);
+        assert_eq!(5, supplement.unison_detune);
+    }
+
+    #[test]
+    fn read_with_bank() {
+        let path = test_data_path(&["rom1a_amem.syx"]);
+        let banks = Bank::read_all_with_supplements_file(&path).unwrap();
+        assert_eq!(1, banks.len());
+        assert_eq!(32, banks[0].len());
+
+        let SupplementedPreset {
+            preset, supplement, ..
+        } = &banks[0][0];
+        assert_eq!("BRASS   1", preset.name.to_string());
+        let supplement = supplement.unwrap();
+        assert_eq!(1, supplement.pitch_envelope_range);
+        assert_eq!(3, supplement.unison_detune);
+        assert_eq!(2, supplement.poly_mono);
+
+        let SupplementedPreset {
+            preset, supplement, ..
+        } = &banks[0][31];
+        assert_eq!("TAKE OFF", preset.name.to_string());
+        let supplement = supplement.unwrap();
+        assert_eq!(3, supplement.pitch_envelope_range);
+        assert_eq!(7, supplement.unison_detune);
+
+        assert_eq!(AcedSupplement::default(), banks[0][5].supplement.unwrap());
+
+        // The presets are the same as reading the bank alone
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"]))
+            .unwrap()
+            .presets;
+        let read: Vec<_> = banks[0]
+            .iter()
+            .map(|preset| preset.preset.clone())
+            .collect();
+        assert_eq!(presets, read);
+        assert!(banks[0]
+            .iter()
+            .all(|preset| preset.fractional_scaling.is_none()));
+        assert_eq!(
+            vec![Bank::new(presets.clone())],
+            Bank::read_all(&mut std::fs::read(&path).unwrap().as_slice()).unwrap()
+        );
+
+        // Banks without supplements
+        let banks =
+            Bank::read_all_with_supplements_file(test_data_path(&["rom1a_twice.syx"])).unwrap();
+        assert_eq!(2, banks.len());
+        assert!(banks
+            .iter()
+            .flatten()
+            .all(|preset| preset.supplement.is_none()));
+    }
+
+    #[test]
+    fn supplement_after_bank() {
+        let contents = std::fs::read(test_data_path(&["rom1a_amem.syx"])).unwrap();
+        let supplement_len = 6 + 32 * PACKED_SUPPLEMENT_LEN + 2;
+        let (supplement, bank) = contents.split_at(supplement_len);
+        let data = [bank, supplement].concat();
+        let banks = Bank::read_all_with_supplements(&mut data.as_slice()).unwrap();
+        assert_eq!(1, banks.len());
+        assert_eq!(3, banks[0][0].supplement.unwrap().unison_detune);
+
+        let supplements = Supplement::load(&mut &supplement[..]).unwrap();
+        assert_eq!(32, supplements.len());
+        assert_eq!(banks[0][0].supplement, Some(supplements[0]));
+    }
+
+    #[test]
+    fn fractional_scaling() {
+        let path = test_data_path(&["rom1a_fractional.syx"]);
+        let banks = Bank::read_all_with_supplements_file(&path).unwrap();
+        assert_eq!(1, banks.len());
+        let fractional_scaling = banks[0][0].fractional_scaling.unwrap();
+        assert_eq!(0xFC, fractional_scaling.offsets[0][0]);
+        assert_eq!(
+            Some(FractionalScaling::default()),
+            banks[0][31].fractional_scaling
+        );
+        assert!(banks[0].iter().all(|preset| preset.supplement.is_none()));
+
+        // Fractional scaling is skipped when reading banks
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"]))
+            .unwrap()
+            .presets;
+        assert_eq!(
+            vec![Bank::new(presets)],
+            Bank::read_all(&mut std::fs::read(&path).unwrap().as_slice()).unwrap()
+        );
+
+        // Fractional scaling before the bank
+        let contents = std::fs::read(&path).unwrap();
+        let (bank, fractional_scaling) = contents.split_at(4104);
+        let data = [fractional_scaling, bank].concat();
+        let banks = Bank::read_all_with_supplements(&mut data.as_slice()).unwrap();
+        assert_eq!(1, banks.len());
+        assert_eq!(0xA5, banks[0][0].fractional_scaling.unwrap().offsets[5][31]);
+    }
+}
@@ -0,0 +1,599 @@
+//! Presets as plain text tables, like the patch sheets printed in magazines
+//! and manuals.
+//!
+//! ```text
+//! NAME:      "BRASS   1"
+//! ALGORITHM: 22
+//! FEEDBACK:  7
+//! OSC SYNC:  ON
+//! TRANSPOSE: C3
+//! LFO:       WAVE SINE  SPEED 37  DELAY 0  PMD 5  AMD 0  SYNC OFF  PMS 3
+//! PITCH EG:  R 84 95 95 60  L 50 50 50 50
+//!
+//! OP   MODE  COARSE  FINE  DETUNE  LEVEL  R1  R2  R3  R4  L1  L2  L3  L4  ...
+//!  1  RATIO       0     0      +7     98  72  76  99  71  99  88  96   0  ...
+//! ```
+//!
+//! Values are shown the way the DX7 does, so algorithms are numbered from 1,
+//! detune is signed and break points and transpose are notes. Spaces in
+//! labels are written as hyphens, such as `SAW-DOWN`.
+
+use std::fmt::{Display, Formatter};
+
+use crate::{NameError, OperatorId, ParamMeta, Parameter, Preset, PresetName};
+
+/// A column of the operator table, with the parameter for an operator.
+type Column = (&'static str, fn(OperatorId) -> Parameter);
+
+/// The columns of the operator table, in the order they are written.
+const COLUMNS: [Column; 21] = [
+    ("MODE", Parameter::Mode),
+    ("COARSE", Parameter::FrequencyCoarse),
+    ("FINE", Parameter::FrequencyFine),
+    ("DETUNE", Parameter::Detune),
+    ("LEVEL", Parameter::OutputLevel),
+    ("R1", |operator| Parameter::EgRate(operator, 0)),
+    ("R2", |operator| Parameter::EgRate(operator, 1)),
+    ("R3", |operator| Parameter::EgRate(operator, 2)),
+    ("R4", |operator| Parameter::EgRate(operator, 3)),
+    ("L1", |operator| Parameter::EgLevel(operator, 0)),
+    ("L2", |operator| Parameter::EgLevel(operator, 1)),
+    ("L3", |operator| Parameter::EgLevel(operator, 2)),
+    ("L4", |operator| Parameter::EgLevel(operator, 3)),
+    ("BP", Parameter::BreakPoint),
+    ("LD", Parameter::LeftDepth),
+    ("RD", Parameter::RightDepth),
+    ("LC", Parameter::LeftCurve),
+    ("RC", Parameter::RightCurve),
+    ("RS", Parameter::RateScaling),
+    ("AMS", Parameter::ModulationSensitivity),
+    ("VEL", Parameter::VelocitySensitivity),
+];
+
+/// The fields of the LFO line.
+const LFO_FIELDS: [(&str, Parameter); 7] = [
+    ("WAVE", Parameter::LfoWaveform),
+    ("SPEED", Parameter::LfoSpeed),
+    ("DELAY", Parameter::LfoDelay),
+    ("PMD", Parameter::LfoPitchModDepth),
+    ("AMD", Parameter::LfoAmplitudeModDepth),
+    ("SYNC", Parameter::LfoKeySync),
+    ("PMS", Parameter::LfoPitchModSensitivity),
+];
+
+/// Lines with a single value.
+const SINGLE_FIELDS: [(&str, Parameter); 4] = [
+    ("ALGORITHM", Parameter::Algorithm),
+    ("FEEDBACK", Parameter::Feedback),
+    ("OSC SYNC", Parameter::OscillatorKeySync),
+    ("TRANSPOSE", Parameter::Transpose),
+];
+
+/// A value written the way it is in the text.
+fn token(preset: &Preset, parameter: Parameter) -> String {
+    let value = preset.get(parameter);
+    parameter
+        .metadata()
+        .format_value(value)
+        .replace(' ', "-")
+        .to_ascii_uppercase()
+}
+
+/// A problem with a preset in text form.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TextParseError {
+    /// Line the problem was found on, counting from 1. Missing lines are
+    /// reported after the last line.
+    pub line: usize,
+
+    /// The field with the problem. Parameters use the name from
+    /// [`ParamMeta`], such as `"OP3 EG RATE 2"`, and lines use their key,
+    /// such as `"LFO"`.
+    pub field: String,
+
+    pub kind: TextErrorKind,
+}
+
+/// Why a preset could not be read from text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TextErrorKind {
+    /// A line or value is missing.
+    Missing,
+
+    /// A line, column or field name that isn't recognized.
+    Unknown(String),
+
+    /// A value that could not be read.
+    Invalid(String),
+
+    /// A value outside of the range of the parameter, shown with the first
+    /// and last values of the range.
+    OutOfRange {
+        value: String,
+        min: String,
+        max: String,
+    },
+
+    /// The line or value was given more than once.
+    Duplicate,
+
+    Name(NameError),
+}
+
+impl Display for TextParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use TextErrorKind::*;
+        write!(f, "Line {}, {}: ", self.line, self.field)?;
+        match &self.kind {
+            Missing => f.write_str("missing"),
+            Unknown(name) => write!(f, "'{name}' is not recognized"),
+            Invalid(value) => write!(f, "'{value}' is not a valid value"),
+            OutOfRange { value, min, max } => {
+                write!(f, "{value} is not between {min} and {max}")
+            }
+            Duplicate => f.write_str("given more than once"),
+            Name(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TextParseError {}
+
+/// Reads the lines of a preset, keeping track of where errors are.
+struct TextParser {
+    preset: Preset,
+
+    /// Parameters that have been set, by their index in [`Parameter::ALL`].
+    seen: Vec<bool>,
+
+    name_seen: bool,
+    columns: Option<Vec<usize>>,
+}
+
+impl TextParser {
+    fn error(line: usize, field: impl ToString, kind: TextErrorKind) -> TextParseError {
+        TextParseError {
+            line,
+            field: field.to_string(),
+            kind,
+        }
+    }
+
+    fn set(&mut self, line: usize, parameter: Parameter, text: &str) -> Result<(), TextParseError> {
+        let metadata: &ParamMeta = parameter.metadata();
+        let error = |kind| Self::error(line, &metadata.name, kind);
+        let value = metadata
+            .parse_value(text)
+            .ok_or_else(|| error(TextErrorKind::Invalid(text.to_owned())))?;
+        if !(metadata.min as i16..=metadata.max as i16).contains(&value) {
+            return Err(error(TextErrorKind::OutOfRange {
+                value: text.to_owned(),
+                min: metadata.format_value(metadata.min),
+                max: metadata.format_value(metadata.max),
+            }));
+        }
+        let index = Parameter::ALL
+            .iter()
+            .position(|&other| other == parameter)
+            .expect("every parameter is in the list");
+        if std::mem::replace(&mut self.seen[index], true) {
+            return Err(error(TextErrorKind::Duplicate));
+        }
+        self.preset.set_unchecked(parameter, value as u8);
+        Ok(())
+    }
+
+    fn parse_line(&mut self, line: usize, text: &str) -> Result<(), TextParseError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(());
+        }
+        if let Some((key, value)) = text.split_once(':') {
+            let key = key.split_whitespace().collect::<Vec<_>>().join(" ");
+            let key = key.to_ascii_uppercase();
+            return self.parse_field(line, &key, value.trim());
+        }
+
+        let mut tokens = text.split_whitespace();
+        let first = tokens.next().unwrap_or_default();
+        if first.eq_ignore_ascii_case("OP") {
+            return self.parse_header(line, tokens);
+        }
+        let operator = match first.parse::<usize>() {
+            Ok(number @ 1..=6) => (number - 1) as OperatorId,
+            _ => {
+                return Err(Self::error(
+                    line,
+                    "OP",
+                    TextErrorKind::Unknown(first.into()),
+                ))
+            }
+        };
+        let columns = self
+            .columns
+            .clone()
+            .ok_or_else(|| Self::error(line, "OP", TextErrorKind::Missing))?;
+        let mut tokens = tokens.fuse();
+        for column in columns {
+            let parameter = COLUMNS[column].1(operator);
+            match tokens.next() {
+                Some(token) => self.set(line, parameter, token)?,
+                None => {
+                    let field = &parameter.metadata().name;
+                    return Err(Self::error(line, field, TextErrorKind::Missing));
+                }
+            }
+        }
+        match tokens.next() {
+            Some(extra) => Err(Self::error(
+                line,
+                format!("OP{}", operator + 1),
+                TextErrorKind::Unknown(extra.to_owned()),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn parse_header<'a>(
+        &mut self,
+        line: usize,
+        names: impl Iterator<Item = &'a str>,
+    ) -> Result<(), TextParseError> {
+        let mut columns = Vec::new();
+        for name in names {
+            let column = COLUMNS
+                .iter()
+                .position(|(column, _)| column.eq_ignore_ascii_case(name))
+                .ok_or_else(|| Self::error(line, "OP", TextErrorKind::Unknown(name.into())))?;
+            if columns.contains(&column) {
+                return Err(Self::error(line, name, TextErrorKind::Duplicate));
+            }
+            columns.push(column);
+        }
+        self.columns = Some(columns);
+        Ok(())
+    }
+
+    fn parse_field(&mut self, line: usize, key: &str, value: &str) -> Result<(), TextParseError> {
+        if key == "NAME" {
+            if std::mem::replace(&mut self.name_seen, true) {
+                return Err(Self::error(line, key, TextErrorKind::Duplicate));
+            }
+            let name = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            self.preset.name = PresetName::new(name)
+                .map_err(|error| Self::error(line, key, TextErrorKind::Name(error)))?;
+            return Ok(());
+        }
+        if let Some((_, parameter)) = SINGLE_FIELDS.iter().find(|(name, _)| *name == key) {
+            return self.set(line, *parameter, value);
+        }
+
+        let mut tokens = value.split_whitespace();
+        match key {
+            "LFO" => {
+                while let Some(name) = tokens.next() {
+                    let (_, parameter) = LFO_FIELDS
+                        .iter()
+                        .find(|(field, _)| field.eq_ignore_ascii_case(name))
+                        .ok_or_else(|| {
+                            Self::error(line, key, TextErrorKind::Unknown(name.into()))
+                        })?;
+                    let value = tokens.next().ok_or_else(|| {
+                        Self::error(line, &parameter.metadata().name, TextErrorKind::Missing)
+                    })?;
+                    self.set(line, *parameter, value)?;
+                }
+                Ok(())
+            }
+            "PITCH EG" => {
+                for (label, parameter) in [
+                    ("R", Parameter::PitchEgRate as fn(usize) -> _),
+                    ("L", Parameter::PitchEgLevel),
+                ] {
+                    match tokens.next() {
+                        Some(token) if token.eq_ignore_ascii_case(label) => {}
+                        Some(token) => {
+                            return Err(Self::error(
+                                line,
+                                key,
+                                TextErrorKind::Unknown(token.into()),
+                            ))
+                        }
+                        None => return Err(Self::error(line, key, TextErrorKind::Missing)),
+                    }
+                    for segment in 0..4 {
+                        let parameter = parameter(segment);
+                        let value = tokens.next().ok_or_else(|| {
+                            Self::error(line, &parameter.metadata().name, TextErrorKind::Missing)
+                        })?;
+                        self.set(line, parameter, value)?;
+                    }
+                }
+                match tokens.next() {
+                    Some(extra) => {
+                        Err(Self::error(line, key, TextErrorKind::Unknown(extra.into())))
+                    }
+                    None => Ok(()),
+                }
+            }
+            _ => Err(Self::error(line, key, TextErrorKind::Unknown(key.into()))),
+        }
+    }
+}
+
+impl Preset {
+    /// Write the preset as a plain text table with a line for each global
+    /// setting and a row for each operator. See [`Preset::from_text`].
+    pub fn to_text(&self) -> String {
+        let preset = self.normalize();
+        let mut text = String::new();
+        let mut line = |key: &str, value: &str| {
+            text.push_str(&format!("{:<10} {value}\n", format!("{key}:")));
+        };
+        line("NAME", &format!("\"{}\"", preset.name));
+        for (name, parameter) in SINGLE_FIELDS {
+            line(name, &token(&preset, parameter));
+        }
+        let lfo: Vec<String> = LFO_FIELDS
+            .iter()
+            .map(|(name, parameter)| format!("{name} {}", token(&preset, *parameter)))
+            .collect();
+        line("LFO", &lfo.join("  "));
+        let segments = |parameter: fn(usize) -> Parameter| -> String {
+            (0..4)
+                .map(|segment| token(&preset, parameter(segment)))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        line(
+            "PITCH EG",
+            &format!(
+                "R {}  L {}",
+                segments(Parameter::PitchEgRate),
+                segments(Parameter::PitchEgLevel)
+            ),
+        );
+        text.push('\n');
+
+        // Columns are as wide as their widest value
+        let rows: Vec<Vec<String>> = (0..Preset::OPERATOR_COUNT as OperatorId)
+            .map(|operator| {
+                COLUMNS
+                    .iter()
+                    .map(|(_, parameter)| token(&preset, parameter(operator)))
+                    .collect()
+            })
+            .collect();
+        let widths: Vec<usize> = COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(column, (name, _))| {
+                rows.iter()
+                    .map(|row| row[column].len())
+                    .chain([name.len()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        text.push_str("OP");
+        for ((name, _), width) in COLUMNS.iter().zip(&widths) {
+            text.push_str(&format!("  {name:>width$}"));
+        }
+        text.push('\n');
+        for (operator, row) in rows.iter().enumerate() {
+            text.push_str(&format!("{:>2}", operator + 1));
+            for (value, width) in row.iter().zip(&widths) {
+                text.push_str(&format!("  {value:>width$}"));
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Read a preset written by [`Preset::to_text`] or typed in from a
+    /// patch sheet. Differences in whitespace and case are ignored, the
+    /// columns of the operator table can be in any order and the fields of
+    /// the LFO line can be in any order. Every parameter must be given
+    /// exactly once.
+    ///
+    /// ```
+    /// use synthahol_dx7::{Preset, TextErrorKind};
+    /// let preset = Preset::default();
+    /// assert_eq!(preset, Preset::from_text(&preset.to_text()).unwrap());
+    ///
+    /// let text = preset.to_text().replace("FEEDBACK:  0", "FEEDBACK: 9");
+    /// let error = Preset::from_text(&text).unwrap_err();
+    /// assert_eq!(3, error.line);
+    /// assert_eq!("Line 3, FEEDBACK: 9 is not between 0 and 7", error.to_string());
+    /// ```
+    pub fn from_text(text: &str) -> Result<Preset, TextParseError> {
+        let mut parser = TextParser {
+            preset: Preset::init(),
+            seen: vec![false; Parameter::COUNT],
+            name_seen: false,
+            columns: None,
+        };
+        let mut last_line = 0;
+        for (index, line) in text.lines().enumerate() {
+            parser.parse_line(index + 1, line)?;
+            last_line = index + 1;
+        }
+
+        let end = last_line + 1;
+        if !parser.name_seen {
+            return Err(TextParser::error(end, "NAME", TextErrorKind::Missing));
+        }
+        if let Some((parameter, _)) = Parameter::ALL
+            .iter()
+            .zip(&parser.seen)
+            .find(|(parameter, seen)| !**seen && !matches!(parameter, Parameter::NameCharacter(_)))
+        {
+            let field = &parameter.metadata().name;
+            return Err(TextParser::error(end, field, TextErrorKind::Missing));
+        }
+        Ok(parser.preset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    #[test]
+    fn round_trip() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        for preset in presets {
+            let text = preset.to_text();
+            assert_eq!(preset, Preset::from_text(&text).unwrap(), "{text}");
+        }
+    }
+
+    #[test]
+    fn format() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        let text = presets[0].to_text();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!("NAME:      \"BRASS   1\"", lines[0]);
+        assert_eq!("ALGORITHM: 22", lines[1]);
+        assert_eq!("OSC SYNC:  ON", lines[3]);
+        assert_eq!("TRANSPOSE: C3", lines[4]);
+        assert_eq!(
+            "LFO:       WAVE SINE  SPEED 37  DELAY 0  PMD 5  AMD 0  SYNC OFF  PMS 3",
+            lines[5]
+        );
+        assert_eq!("PITCH EG:  R 84 95 95 60  L 50 50 50 50", lines[6]);
+        assert_eq!("", lines[7]);
+        assert!(lines[8].starts_with("OP   MODE  COARSE  FINE  DETUNE  LEVEL"));
+        assert_eq!(15, lines.len());
+    }
+
+    /// A patch sheet typed in by hand with uneven spacing, lowercase and the
+    /// columns in a different order.
+    const SHEET: &str = "
+        name: \"TYPED\"
+        algorithm: 5
+        feedback:  6
+        osc sync:  on
+        transpose: c3
+        lfo:       speed 35 delay 0 pmd 0 amd 0 sync on pms 3 wave triangle
+        pitch eg:  r 99 99 99 99 l 50 50 50 50
+
+        op mode coarse fine detune level vel ams rs r1 r2 r3 r4 l1 l2 l3 l4 bp lc ld rc rd
+        1  ratio 1 0  0 99 0 0 0  95 29 20 50  99 95 0 0  a-1 -lin 0 -lin 0
+        2  ratio 14 0 0 58 0 0 0  95 20 20 50  99 95 0 0  a-1 -lin 0 -lin 0
+        3  ratio 1 0  0 99 0 0 0  95 29 20 50  99 95 0 0  a-1 -lin 0 -lin 0
+        4  ratio 1 0  +7 89 0 0 0 95 29 20 50  99 95 0 0  a-1 -lin 0 -lin 0
+        5  ratio 1 0  -7 99 2 0 0 95 29 20 50  99 95 0 0  c3 +exp 10 -exp 20
+        6  fixed 1 0  0 79 0 0 0  95 29 20 50  99 95 0 0  a-1 -lin 0 -lin 0
+    ";
+
+    #[test]
+    fn typed_sheet() {
+        let preset = Preset::from_text(SHEET).unwrap();
+        assert_eq!("TYPED", preset.name.to_string());
+        assert_eq!(5, preset.algorithm_id.display());
+        assert_eq!(6, preset.feedback_level);
+        assert_eq!(Waveform::Triangle, preset.lfo_waveform);
+        assert_eq!(0, preset.transpose_semitones());
+        assert_eq!(14, preset.operators[1].frequency_course);
+        assert_eq!(7, preset.operators[3].detune.value());
+        assert_eq!(-7, preset.operators[4].detune.value());
+        assert_eq!("C3", preset.operators[4].break_point_name());
+        assert_eq!(
+            ScalingCurve::PositiveExponential,
+            preset.operators[4].scaling_left_curve
+        );
+        assert_eq!(20, preset.operators[4].scaling_right_depth);
+        assert_eq!(OperatorMode::Fixed, preset.operators[5].mode);
+        assert!(preset.validate().is_empty());
+
+        // Reading what was written gives the same preset
+        assert_eq!(preset, Preset::from_text(&preset.to_text()).unwrap());
+    }
+
+    #[test]
+    fn typo() {
+        let sheet = SHEET.replace(
+            "3  ratio 1 0  0 99 0 0 0  95 29",
+            "3  ratio 1 0  0 99 0 0 0  95 120",
+        );
+        let error = Preset::from_text(&sheet).unwrap_err();
+        assert_eq!(
+            TextParseError {
+                line: 13,
+                field: "OP3 EG RATE 2".to_owned(),
+                kind: TextErrorKind::OutOfRange {
+                    value: "120".to_owned(),
+                    min: "0".to_owned(),
+                    max: "99".to_owned()
+                }
+            },
+            error
+        );
+        assert_eq!(
+            "Line 13, OP3 EG RATE 2: 120 is not between 0 and 99",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let error = |from: &str, to: &str| {
+            assert!(SHEET.contains(from), "{from}");
+            Preset::from_text(&SHEET.replace(from, to))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            "Line 7, LFO WAVEFORM: 'wobble' is not a valid value",
+            error("wave triangle", "wave wobble")
+        );
+        assert_eq!(
+            "Line 6, TRANSPOSE: C6 is not between C1 and C5",
+            error("transpose: c3", "transpose: C6")
+        );
+        assert_eq!(
+            "Line 14, OP4 OSC DETUNE: +8 is not between -7 and +7",
+            error("+7", "+8")
+        );
+        assert_eq!(
+            "Line 3, ALGORITHM #: 33 is not between 1 and 32",
+            error("algorithm: 5", "algorithm: 33")
+        );
+        assert_eq!(
+            "Line 7, LFO: 'rate' is not recognized",
+            error("speed", "rate")
+        );
+        assert_eq!(
+            "Line 10, OP: 'q' is not recognized",
+            error("lc ld", "lc q ld")
+        );
+        assert_eq!(
+            "Line 16, OP6 KBD LEV SCL RHT CURVE: missing",
+            error(
+                "fixed 1 0  0 79 0 0 0  95 29 20 50  99 95 0 0  a-1 -lin 0 -lin 0",
+                "fixed 1 0  0 79 0 0 0  95 29 20 50  99 95 0 0  a-1 -lin 0"
+            )
+        );
+        assert_eq!(
+            "Line 2, NAME: Name is 13 characters, more than 10",
+            error("\"TYPED\"", "\"MUCH TOO LONG\"")
+        );
+        assert_eq!(
+            "Line 5, FEEDBACK: given more than once",
+            error("osc sync", "feedback: 6\nosc sync")
+        );
+
+        // Missing values are reported after the last line
+        let without_op6: Vec<&str> = SHEET
+            .lines()
+            .filter(|line| !line.contains("fixed"))
+            .collect();
+        let missing = Preset::from_text(&without_op6.join("\n")).unwrap_err();
+        assert_eq!("Line 17, OP6 EG RATE 1: missing", missing.to_string());
+    }
+}
@@ -0,0 +1,212 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::read::{byte_count, read_exact, read_footer, read_header};
+use crate::*;
+
+/// Header of a DX7II full keyboard micro tuning. The low nibble of the
+/// third byte is the MIDI channel.
+const KEYBOARD_TUNING_SYSEX_HEADER: [u8; Format::HEADER_LEN] = [0xF0, 0x43, 0x00, 0x7E, 0x02, 0x0A];
+
+/// Header of a DX7II octave micro tuning.
+const OCTAVE_TUNING_SYSEX_HEADER: [u8; Format::HEADER_LEN] = [0xF0, 0x43, 0x00, 0x7E, 0x00, 0x22];
+
+/// Identifies the type of data at the start of the body of messages with
+/// the universal bulk dump format number.
+pub(crate) const KEYBOARD_TUNING_IDENTIFIER: [u8; 10] = *b"LM  MCRYE0";
+pub(crate) const OCTAVE_TUNING_IDENTIFIER: [u8; 10] = *b"LM  MCRTE0";
+
+/// Number of fine tuning steps in a semitone.
+const FINE_STEPS: f32 = 85.0;
+
+/// Number of keys in an octave.
+const OCTAVE_LEN: usize = 12;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum MicroTuningKind {
+    /// Every key is tuned separately.
+    #[default]
+    Keyboard,
+
+    /// The twelve keys of an octave are tuned and repeated across the
+    /// keyboard.
+    Octave,
+}
+
+impl Display for MicroTuningKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use MicroTuningKind::*;
+        let txt = match self {
+            Keyboard => "Keyboard",
+            Octave => "Octave",
+        };
+        f.write_str(txt)
+    }
+}
+
+/// A DX7II micro tuning, which sets the pitch of each key.
+///
+/// Yamaha stores the pitch of each key as two bytes, a key number and a
+/// fine tuning from 0 to 84 in steps of 1/85 of a semitone. They are
+/// converted to cents. The identifiers follow the DX7II data list as
+/// described by patch librarians. No dump from a DX7II was available to
+/// verify them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MicroTuning {
+    pub kind: MicroTuningKind,
+
+    /// Offset of each MIDI note from equal temperament in cents.
+    pub offsets: [f32; MicroTuning::NOTE_COUNT],
+}
+
+impl MicroTuning {
+    /// Number of MIDI notes.
+    pub const NOTE_COUNT: usize = 128;
+
+    /// Offset of a MIDI note from equal temperament in cents. Notes above
+    /// 127 use the tuning of note 127.
+    pub fn tuning_for_note(&self, note: u8) -> f32 {
+        self.offsets[(note as usize).min(Self::NOTE_COUNT - 1)]
+    }
+
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<MicroTuning, Error> {
+        let input = File::open(&path)?;
+        let mut reader = BufReader::new(input);
+        Self::load(&mut reader)
+    }
+
+    /// Read a full keyboard or octave micro tuning. They are told apart by
+    /// the byte count in the header.
+    pub fn load<R: Read>(reader: &mut R) -> Result<MicroTuning, Error> {
+        // Octave tunings are identified by their byte count. Anything else
+        // is checked against the header of a keyboard tuning.
+        let mut header = [0; Format::HEADER_LEN];
+        read_exact(reader, &mut header)?;
+        let (kind, expected_header, identifier) = if header[4..] == OCTAVE_TUNING_SYSEX_HEADER[4..]
+        {
+            (
+                MicroTuningKind::Octave,
+                &OCTAVE_TUNING_SYSEX_HEADER,
+                OCTAVE_TUNING_IDENTIFIER,
+            )
+        } else {
+            (
+                MicroTuningKind::Keyboard,
+                &KEYBOARD_TUNING_SYSEX_HEADER,
+                KEYBOARD_TUNING_IDENTIFIER,
+            )
+        };
+        read_header(&mut header.as_slice(), expected_header)?;
+
+        // Body, starting with the identifier of the type of data
+        let mut body = vec![0; byte_count(expected_header)];
+        read_exact(reader, &mut body)?;
+        let (found, tunings) = body.split_at(identifier.len());
+        if found != identifier {
+            return Err(Error::IncorrectIdentifier {
+                found: found.to_vec(),
+            });
+        }
+
+        // Body checksum and the end of SysEx marker
+        read_footer(reader, &body)?;
+
+        let mut offsets = [0.0; Self::NOTE_COUNT];
+        for (note, offset) in offsets.iter_mut().enumerate() {
+            let key = match kind {
+                MicroTuningKind::Keyboard => note,
+                MicroTuningKind::Octave => note % OCTAVE_LEN,
+            };
+            let (tuned_key, fine) = (tunings[key * 2], tunings[key * 2 + 1]);
+            let cents = tuned_key as f32 * 100.0 + fine.min(84) as f32 * 100.0 / FINE_STEPS;
+            *offset = cents - key as f32 * 100.0;
+        }
+        Ok(MicroTuning { kind, offsets })
+    }
+}
+
+/// Equal temperament across the full keyboard.
+impl Default for MicroTuning {
+    fn default() -> Self {
+        MicroTuning {
+            kind: MicroTuningKind::Keyboard,
+            offsets: [0.0; Self::NOTE_COUNT],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+
+    use super::*;
+
+    fn assert_cents(expected: f32, found: f32) {
+        assert!(
+            (expected - found).abs() < 0.1,
+            "Expected {expected} cents, found {found}"
+        );
+    }
+
+    #[test]
+    fn just_intonation() {
+        let tuning = MicroTuning::load_file(test_data_path(&["just_intonation.syx"])).unwrap();
+        assert_eq!(MicroTuningKind::Keyboard, tuning.kind);
+
+        // The just ratios are rounded to the nearest 1/85 of a semitone
+        assert_cents(0.0, tuning.tuning_for_note(60));
+        assert_cents(-14.12, tuning.tuning_for_note(64)); // 5/4
+        assert_cents(2.35, tuning.tuning_for_note(67)); // 3/2
+        assert_cents(-15.29, tuning.tuning_for_note(69)); // 5/3
+        assert_cents(-11.76, tuning.tuning_for_note(71)); // 15/8
+        assert_cents(-11.76, tuning.tuning_for_note(11));
+        assert_cents(2.35, tuning.tuning_for_note(127));
+    }
+
+    #[test]
+    fn octave() {
+        let mut body = OCTAVE_TUNING_IDENTIFIER.to_vec();
+        for key in 0..OCTAVE_LEN as u8 {
+            body.extend_from_slice(&[key, 0]);
+        }
+        // A quarter tone sharp
+        body[10 + 7 * 2 + 1] = 42;
+
+        let mut data = OCTAVE_TUNING_SYSEX_HEADER.to_vec();
+        data.extend_from_slice(&body);
+        data.push(checksum(&body));
+        data.push(0xF7);
+
+        let tuning = MicroTuning::load(&mut data.as_slice()).unwrap();
+        assert_eq!(MicroTuningKind::Octave, tuning.kind);
+        assert_cents(0.0, tuning.tuning_for_note(60));
+        assert_cents(49.41, tuning.tuning_for_note(7));
+        assert_cents(49.41, tuning.tuning_for_note(67));
+    }
+
+    #[test]
+    fn default() {
+        let tuning = MicroTuning::default();
+        assert!((0..=127).all(|note| tuning.tuning_for_note(note) == 0.0));
+    }
+
+    #[test]
+    fn malformed() {
+        let path = test_data_path(&["just_intonation.syx"]);
+        let contents = std::fs::read(path).unwrap();
+
+        let mut data = contents.clone();
+        data[contents.len() - 2] ^= 1;
+        let error = MicroTuning::load(&mut data.as_slice()).unwrap_err();
+        assert!(matches!(error, Error::ChecksumMismatch { .. }));
+
+        let error = MicroTuning::load_file(test_data_path(&["rom1a.syx"])).unwrap_err();
+        assert!(matches!(error, Error::IncorrectHeader { .. }));
+
+        let error =
+            MicroTuning::load_file(test_data_path(&["dx7ii_performances.syx"])).unwrap_err();
+        assert!(matches!(error, Error::IncorrectByteCount { .. }));
+    }
+}
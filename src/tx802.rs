@@ -0,0 +1,158 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::read::{read_exact, read_footer, read_header};
+use crate::*;
+
+/// Header of a TX802 performance edit buffer dump, also known as PCED. The
+/// low nibble of the third byte is the MIDI channel.
+const TX802_PERFORMANCE_SYSEX_HEADER: [u8; Format::HEADER_LEN] =
+    [0xF0, 0x43, 0x00, 0x7E, 0x00, 0x5E];
+
+/// Identifies the type of data at the start of the body of messages with
+/// the universal bulk dump format number.
+pub(crate) const TX802_PERFORMANCE_IDENTIFIER: [u8; 10] = *b"LM  8952PE";
+
+/// Number of bytes in a performance after the identifier.
+const TX802_PERFORMANCE_LEN: usize = 84;
+
+/// Number of tone generators in a TX802.
+const PART_COUNT: usize = 8;
+
+/// One of the eight tone generators of a TX802 performance.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Tx802Part {
+    /// Voice played by the part, from 0 to 127. Voices 0 to 63 are in
+    /// internal memory and 64 to 127 are on the cartridge.
+    pub voice_number: u8,
+
+    /// MIDI receive channel from 0 to 15, or 16 for omni.
+    pub channel: u8,
+
+    /// Detune from -7 to 7.
+    pub detune: i8,
+
+    /// Output volume from 0 to 99.
+    pub volume: u8,
+
+    /// 0 is off, 1 is output I, 2 is output II and 3 is both.
+    pub output_assign: u8,
+
+    /// Lowest and highest keys the part plays.
+    pub note_limits: [u8; 2],
+
+    /// Note shift from 0 to 48 where 24 is no shift.
+    pub note_shift: u8,
+}
+
+/// A TX802 performance, which combines up to eight voices.
+///
+/// The voices of a TX802 are sent as a bank and an additional voice bank
+/// like the DX7II and are read with [`Bank`] and [`Supplement`].
+///
+/// The layout follows the TX802 performance parameters, where each
+/// parameter is stored for all eight parts before the next parameter. No
+/// dump from a TX802 was available to verify it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Tx802Performance {
+    pub name: String,
+    pub parts: [Tx802Part; PART_COUNT],
+}
+
+impl Tx802Performance {
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Tx802Performance, Error> {
+        let input = File::open(&path)?;
+        let mut reader = BufReader::new(input);
+        Self::load(&mut reader)
+    }
+
+    pub fn load<R: Read>(reader: &mut R) -> Result<Tx802Performance, Error> {
+        // Header, ignoring the MIDI channel
+        read_header(reader, &TX802_PERFORMANCE_SYSEX_HEADER)?;
+
+        // Body, starting with the identifier of the type of data
+        let mut body = [0; TX802_PERFORMANCE_IDENTIFIER.len() + TX802_PERFORMANCE_LEN];
+        read_exact(reader, &mut body)?;
+        let (identifier, data) = body.split_at(TX802_PERFORMANCE_IDENTIFIER.len());
+        if identifier != TX802_PERFORMANCE_IDENTIFIER {
+            return Err(Error::IncorrectIdentifier {
+                found: identifier.to_vec(),
+            });
+        }
+
+        // Body checksum and the end of SysEx marker
+        read_footer(reader, &body)?;
+
+        let mut parts = [Tx802Part::default(); PART_COUNT];
+        for (index, part) in parts.iter_mut().enumerate() {
+            let parameter = |number: usize| data[number * PART_COUNT + index];
+            *part = Tx802Part {
+                channel: parameter(0).min(16),
+                voice_number: parameter(1),
+                // -7 to 7 stored as 0-14
+                detune: parameter(2).min(14) as i8 - 7,
+                volume: parameter(3),
+                output_assign: parameter(4) & 0b11,
+                note_limits: [parameter(5), parameter(6)],
+                note_shift: parameter(7),
+            };
+        }
+
+        Ok(Tx802Performance {
+            name: lossy_ascii(&data[64..84]),
+            parts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+
+    use super::*;
+
+    #[test]
+    fn voices() {
+        let path = test_data_path(&["tx802_bank.syx"]);
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"]))
+            .unwrap()
+            .presets;
+
+        let banks = Bank::read_all_with_supplements_file(&path).unwrap();
+        assert_eq!(1, banks.len());
+        let read: Vec<_> = banks[0]
+            .iter()
+            .map(|preset| preset.preset.clone())
+            .collect();
+        assert_eq!(presets, read);
+        assert!(banks[0].iter().all(|preset| preset.supplement.is_some()));
+
+        assert_eq!(
+            vec![Bank::new(presets)],
+            Bank::read_all(&mut std::fs::read(&path).unwrap().as_slice()).unwrap()
+        );
+    }
+
+    #[test]
+    fn performance() {
+        let contents = std::fs::read(test_data_path(&["tx802_bank.syx"])).unwrap();
+        let performance_len = 6 + 10 + TX802_PERFORMANCE_LEN + 2;
+        let mut performance = &contents[contents.len() - performance_len..];
+        let performance = Tx802Performance::load(&mut performance).unwrap();
+        assert_eq!("ENSEMBLE", performance.name);
+
+        let part = performance.parts[0];
+        assert_eq!(0, part.voice_number);
+        assert_eq!(0, part.channel);
+        assert_eq!(-3, part.detune);
+        assert_eq!(1, part.output_assign);
+
+        let part = performance.parts[7];
+        assert_eq!(70, part.voice_number);
+        assert_eq!(7, part.channel);
+        assert_eq!(4, part.detune);
+        assert_eq!(3, part.output_assign);
+        assert_eq!([36, 96], part.note_limits);
+    }
+}
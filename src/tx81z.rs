@@ -0,0 +1,530 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::read::{packed_chunks, read_exact, read_footer, read_header};
+use crate::*;
+
+/// Header of a TX81Z or DX11 bank, also known as VMEM. The low nibble of
+/// the third byte is the MIDI channel.
+const TX81Z_SYSEX_HEADER: [u8; Format::HEADER_LEN] = [0xF0, 0x43, 0x00, 0x04, 0x20, 0x00];
+
+/// Number of bytes for each voice.
+const PACKED_VOICE_LEN: usize = 128;
+
+/// Number of operators in a TX81Z voice.
+const TX81Z_OPERATOR_COUNT: usize = 4;
+
+/// Order the operators are stored in, counting from 0.
+const STORED_OPERATOR_ORDER: [usize; TX81Z_OPERATOR_COUNT] = [3, 1, 2, 0];
+
+/// Frequency ratio of each coarse frequency of an operator with a sine
+/// wave.
+#[allow(clippy::approx_constant)] // The ratios as published, not constants
+const FREQUENCY_RATIOS: [f32; 64] = [
+    0.50, 0.71, 0.78, 0.87, 1.00, 1.41, 1.57, 1.73, 2.00, 2.82, 3.00, 3.14, 3.46, 4.00, 4.24, 4.71,
+    5.00, 5.19, 5.65, 6.00, 6.28, 6.92, 7.00, 7.07, 7.85, 8.00, 8.48, 8.65, 9.00, 9.42, 9.89,
+    10.00, 10.38, 10.99, 11.00, 11.30, 12.00, 12.11, 12.56, 12.72, 13.00, 13.84, 14.00, 14.10,
+    14.13, 15.00, 15.55, 15.57, 15.70, 16.96, 17.27, 17.30, 18.37, 18.84, 19.03, 19.78, 20.41,
+    20.76, 21.20, 21.98, 22.49, 23.55, 24.22, 25.95,
+];
+
+/// An operator of a TX81Z or DX11 voice.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Tx81zOperator {
+    /// Attack rate from 0 to 31.
+    pub attack_rate: u8,
+
+    /// Decay 1 rate from 0 to 31.
+    pub decay_1_rate: u8,
+
+    /// Decay 2 rate from 0 to 31. 0 holds the decay 1 level.
+    pub decay_2_rate: u8,
+
+    /// Release rate from 1 to 15.
+    pub release_rate: u8,
+
+    /// Decay 1 level from 0 to 15.
+    pub decay_1_level: u8,
+
+    /// Level scaling from 0 to 99.
+    pub level_scaling: u8,
+
+    pub amplitude_mod_enable: bool,
+
+    /// EG bias sensitivity from 0 to 7.
+    pub eg_bias_sensitivity: u8,
+
+    /// Key velocity sensitivity from 0 to 7.
+    pub velocity_sensitivity: u8,
+
+    /// Output level from 0 to 99.
+    pub output_level: u8,
+
+    /// Index of the frequency ratio from 0 to 63, or the fixed frequency.
+    pub frequency_coarse: u8,
+
+    /// Rate scaling from 0 to 3.
+    pub rate_scaling: u8,
+
+    /// Detune from 0 to 6 where 3 is no detune.
+    pub detune: u8,
+
+    pub fixed_frequency: bool,
+
+    /// Range of the fixed frequency from 0 to 7.
+    pub fixed_range: u8,
+
+    /// Waveform from 0 to 7, where 0 is a sine wave.
+    pub waveform: u8,
+
+    /// Fine frequency from 0 to 15.
+    pub frequency_fine: u8,
+
+    /// EG shift from 0 to 3, where 0 is off.
+    pub eg_shift: u8,
+}
+
+/// A voice of the four operator TX81Z or DX11.
+///
+/// The layout follows the TX81Z voice data as described by patch
+/// librarians, with the additional parameters of the TX81Z stored in the
+/// unused bytes of the DX21 layout. No dump from a TX81Z or DX11 was
+/// available to verify it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tx81zVoice {
+    pub name: PresetName,
+
+    /// Operators 1 to 4.
+    pub operators: [Tx81zOperator; TX81Z_OPERATOR_COUNT],
+
+    /// Algorithm from 0 to 7.
+    pub algorithm: u8,
+
+    pub feedback_level: u8,
+    pub lfo_key_sync: bool,
+    pub lfo_speed: u8,
+    pub lfo_delay: u8,
+    pub lfo_pitch_mod_depth: u8,
+    pub lfo_amplitude_mod_depth: u8,
+    pub lfo_pitch_mod_sensitivity: u8,
+
+    /// Amplitude modulation sensitivity from 0 to 3, used by operators
+    /// with amplitude modulation enabled.
+    pub lfo_amplitude_mod_sensitivity: u8,
+
+    pub lfo_waveform: Waveform,
+    pub transpose: u8,
+}
+
+impl Tx81zVoice {
+    /// Unpack a voice from the 128 byte layout used by banks.
+    pub fn from_packed(packed: &[u8; PACKED_VOICE_LEN]) -> Self {
+        let mut operators = [Tx81zOperator::default(); TX81Z_OPERATOR_COUNT];
+        for (stored_index, &operator_index) in STORED_OPERATOR_ORDER.iter().enumerate() {
+            let packed_operator = &packed[stored_index * 10..(stored_index + 1) * 10];
+            let additional = &packed[73 + stored_index * 2..73 + (stored_index + 1) * 2];
+            operators[operator_index] = Tx81zOperator {
+                attack_rate: packed_operator[0] & 0b0011111,
+                decay_1_rate: packed_operator[1] & 0b0011111,
+                decay_2_rate: packed_operator[2] & 0b0011111,
+                release_rate: packed_operator[3] & 0b0001111,
+                decay_1_level: packed_operator[4] & 0b0001111,
+                level_scaling: packed_operator[5],
+                amplitude_mod_enable: packed_operator[6] & 0b1000000 != 0,
+                eg_bias_sensitivity: (packed_operator[6] & 0b0111000) >> 3,
+                velocity_sensitivity: packed_operator[6] & 0b0000111,
+                output_level: packed_operator[7],
+                frequency_coarse: packed_operator[8] & 0b0111111,
+                rate_scaling: (packed_operator[9] & 0b0011000) >> 3,
+                detune: packed_operator[9] & 0b0000111,
+                fixed_frequency: additional[0] & 0b0001000 != 0,
+                fixed_range: additional[0] & 0b0000111,
+                eg_shift: (additional[0] & 0b0110000) >> 4,
+                waveform: (additional[1] & 0b1110000) >> 4,
+                frequency_fine: additional[1] & 0b0001111,
+            };
+        }
+
+        let lfo_waveform = match packed[45] & 0b0000011 {
+            0 => Waveform::SawUp,
+            1 => Waveform::Square,
+            2 => Waveform::Triangle,
+            _ => Waveform::SampleAndHold,
+        };
+
+        Tx81zVoice {
+            name: PresetName::from_lossy(&packed[57..67]),
+            operators,
+            algorithm: packed[40] & 0b0000111,
+            feedback_level: (packed[40] & 0b0111000) >> 3,
+            lfo_key_sync: packed[40] & 0b1000000 != 0,
+            lfo_speed: packed[41],
+            lfo_delay: packed[42],
+            lfo_pitch_mod_depth: packed[43],
+            lfo_amplitude_mod_depth: packed[44],
+            lfo_pitch_mod_sensitivity: (packed[45] & 0b1110000) >> 4,
+            lfo_amplitude_mod_sensitivity: (packed[45] & 0b0001100) >> 2,
+            lfo_waveform,
+            transpose: packed[46],
+        }
+    }
+}
+
+/// A parameter of a TX81Z voice that could not be converted exactly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConversionNote {
+    /// The five stage envelopes were approximated with four rates and
+    /// levels.
+    Envelopes,
+
+    /// A waveform other than a sine wave was replaced with a sine wave.
+    Waveform { operator: usize, waveform: u8 },
+
+    /// The frequency ratio was replaced with the nearest DX7 ratio.
+    FrequencyRatio {
+        operator: usize,
+        ratio: f32,
+        approximation: f32,
+    },
+
+    /// The fine frequency was ignored.
+    FrequencyFine { operator: usize, fine: u8 },
+
+    /// The fixed frequency was replaced with the lowest frequency of the
+    /// DX7 range.
+    FixedFrequency { operator: usize },
+
+    /// Level scaling was approximated with a linear curve above the
+    /// lowest key.
+    LevelScaling { operator: usize },
+
+    /// The DX7 has no EG bias sensitivity for each operator.
+    EgBiasSensitivity { operator: usize },
+
+    /// The DX7 has no EG shift.
+    EgShift { operator: usize, shift: u8 },
+}
+
+impl Display for ConversionNote {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use ConversionNote::*;
+        // Operators are numbered from 1 like on the panel
+        match *self {
+            Envelopes => f.write_str("Envelopes approximated with four rates and levels"),
+            Waveform { operator, waveform } => write!(
+                f,
+                "Operator {} waveform W{} replaced with a sine wave",
+                operator + 1,
+                waveform + 1
+            ),
+            FrequencyRatio {
+                operator,
+                ratio,
+                approximation,
+            } => write!(
+                f,
+                "Operator {} frequency ratio {ratio:.2} approximated as {approximation:.2}",
+                operator + 1
+            ),
+            FrequencyFine { operator, fine } => {
+                write!(f, "Operator {} fine frequency {fine} ignored", operator + 1)
+            }
+            FixedFrequency { operator } => {
+                write!(f, "Operator {} fixed frequency approximated", operator + 1)
+            }
+            LevelScaling { operator } => {
+                write!(f, "Operator {} level scaling approximated", operator + 1)
+            }
+            EgBiasSensitivity { operator } => {
+                write!(f, "Operator {} EG bias sensitivity ignored", operator + 1)
+            }
+            EgShift { operator, shift } => {
+                write!(f, "Operator {} EG shift {shift} ignored", operator + 1)
+            }
+        }
+    }
+}
+
+/// Banks of TX81Z or DX11 voices.
+///
+/// Only a generated bank has been read so far. No bank dumped from a TX81Z
+/// or DX11 was available to confirm the layout.
+pub struct Tx81zBank;
+
+impl Tx81zBank {
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Vec<Tx81zVoice>, Error> {
+        let input = File::open(&path)?;
+        let mut reader = BufReader::new(input);
+        Self::load(&mut reader)
+    }
+
+    pub fn load<R: Read>(reader: &mut R) -> Result<Vec<Tx81zVoice>, Error> {
+        // Header, ignoring the MIDI channel
+        read_header(reader, &TX81Z_SYSEX_HEADER)?;
+
+        // Body
+        let mut body = [0; Bank::PRESET_COUNT * PACKED_VOICE_LEN];
+        read_exact(reader, &mut body)?;
+
+        // Body checksum and the end of SysEx marker
+        read_footer(reader, &body)?;
+
+        Ok(packed_chunks(&body).map(Tx81zVoice::from_packed).collect())
+    }
+
+    /// Convert a voice to a DX7 preset so it can be auditioned, along with
+    /// every parameter that could not be converted exactly.
+    ///
+    /// The operators become operators 3 to 6 with the same DX7 algorithms
+    /// as [`Dx9Voice::ALGORITHMS`], and operators 1 and 2 are muted. Output
+    /// levels, detune, velocity sensitivity and the LFO are copied. The
+    /// attack, decay 1, decay 2 and release rates become the four DX7
+    /// rates, and the decay 1 level becomes the second and third levels.
+    /// Waveforms other than a sine wave are replaced with a sine wave.
+    pub fn convert_to_dx7(voice: &Tx81zVoice) -> (Preset, Vec<ConversionNote>) {
+        let mut notes = vec![ConversionNote::Envelopes];
+        let mut operators = [Operator::default(); Preset::OPERATOR_COUNT];
+        let first_operator = Preset::OPERATOR_COUNT - TX81Z_OPERATOR_COUNT;
+        for (index, tx81z_operator) in voice.operators.iter().enumerate() {
+            operators[first_operator + index] =
+                convert_operator(index, tx81z_operator, voice, &mut notes);
+        }
+
+        let preset = Preset {
+            name: voice.name.clone(),
+            operators,
+            pitch_envelope: Preset::default().pitch_envelope,
+            algorithm_id: Dx9Voice::ALGORITHMS[voice.algorithm as usize & 0b111],
+            oscillator_key_sync: true,
+            feedback_level: voice.feedback_level,
+            lfo_speed: voice.lfo_speed.min(99),
+            lfo_delay: voice.lfo_delay.min(99),
+            lfo_pitch_mod_depth: voice.lfo_pitch_mod_depth.min(99),
+            lfo_pitch_mod_sensitivity: voice.lfo_pitch_mod_sensitivity,
+            lfo_amplitude_mod_depth: voice.lfo_amplitude_mod_depth.min(99),
+            lfo_waveform: voice.lfo_waveform,
+            lfo_key_sync: voice.lfo_key_sync,
+            transpose: voice.transpose.min(48),
+        };
+        (preset, notes)
+    }
+}
+
+/// Convert an operator, adding a note for every parameter that could not be
+/// converted exactly.
+fn convert_operator(
+    index: usize,
+    operator: &Tx81zOperator,
+    voice: &Tx81zVoice,
+    notes: &mut Vec<ConversionNote>,
+) -> Operator {
+    // Rates from 0 to 31 and the release rate and levels from 0 to 15
+    let scale =
+        |value: u8, max: u8| ((value.min(max) as u16 * 99 + max as u16 / 2) / max as u16) as u8;
+    let decay_1_level = scale(operator.decay_1_level, 15);
+    let sustain_level = if operator.decay_2_rate == 0 {
+        decay_1_level
+    } else {
+        0
+    };
+    let envelope = Envelope {
+        rates: [
+            scale(operator.attack_rate, 31),
+            scale(operator.decay_1_rate, 31),
+            scale(operator.decay_2_rate, 31),
+            scale(operator.release_rate, 15),
+        ],
+        levels: [99, decay_1_level, sustain_level, 0],
+    };
+
+    if operator.waveform != 0 {
+        notes.push(ConversionNote::Waveform {
+            operator: index,
+            waveform: operator.waveform,
+        });
+    }
+    if operator.level_scaling != 0 {
+        notes.push(ConversionNote::LevelScaling { operator: index });
+    }
+    if operator.eg_bias_sensitivity != 0 {
+        notes.push(ConversionNote::EgBiasSensitivity { operator: index });
+    }
+    if operator.eg_shift != 0 {
+        notes.push(ConversionNote::EgShift {
+            operator: index,
+            shift: operator.eg_shift,
+        });
+    }
+    if operator.frequency_fine != 0 {
+        notes.push(ConversionNote::FrequencyFine {
+            operator: index,
+            fine: operator.frequency_fine,
+        });
+    }
+
+    let (mode, frequency_course, frequency_fine) = if operator.fixed_frequency {
+        notes.push(ConversionNote::FixedFrequency { operator: index });
+        (OperatorMode::Fixed, operator.fixed_range.min(3), 0)
+    } else {
+        let ratio = FREQUENCY_RATIOS[operator.frequency_coarse as usize & 0b111111];
+        let (coarse, fine) = nearest_ratio(ratio);
+        let approximation = dx7_ratio(coarse, fine);
+        if ((approximation - ratio) / ratio).abs() > 0.001 {
+            notes.push(ConversionNote::FrequencyRatio {
+                operator: index,
+                ratio,
+                approximation,
+            });
+        }
+        (OperatorMode::Ratio, coarse, fine)
+    };
+
+    Operator {
+        envelope,
+        scaling_break_point: 0,
+        scaling_left_depth: 0,
+        scaling_right_depth: operator.level_scaling.min(99),
+        scaling_left_curve: ScalingCurve::NegativeLinear,
+        scaling_right_curve: ScalingCurve::NegativeLinear,
+        detune: Detune::new(operator.detune.min(6) as i8 - 3),
+        rate_scaling: operator.rate_scaling * 2,
+        velocity_sensitivity: operator.velocity_sensitivity,
+        modulation_sensitivity: if operator.amplitude_mod_enable {
+            voice.lfo_amplitude_mod_sensitivity
+        } else {
+            0
+        },
+        output_level: operator.output_level.min(99),
+        mode,
+        frequency_course,
+        frequency_fine,
+    }
+}
+
+/// Frequency ratio of a DX7 operator in ratio mode.
+fn dx7_ratio(coarse: u8, fine: u8) -> f32 {
+    crate::operator::ratio(coarse, fine) as f32
+}
+
+/// The DX7 coarse and fine frequency closest to a ratio.
+fn nearest_ratio(ratio: f32) -> (u8, u8) {
+    (0..32)
+        .flat_map(|coarse| (0..100).map(move |fine| (coarse, fine)))
+        .min_by(|&(a_coarse, a_fine), &(b_coarse, b_fine)| {
+            let a = (dx7_ratio(a_coarse, a_fine) - ratio).abs();
+            let b = (dx7_ratio(b_coarse, b_fine) - ratio).abs();
+            a.total_cmp(&b)
+        })
+        .unwrap_or((1, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+
+    use super::*;
+
+    #[test]
+    fn algorithms() {
+        // Carriers of each TX81Z algorithm, counting operators from 0
+        let carriers: [&[OperatorId]; 8] = [
+            &[0],
+            &[0],
+            &[0],
+            &[0],
+            &[0, 2],
+            &[0, 1, 2],
+            &[0, 1, 2],
+            &[0, 1, 2, 3],
+        ];
+        let voices = Tx81zBank::load_file(test_data_path(&["tx81z_bank.syx"])).unwrap();
+        for (tx81z_algorithm, carriers) in carriers.iter().enumerate() {
+            let voice = Tx81zVoice {
+                algorithm: tx81z_algorithm as u8,
+                ..voices[0].clone()
+            };
+            let (preset, _) = Tx81zBank::convert_to_dx7(&voice);
+            assert_eq!(Dx9Voice::ALGORITHMS[tx81z_algorithm], preset.algorithm_id);
+            let algorithm = preset.algorithm();
+            let found: Vec<OperatorId> = (0..TX81Z_OPERATOR_COUNT as OperatorId)
+                .filter(|operator_id| algorithm.is_carrier(operator_id + 2))
+                .collect();
+            assert_eq!(
+                carriers.to_vec(),
+                found,
+                "TX81Z algorithm {}",
+                tx81z_algorithm + 1
+            );
+            assert_eq!(0, preset.operators[0].output_level);
+            assert_eq!(0, preset.operators[1].output_level);
+        }
+    }
+
+    #[test]
+    fn bank() {
+        let voices = Tx81zBank::load_file(test_data_path(&["tx81z_bank.syx"])).unwrap();
+        assert_eq!(32, voices.len());
+
+        let voice = &voices[0];
+        assert_eq!("LATELY BS", voice.name.to_string());
+        assert_eq!(4, voice.algorithm);
+        assert_eq!(7, voice.feedback_level);
+        assert_eq!(99, voice.operators[0].output_level);
+        assert_eq!(4, voice.operators[0].frequency_coarse);
+        assert_eq!(31, voice.operators[0].attack_rate);
+        assert_eq!(5, voice.operators[1].waveform);
+        assert_eq!(0, voice.operators[2].frequency_coarse);
+        assert!(voice.operators[3].fixed_frequency);
+        assert_eq!(Waveform::Triangle, voice.lfo_waveform);
+
+        let (preset, notes) = Tx81zBank::convert_to_dx7(voice);
+        assert_eq!("LATELY BS", preset.name.to_string());
+        assert_eq!(Dx9Voice::ALGORITHMS[4], preset.algorithm_id);
+        let operator = preset.operators[2];
+        assert_eq!(99, operator.output_level);
+        assert_eq!(
+            (OperatorMode::Ratio, 1, 0),
+            (
+                operator.mode,
+                operator.frequency_course,
+                operator.frequency_fine
+            )
+        );
+        assert_eq!([99, 99, 99, 0], operator.envelope.levels);
+        assert_eq!(99, operator.envelope.rates[0]);
+        assert_eq!(0, preset.operators[4].frequency_course); // 0.5
+        assert_eq!(OperatorMode::Fixed, preset.operators[5].mode);
+
+        let notes: Vec<_> = notes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            vec![
+                "Envelopes approximated with four rates and levels",
+                "Operator 2 waveform W6 replaced with a sine wave",
+                "Operator 4 fixed frequency approximated",
+            ],
+            notes
+        );
+
+        // The rest of the bank converts without notes beyond the envelopes
+        for voice in &voices[1..] {
+            let (_, notes) = Tx81zBank::convert_to_dx7(voice);
+            assert_eq!(vec![ConversionNote::Envelopes], notes, "{}", voice.name);
+        }
+    }
+
+    #[test]
+    fn nearest_ratio() {
+        assert_eq!((1, 0), super::nearest_ratio(1.0));
+        assert_eq!((0, 0), super::nearest_ratio(0.5));
+        assert_eq!((1, 41), super::nearest_ratio(1.41));
+        assert_eq!((15, 73), super::nearest_ratio(25.95));
+    }
+
+    #[test]
+    fn not_a_tx81z_bank() {
+        let error = Tx81zBank::load_file(test_data_path(&["rom1a.syx"])).unwrap_err();
+        assert!(matches!(error, Error::IncorrectHeader { .. }));
+    }
+}
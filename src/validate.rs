@@ -0,0 +1,227 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{NameError, Parameter, Preset, PresetName};
+
+/// A problem that would change a preset when it is written.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Violation {
+    /// A parameter is outside of its valid range. Writing clamps it.
+    OutOfRange {
+        parameter: Parameter,
+        value: u8,
+        min: u8,
+        max: u8,
+    },
+
+    /// The name is too long or has characters that can't be stored.
+    /// Writing truncates it and replaces the characters with spaces.
+    Name(NameError),
+}
+
+impl Violation {
+    /// The value an out of range parameter is clamped to.
+    pub fn clamped(&self) -> Option<u8> {
+        match *self {
+            Violation::OutOfRange {
+                value, min, max, ..
+            } => Some(value.clamp(min, max)),
+            Violation::Name(_) => None,
+        }
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::OutOfRange {
+                parameter, value, ..
+            } => parameter.out_of_range(*value).fmt(f),
+            Violation::Name(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Preset {
+    /// List every parameter outside of its valid range and problems with
+    /// the name, without changing the preset. An empty list means the
+    /// preset is written exactly as it is and reading it back in strict
+    /// mode gives the same preset.
+    ///
+    /// Presets are normalized when they are written, which is the same as
+    /// clamping every parameter that is out of range to the value from
+    /// [`Violation::clamped`].
+    ///
+    /// ```
+    /// use synthahol_dx7::{Parameter, Preset, Violation};
+    /// let mut preset = Preset {
+    ///     feedback_level: 9,
+    ///     ..Preset::default()
+    /// };
+    /// assert_eq!(
+    ///     vec![Violation::OutOfRange {
+    ///         parameter: Parameter::Feedback,
+    ///         value: 9,
+    ///         min: 0,
+    ///         max: 7
+    ///     }],
+    ///     preset.validate()
+    /// );
+    /// preset.set(Parameter::Feedback, 7).unwrap();
+    /// assert!(preset.validate().is_empty());
+    /// ```
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations: Vec<Violation> = Parameter::ALL
+            .iter()
+            // The name is checked as a whole
+            .filter(|parameter| !matches!(parameter, Parameter::NameCharacter(_)))
+            .filter_map(|&parameter| {
+                let value = self.get(parameter);
+                if parameter.contains(value) {
+                    return None;
+                }
+                let metadata = parameter.metadata();
+                Some(Violation::OutOfRange {
+                    parameter,
+                    value,
+                    min: metadata.min,
+                    max: metadata.max,
+                })
+            })
+            .collect();
+        if let Err(error) = PresetName::new(&self.name.0) {
+            violations.push(Violation::Name(error));
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+    use crate::*;
+
+    fn out_of_range(parameter: Parameter, value: u8, max: u8) -> Violation {
+        Violation::OutOfRange {
+            parameter,
+            value,
+            min: 0,
+            max,
+        }
+    }
+
+    #[test]
+    fn valid() {
+        assert!(Preset::init().validate().is_empty());
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        for preset in presets {
+            assert!(preset.validate().is_empty(), "{}", preset.name);
+        }
+    }
+
+    #[test]
+    fn globals() {
+        let preset = Preset {
+            algorithm_id: AlgorithmId::from_stored(40),
+            feedback_level: 8,
+            lfo_speed: 100,
+            lfo_pitch_mod_sensitivity: 8,
+            transpose: 49,
+            ..Preset::init()
+        };
+        assert_eq!(
+            vec![
+                out_of_range(Parameter::Algorithm, 40, 31),
+                out_of_range(Parameter::Feedback, 8, 7),
+                out_of_range(Parameter::LfoSpeed, 100, 99),
+                out_of_range(Parameter::LfoPitchModSensitivity, 8, 7),
+                out_of_range(Parameter::Transpose, 49, 48),
+            ],
+            preset.validate()
+        );
+        assert_eq!(
+            "Algorithm is 40, not between 0 and 31",
+            preset.validate()[0].to_string()
+        );
+    }
+
+    #[test]
+    fn operators_and_envelopes() {
+        let mut preset = Preset::init();
+        preset.operators[0].output_level = 120;
+        preset.operators[0].envelope.rates[1] = 100;
+        preset.operators[2].frequency_course = 32;
+        preset.operators[2].modulation_sensitivity = 4;
+        preset.operators[5].envelope.levels[3] = 127;
+        preset.pitch_envelope.levels[0] = 100;
+        assert_eq!(
+            vec![
+                out_of_range(Parameter::EgLevel(5, 3), 127, 99),
+                out_of_range(Parameter::ModulationSensitivity(2), 4, 3),
+                out_of_range(Parameter::FrequencyCoarse(2), 32, 31),
+                out_of_range(Parameter::EgRate(0, 1), 100, 99),
+                out_of_range(Parameter::OutputLevel(0), 120, 99),
+                out_of_range(Parameter::PitchEgLevel(0), 100, 99),
+            ],
+            preset.validate()
+        );
+        assert_eq!(
+            "Operator 1 output level is 120, not between 0 and 99",
+            preset.validate()[4].to_string()
+        );
+    }
+
+    #[test]
+    fn name() {
+        let mut preset = Preset::init();
+        preset.name = PresetName("TAB\tNAME".to_owned());
+        assert_eq!(
+            vec![Violation::Name(NameError::InvalidCharacter {
+                index: 3,
+                character: '\t'
+            })],
+            preset.validate()
+        );
+
+        preset.name = PresetName("MUCH TOO LONG".to_owned());
+        assert_eq!(
+            vec![Violation::Name(NameError::TooLong { length: 13 })],
+            preset.validate()
+        );
+        assert_eq!(None, preset.validate()[0].clamped());
+    }
+
+    #[test]
+    fn normalize_clamps_violations() {
+        let mut preset = Preset::init();
+        preset.operators[1].rate_scaling = 9;
+        preset.operators[3].envelope.rates[0] = 150;
+        preset.operators[4].scaling_break_point = 100;
+        preset.lfo_amplitude_mod_depth = 127;
+        preset.algorithm_id = AlgorithmId::from_stored(127);
+
+        let mut clamped = preset.clone();
+        for violation in preset.validate() {
+            if let Violation::OutOfRange { parameter, .. } = violation {
+                clamped.set_unchecked(parameter, violation.clamped().unwrap());
+            }
+        }
+        assert_eq!(preset.normalize(), clamped);
+        assert!(clamped.validate().is_empty());
+
+        // Normalizing uses the same ranges for every parameter
+        for parameter in Parameter::ALL {
+            if matches!(parameter, Parameter::NameCharacter(_)) {
+                continue;
+            }
+            let mut preset = Preset::init();
+            preset.set_unchecked(parameter, 127);
+            let mut clamped = preset.clone();
+            for violation in preset.validate() {
+                if let Violation::OutOfRange { parameter, .. } = violation {
+                    clamped.set_unchecked(parameter, violation.clamped().unwrap());
+                }
+            }
+            assert_eq!(preset.normalize(), clamped, "{parameter:?}");
+        }
+    }
+}
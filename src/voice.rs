@@ -0,0 +1,307 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::read::{read_exact, read_footer, read_header};
+use crate::*;
+
+/// Header of a single voice dump. The low nibble of the third byte is the
+/// MIDI channel.
+pub(crate) const VOICE_SYSEX_HEADER: [u8; Format::HEADER_LEN] =
+    [0xF0, 0x43, 0x00, 0x00, 0x01, 0x1B];
+
+/// Number of bytes in the unpacked voice layout.
+pub(crate) const UNPACKED_VOICE_LEN: usize = 155;
+
+/// Single voice dumps, also known as VCED, where every parameter is stored
+/// in its own byte.
+///
+/// The operator on/off state is only sent in parameter change messages and
+/// is not part of a voice dump.
+pub struct Voice;
+
+impl Voice {
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Preset, Error> {
+        let input = File::open(&path)?;
+        let mut reader = BufReader::new(input);
+        Self::load(&mut reader)
+    }
+
+    pub fn load<R: Read>(reader: &mut R) -> Result<Preset, Error> {
+        // Header, ignoring the MIDI channel
+        read_header(reader, &VOICE_SYSEX_HEADER)?;
+
+        // Body
+        let mut body = [0; UNPACKED_VOICE_LEN];
+        read_exact(reader, &mut body)?;
+
+        // Body checksum and the end of SysEx marker
+        read_footer(reader, &body)?;
+
+        unpack_preset(&body)
+    }
+
+    pub fn write_file<P: AsRef<Path>>(preset: &Preset, path: P) -> Result<(), Error> {
+        let output = File::create(path)?;
+        let mut writer = BufWriter::new(output);
+        Self::write(preset, &mut writer)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Write a single voice dump. The preset is normalized before being
+    /// written.
+    pub fn write<W: Write>(preset: &Preset, writer: &mut W) -> Result<(), Error> {
+        let body = unpacked_preset(&preset.normalize());
+        writer.write_all(&VOICE_SYSEX_HEADER)?;
+        writer.write_all(&body)?;
+        Ok(writer.write_all(&[checksum(&body), 0xF7])?)
+    }
+}
+
+/// Read a preset from the unpacked voice layout. The preset is normalized.
+fn unpack_preset(unpacked_preset: &[u8; UNPACKED_VOICE_LEN]) -> Result<Preset, Error> {
+    let name = PresetName::from_lossy(&unpacked_preset[145..155]);
+
+    // Operators
+    let mut operators = [Operator::default(); Preset::OPERATOR_COUNT];
+    for (operator_index, unpacked_operator) in unpacked_preset[..126].chunks_exact(21).enumerate() {
+        // Envelope generators
+        let rates = &unpacked_operator[0..4];
+        let levels = &unpacked_operator[4..8];
+        let envelope = Envelope::try_from_rates_and_levels(rates, levels)
+            .map_err(|_| Error::InvalidEnvelope)?;
+
+        let scaling_curve =
+            |curve| ScalingCurve::try_from(curve).map_err(|_| Error::InvalidScalingCurve(curve));
+
+        let mode = if unpacked_operator[17] == 0 {
+            OperatorMode::Ratio
+        } else {
+            OperatorMode::Fixed
+        };
+
+        operators[operator_index] = Operator {
+            envelope,
+            scaling_break_point: unpacked_operator[8],
+            scaling_left_depth: unpacked_operator[9],
+            scaling_right_depth: unpacked_operator[10],
+            scaling_left_curve: scaling_curve(unpacked_operator[11])?,
+            scaling_right_curve: scaling_curve(unpacked_operator[12])?,
+            rate_scaling: unpacked_operator[13],
+            modulation_sensitivity: unpacked_operator[14],
+            velocity_sensitivity: unpacked_operator[15],
+            output_level: unpacked_operator[16],
+            mode,
+            frequency_course: unpacked_operator[18],
+            frequency_fine: unpacked_operator[19],
+            detune: Detune::from_stored(unpacked_operator[20]),
+        };
+    }
+    operators.reverse(); // Stored last-operator-first in the file
+
+    // Pitch envelope generators
+    let pitch_envelope =
+        Envelope::try_from_rates_and_levels(&unpacked_preset[126..130], &unpacked_preset[130..134])
+            .map_err(|_| Error::InvalidEnvelope)?;
+
+    let lfo_waveform = unpacked_preset[142];
+    let lfo_waveform =
+        Waveform::try_from(lfo_waveform).map_err(|_| Error::InvalidWaveform(lfo_waveform))?;
+
+    let preset = Preset {
+        name,
+        operators,
+        pitch_envelope,
+        algorithm_id: AlgorithmId::from_stored(unpacked_preset[134]),
+        feedback_level: unpacked_preset[135],
+        oscillator_key_sync: unpacked_preset[136] != 0,
+        lfo_speed: unpacked_preset[137],
+        lfo_delay: unpacked_preset[138],
+        lfo_pitch_mod_depth: unpacked_preset[139],
+        lfo_amplitude_mod_depth: unpacked_preset[140],
+        lfo_key_sync: unpacked_preset[141] != 0,
+        lfo_waveform,
+        lfo_pitch_mod_sensitivity: unpacked_preset[143],
+        transpose: unpacked_preset[144],
+    }
+    .normalize();
+    Ok(preset)
+}
+
+/// Write a preset in the unpacked voice layout.
+fn unpacked_preset(preset: &Preset) -> [u8; UNPACKED_VOICE_LEN] {
+    let mut unpacked = [0; UNPACKED_VOICE_LEN];
+
+    // Stored last-operator-first in the file
+    for (unpacked_operator, operator) in unpacked[..126]
+        .chunks_exact_mut(21)
+        .zip(preset.operators.iter().rev())
+    {
+        unpacked_operator[0..4].copy_from_slice(&operator.envelope.rates);
+        unpacked_operator[4..8].copy_from_slice(&operator.envelope.levels);
+        unpacked_operator[8] = operator.scaling_break_point;
+        unpacked_operator[9] = operator.scaling_left_depth;
+        unpacked_operator[10] = operator.scaling_right_depth;
+        unpacked_operator[11] = operator.scaling_left_curve.into();
+        unpacked_operator[12] = operator.scaling_right_curve.into();
+        unpacked_operator[13] = operator.rate_scaling;
+        unpacked_operator[14] = operator.modulation_sensitivity;
+        unpacked_operator[15] = operator.velocity_sensitivity;
+        unpacked_operator[16] = operator.output_level;
+        unpacked_operator[17] = match operator.mode {
+            OperatorMode::Ratio => 0,
+            OperatorMode::Fixed => 1,
+        };
+        unpacked_operator[18] = operator.frequency_course;
+        unpacked_operator[19] = operator.frequency_fine;
+        unpacked_operator[20] = operator.detune.stored();
+    }
+
+    // Pitch envelope generators
+    unpacked[126..130].copy_from_slice(&preset.pitch_envelope.rates);
+    unpacked[130..134].copy_from_slice(&preset.pitch_envelope.levels);
+
+    unpacked[134] = preset.algorithm_id.stored();
+    unpacked[135] = preset.feedback_level;
+    unpacked[136] = preset.oscillator_key_sync as u8;
+    unpacked[137] = preset.lfo_speed;
+    unpacked[138] = preset.lfo_delay;
+    unpacked[139] = preset.lfo_pitch_mod_depth;
+    unpacked[140] = preset.lfo_amplitude_mod_depth;
+    unpacked[141] = preset.lfo_key_sync as u8;
+    unpacked[142] = preset.lfo_waveform as u8;
+    unpacked[143] = preset.lfo_pitch_mod_sensitivity;
+    unpacked[144] = preset.transpose;
+    unpacked[145..155].copy_from_slice(&preset.name.to_padded_bytes());
+
+    unpacked
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_data_path;
+
+    use super::*;
+
+    #[test]
+    fn single_voice() {
+        let preset = Voice::load_file(test_data_path(&["tub_bells.syx"])).unwrap();
+        assert_eq!("TUB BELLS", preset.name.to_string());
+        assert_eq!(4, preset.algorithm_id.stored());
+        assert_eq!(7, preset.feedback_level);
+        assert_eq!(Waveform::SawDown, preset.lfo_waveform);
+
+        let op5 = preset.operators[4];
+        assert_eq!(OperatorMode::Fixed, op5.mode);
+        assert_eq!(2, op5.frequency_course);
+        assert_eq!(51, op5.frequency_fine);
+
+        // The same voice is in the factory bank
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"])).unwrap();
+        assert_eq!(presets[25], preset);
+    }
+
+    #[test]
+    fn write_fixture() {
+        let path = test_data_path(&["tub_bells.syx"]);
+        let preset = Voice::load_file(&path).unwrap();
+        let mut buffer = Vec::new();
+        Voice::write(&preset, &mut buffer).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), buffer);
+    }
+
+    #[test]
+    fn init_voice() {
+        #[rustfmt::skip]
+        let operator = [
+            99, 99, 99, 99, 99, 99, 99, 0, // Envelope generator
+            39, 0, 0, 0, 0, // Keyboard level scaling
+            0, 0, 0, // Rate scaling and sensitivities
+            0, // Output level
+            0, 1, 0, 7, // Ratio mode with a coarse frequency of 1 and no detune
+        ];
+        #[rustfmt::skip]
+        let global = [
+            99, 99, 99, 99, 50, 50, 50, 50, // Pitch envelope generator
+            0, 0, 1, // Algorithm 1, feedback and oscillator key sync
+            35, 0, 0, 0, 1, 0, 3, // LFO
+            24, // Transpose
+        ];
+        let mut expected = Vec::new();
+        for operator_index in (0..6).rev() {
+            let mut operator = operator;
+            if operator_index == 0 {
+                operator[16] = 99;
+            }
+            expected.extend(operator);
+        }
+        expected.extend(global);
+        expected.extend(b"INIT VOICE");
+
+        assert_eq!(expected, unpacked_preset(&Preset::default()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let preset = Preset {
+            name: PresetName::from_lossy(b"ROUND"),
+            transpose: 200,
+            ..Default::default()
+        };
+        let mut operators = preset.operators;
+        operators[2].detune = Detune::new(-5);
+        operators[3].detune = Detune::new(6);
+        let preset = Preset {
+            operators,
+            ..preset
+        };
+
+        let mut buffer = Vec::new();
+        Voice::write(&preset, &mut buffer).unwrap();
+        assert_eq!(163, buffer.len());
+        assert_eq!(b"ROUND     ", &buffer[151..161]);
+
+        let read = Voice::load(&mut buffer.as_slice()).unwrap();
+        assert_eq!(preset.normalize(), read);
+        assert_eq!(48, read.transpose);
+    }
+
+    #[test]
+    fn malformed() {
+        let mut contents = std::fs::read(test_data_path(&["tub_bells.syx"])).unwrap();
+        contents[6 + 142] = 9;
+        contents[161] = checksum(&contents[6..161]);
+        let error = Voice::load(&mut contents.as_slice()).unwrap_err();
+        assert!(matches!(error, Error::InvalidWaveform(9)));
+
+        let mut contents = std::fs::read(test_data_path(&["tub_bells.syx"])).unwrap();
+        contents[6 + 11] = 4;
+        contents[161] = checksum(&contents[6..161]);
+        let error = Voice::load(&mut contents.as_slice()).unwrap_err();
+        assert!(matches!(error, Error::InvalidScalingCurve(4)));
+
+        for len in [0, 6, 100, 161, 162] {
+            assert!(Voice::load(&mut &contents[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn byte_count() {
+        let mut contents = std::fs::read(test_data_path(&["tub_bells.syx"])).unwrap();
+        contents[5] = 0x1C;
+        let error = Voice::load(&mut contents.as_slice()).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::IncorrectByteCount {
+                expected: 155,
+                declared: 156
+            }
+        ));
+    }
+
+    #[test]
+    fn bank_is_not_a_voice() {
+        let error = Voice::load_file(test_data_path(&["rom1a.syx"])).unwrap_err();
+        assert!(matches!(error, Error::IncorrectHeader { .. }));
+    }
+}
@@ -0,0 +1,269 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::*;
+
+impl Preset {
+    /// Pack the preset into the 128 byte voice layout used by banks. The
+    /// preset is not normalized first so out of range values may be
+    /// truncated.
+    pub fn to_packed(&self) -> [u8; Format::PACKED_VOICE_LEN] {
+        let mut packed = [0; Format::PACKED_VOICE_LEN];
+
+        // Stored last-operator-first in the file
+        for (operator_index, operator) in self.operators.iter().rev().enumerate() {
+            let packed_operator = &mut packed[(operator_index * 17)..(operator_index + 1) * 17];
+
+            // Envelope generators
+            packed_operator[0..4].copy_from_slice(&operator.envelope.rates);
+            packed_operator[4..8].copy_from_slice(&operator.envelope.levels);
+
+            packed_operator[8] = operator.scaling_break_point;
+            packed_operator[9] = operator.scaling_left_depth;
+            packed_operator[10] = operator.scaling_right_depth;
+            packed_operator[11] =
+                (operator.scaling_right_curve as u8) << 2 | operator.scaling_left_curve as u8;
+
+            // -7 to 7 stored as 0-14 in the preset
+            let detune = operator.detune.stored();
+            packed_operator[12] = detune << 3 | operator.rate_scaling & 0b0000111;
+
+            packed_operator[13] = (operator.velocity_sensitivity & 0b111) << 2
+                | operator.modulation_sensitivity & 0b0000011;
+            packed_operator[14] = operator.output_level;
+
+            let mode = match operator.mode {
+                OperatorMode::Ratio => 0,
+                OperatorMode::Fixed => 1,
+            };
+            packed_operator[15] = (operator.frequency_course & 0b11111) << 1 | mode;
+            packed_operator[16] = operator.frequency_fine;
+        }
+
+        // Pitch envelope generators
+        packed[102..106].copy_from_slice(&self.pitch_envelope.rates);
+        packed[106..110].copy_from_slice(&self.pitch_envelope.levels);
+
+        packed[110] = self.algorithm_id.stored() & 0b0011111;
+        packed[111] = (self.oscillator_key_sync as u8) << 3 | self.feedback_level & 0b0000111;
+        packed[112] = self.lfo_speed;
+        packed[113] = self.lfo_delay;
+        packed[114] = self.lfo_pitch_mod_depth;
+        packed[115] = self.lfo_amplitude_mod_depth;
+        packed[116] = (self.lfo_pitch_mod_sensitivity & 0b111) << 4
+            | (self.lfo_waveform as u8) << 1
+            | self.lfo_key_sync as u8;
+        packed[117] = self.transpose;
+
+        packed[118..128].copy_from_slice(&self.name.to_padded_bytes());
+
+        packed
+    }
+}
+
+/// Options for writing banks.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct WriteOptions {
+    /// MIDI channel of the device the bank is for, from 0 to 15.
+    pub channel: u8,
+
+    /// Preset used for slots that are not supplied.
+    pub filler: Preset,
+}
+
+impl Bank {
+    /// The number of presets in a bank.
+    pub const PRESET_COUNT: usize = 32;
+
+    /// Write up to [`Bank::PRESET_COUNT`] presets as a bank to a file. See
+    /// [`Bank::write`].
+    pub fn write_file<P: AsRef<Path>>(presets: &[Preset], path: P) -> Result<(), Error> {
+        // Checked before the file is created so an existing file is kept
+        Self::check_preset_count(presets)?;
+        let output = File::create(path)?;
+        let mut writer = BufWriter::new(output);
+        Self::write(presets, &mut writer)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Write up to [`Bank::PRESET_COUNT`] presets as a bank. Presets are
+    /// normalized before being written. The DX7 only accepts full banks so
+    /// any remaining slots are filled with the default preset.
+    pub fn write<W: Write>(presets: &[Preset], writer: &mut W) -> Result<(), Error> {
+        Self::write_with(presets, &WriteOptions::default(), writer)
+    }
+
+    /// Write up to [`Bank::PRESET_COUNT`] presets as a bank, filling any
+    /// remaining slots with `filler`.
+    pub fn write_padded<W: Write>(
+        presets: &[Preset],
+        filler: &Preset,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        let options = WriteOptions {
+            filler: filler.clone(),
+            ..Default::default()
+        };
+        Self::write_with(presets, &options, writer)
+    }
+
+    /// Write up to [`Bank::PRESET_COUNT`] presets as a bank.
+    pub fn write_with<W: Write>(
+        presets: &[Preset],
+        options: &WriteOptions,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        Self::check_preset_count(presets)?;
+        if options.channel > 0x0F {
+            return Err(Error::InvalidChannel(options.channel));
+        }
+
+        let mut header = SYSEX_HEADER;
+        header[2] |= options.channel;
+        writer.write_all(&header)?;
+
+        // The checksum is accumulated as each preset is written.
+        let mut checksum = Checksum::new();
+        let packed_filler = options.filler.normalize().to_packed();
+        for slot in 0..Self::PRESET_COUNT {
+            let packed_preset = presets
+                .get(slot)
+                .map(|preset| preset.normalize().to_packed())
+                .unwrap_or(packed_filler);
+            checksum.update(&packed_preset);
+            writer.write_all(&packed_preset)?;
+        }
+
+        Ok(writer.write_all(&[checksum.value(), 0xF7])?)
+    }
+
+    fn check_preset_count(presets: &[Preset]) -> Result<(), Error> {
+        if presets.len() > Self::PRESET_COUNT {
+            return Err(Error::TooManyPresets {
+                count: presets.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{read, remove_file};
+
+    use crate::tests::test_data_path;
+
+    use super::*;
+
+    #[test]
+    fn round_trip_factory_bank() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"]))
+            .unwrap()
+            .presets;
+
+        let path = temp_dir().join("synthahol-dx7-round-trip.syx");
+        Bank::write_file(&presets, &path).unwrap();
+        let contents = read(&path).unwrap();
+        let reread = Bank::load_file(&path).unwrap().presets;
+        remove_file(&path).unwrap();
+
+        assert_eq!(4104, contents.len());
+        assert_eq!(presets, reread);
+    }
+
+    #[test]
+    fn detune_round_trip() {
+        for detune in -7..=7 {
+            let mut preset = Preset::default();
+            preset.operators[1].detune = Detune::new(detune);
+            let packed = preset.to_packed();
+            assert_eq!((detune + 7) as u8, packed[4 * 17 + 12] >> 3);
+            let unpacked = Preset::from_packed(&packed).unwrap();
+            assert_eq!(detune, unpacked.operators[1].detune.value());
+        }
+    }
+
+    #[test]
+    fn wrong_preset_count() {
+        let presets = vec![Preset::default(); Bank::PRESET_COUNT + 1];
+        let mut buffer = Vec::new();
+        let error = Bank::write(&presets, &mut buffer).unwrap_err();
+        assert!(matches!(error, Error::TooManyPresets { count: 33 }));
+        assert!(buffer.is_empty());
+
+        let path = temp_dir().join("synthahol-dx7-wrong-count.syx");
+        let _ = remove_file(&path);
+        let error = Bank::write_file(&presets, &path).unwrap_err();
+        assert!(matches!(error, Error::TooManyPresets { count: 33 }));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn padded() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"]))
+            .unwrap()
+            .presets;
+
+        let mut buffer = Vec::new();
+        Bank::write(&presets[..1], &mut buffer).unwrap();
+        assert_eq!(4104, buffer.len());
+        let reread = Bank::load(&mut buffer.as_slice(), None).unwrap().presets;
+        assert_eq!(Bank::PRESET_COUNT, reread.len());
+        assert_eq!(presets[0], reread[0]);
+        assert!(reread[1..]
+            .iter()
+            .all(|preset| preset == &Preset::default()));
+
+        let filler = &presets[5];
+        let mut buffer = Vec::new();
+        Bank::write_padded(&presets[..1], filler, &mut buffer).unwrap();
+        assert_eq!(4104, buffer.len());
+        let reread = Bank::load(&mut buffer.as_slice(), None).unwrap().presets;
+        assert_eq!(presets[0], reread[0]);
+        assert!(reread[1..].iter().all(|preset| preset == filler));
+
+        let mut buffer = Vec::new();
+        Bank::write(&[], &mut buffer).unwrap();
+        assert_eq!(4104, buffer.len());
+    }
+
+    #[test]
+    fn channel() {
+        let options = WriteOptions {
+            channel: 9,
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        Bank::write_with(&[], &options, &mut buffer).unwrap();
+        assert_eq!(0x09, buffer[2]);
+        assert_eq!(9, Bank::read_dump(&mut buffer.as_slice()).unwrap().channel);
+
+        let options = WriteOptions {
+            channel: 16,
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        let error = Bank::write_with(&[], &options, &mut buffer).unwrap_err();
+        assert!(matches!(error, Error::InvalidChannel(16)));
+    }
+
+    #[test]
+    fn write_to_memory() {
+        let presets = Bank::load_file(test_data_path(&["rom1a.syx"]))
+            .unwrap()
+            .presets;
+
+        let mut buffer = Vec::new();
+        Bank::write(&presets, &mut buffer).unwrap();
+
+        let path = temp_dir().join("synthahol-dx7-write-to-memory.syx");
+        Bank::write_file(&presets, &path).unwrap();
+        let contents = read(&path).unwrap();
+        remove_file(&path).unwrap();
+
+        assert_eq!(contents, buffer);
+        assert_eq!(checksum(&buffer[6..4102]), buffer[4102]);
+    }
+}